@@ -1,50 +1,212 @@
-use alloc::alloc::alloc;
+use alloc::alloc::handle_alloc_error;
+use alloc::boxed::Box;
 use core::alloc::Layout;
-use core::borrow;
-use core::cmp::Ordering;
-use core::convert::From;
 use core::ffi::c_void;
 use core::fmt;
-use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
 use core::mem;
-use core::mem::{ManuallyDrop, MaybeUninit};
+use core::mem::MaybeUninit;
 use core::ops::Deref;
+use core::pin::Pin;
 use core::ptr;
+use core::str::{self, Utf8Error};
 use core::sync::atomic;
 use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
-use erasable::{Erasable, ErasablePtr};
 
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-#[cfg(feature = "slice-dst")]
-use slice_dst::{AllocSliceDst, SliceDst, TryAllocSliceDst};
-#[cfg(feature = "stable_deref_trait")]
-use stable_deref_trait::{CloneStableDeref, StableDeref};
+use serde::Deserialize;
 
-use crate::{abort, ArcBorrow, ArcBox};
+use crate::shared_ptr::impl_shared_ptr;
+
+#[cfg(feature = "header-canary")]
+use crate::canary::Canary;
+use crate::{refcount_overflow, AllocError, Allocator, ArcBorrow, ArcBox, Global};
 
 /// A soft limit on the amount of references that may be made to an `Arc`.
 ///
-/// Going above this limit will abort your program (although not
-/// necessarily) at _exactly_ `MAX_REFCOUNT + 1` references.
-const MAX_REFCOUNT: usize = (isize::MAX) as usize;
+/// Going above this limit will apply the [`OVERFLOW_POLICY`][`crate::OVERFLOW_POLICY`] (although
+/// not necessarily) at _exactly_ `MAX_REFCOUNT + 1` references.
+pub(crate) const MAX_REFCOUNT: usize = (isize::MAX) as usize;
+
+/// The value returned by [`Arc::count`] and friends for an immortal allocation, which is never
+/// freed
+pub const IMMORTAL_COUNT: usize = usize::MAX;
+
+/// The reference count of an immortal [`ArcInner`], such as one declared with
+/// [`static_arc!`][`crate::static_arc`], which is never freed
+///
+/// This lies halfway between [`MAX_REFCOUNT`] and `usize::MAX`, so that no realistic number of
+/// clones and drops can move it out of the immortal range, nor anywhere near zero.
+pub(crate) const IMMORTAL_REFCOUNT: usize = MAX_REFCOUNT + (usize::MAX - MAX_REFCOUNT) / 2;
+
+/// The smallest reference count considered immortal. Counts between [`MAX_REFCOUNT`] and this
+/// value can only be reached by overflowing a mortal count.
+pub(crate) const MIN_IMMORTAL_REFCOUNT: usize = MAX_REFCOUNT + (usize::MAX - MAX_REFCOUNT) / 4;
+
+/// Convert a raw reference count into the value reported by [`Arc::count`], which is
+/// [`IMMORTAL_COUNT`] for immortal allocations
+#[inline]
+pub(crate) fn reported_count(count: usize) -> usize {
+    if count >= MIN_IMMORTAL_REFCOUNT {
+        IMMORTAL_COUNT
+    } else {
+        count
+    }
+}
+
+/// The error type returned by [`Arc::from_utf8`] and [`Rc::from_utf8`][`crate::Rc::from_utf8`]
+/// when the bytes are not valid UTF-8
+///
+/// The original [`Arc<[u8]>`][`Arc`] (or `B`) can be recovered with
+/// [`into_bytes`][`FromUtf8Error::into_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromUtf8Error<B = Arc<[u8]>> {
+    pub(crate) bytes: B,
+    pub(crate) error: Utf8Error,
+}
+
+impl<B: Deref<Target = [u8]>> FromUtf8Error<B> {
+    /// Get the bytes that were attempted to be converted
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Get back the [`Arc<[u8]>`][`Arc`] (or `B`) that was attempted to be converted
+    #[inline]
+    pub fn into_bytes(self) -> B {
+        self.bytes
+    }
+
+    /// Get the underlying [`Utf8Error`]
+    #[inline]
+    pub fn utf8_error(&self) -> Utf8Error {
+        self.error
+    }
+}
+
+impl<B> fmt::Display for FromUtf8Error<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<B: fmt::Debug> std::error::Error for FromUtf8Error<B> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Allocate memory for an [`ArcInner`] containing a `T` with the given layout and reference count
+/// type `C` from `alloc`
+///
+/// # Safety
+/// `layout` must have non-zero size, which always holds for the layout of an [`ArcInner`] since it
+/// contains the reference count
+#[inline]
+#[cfg_attr(
+    not(feature = "header-canary"),
+    allow(clippy::extra_unused_type_parameters)
+)]
+pub(crate) unsafe fn try_allocate<T: ?Sized, A: Allocator, C>(
+    alloc: &A,
+    layout: Layout,
+) -> Result<ptr::NonNull<u8>, AllocError> {
+    debug_assert_ne!(layout.size(), 0);
+    let inner = alloc.allocate(layout)?.cast();
+    #[cfg(feature = "header-canary")]
+    Canary::init::<T, A, C>(inner.as_ptr(), layout);
+    #[cfg(feature = "track-allocations")]
+    crate::debug::register::<T>(inner.as_ptr(), layout.size());
+    Ok(inner)
+}
+
+/// Free the memory of an [`ArcInner`] with the given layout and reference count type `C`, which
+/// was allocated from `alloc`
+///
+/// # Safety
+/// `inner` must have been allocated from `alloc` with `layout`, as by [`try_allocate`]
+#[inline]
+#[cfg_attr(
+    not(feature = "header-canary"),
+    allow(clippy::extra_unused_type_parameters)
+)]
+pub(crate) unsafe fn deallocate<A: Allocator, C>(alloc: A, inner: *mut u8, layout: Layout) {
+    #[cfg(feature = "track-allocations")]
+    crate::debug::unregister(inner);
+    #[cfg(feature = "header-canary")]
+    Canary::poison::<A, C>(inner);
+    alloc.deallocate(ptr::NonNull::new_unchecked(inner), layout)
+}
+
+/// Free an [`ArcInner`] without dropping its data, moving its allocator out of it first
+///
+/// # Safety
+/// `inner` must point to the start of a live [`ArcInner`] with allocator type `A`, reference count
+/// type `C` and layout `layout`, which must not be used afterwards
+#[inline]
+#[cfg_attr(
+    not(feature = "header-canary"),
+    allow(clippy::extra_unused_type_parameters)
+)]
+pub(crate) unsafe fn free_inner<A: Allocator, C>(inner: *mut A, layout: Layout) {
+    let alloc = ptr::read(inner);
+    deallocate::<A, C>(alloc, inner as *mut u8, layout)
+}
+
+/// Replace the address of a (potentially wide) pointer, keeping its metadata but taking the
+/// provenance of `addr`
+///
+/// # Safety
+/// The metadata of `ptr` must be valid for a value located at `addr`
+#[inline]
+pub(crate) unsafe fn with_data_ptr<T: ?Sized>(mut ptr: *mut T, addr: *mut u8) -> *mut T {
+    // The data pointer is stored first in every wide pointer, so we overwrite it in place.
+    (&mut ptr as *mut *mut T as *mut *mut u8).write(addr);
+    debug_assert_eq!(ptr as *mut u8, addr);
+    ptr
+}
+
+/// Allocate memory for an [`ArcInner`] containing a `T` with the given layout and reference count
+/// type `C` from `alloc`, calling [`handle_alloc_error`] on failure
+///
+/// # Safety
+/// `layout` must have non-zero size, as for [`try_allocate`]
+#[inline]
+pub(crate) unsafe fn allocate<T: ?Sized, A: Allocator, C>(
+    alloc: &A,
+    layout: Layout,
+) -> ptr::NonNull<u8> {
+    match try_allocate::<T, A, C>(alloc, layout) {
+        Ok(p) => p,
+        Err(AllocError) => handle_alloc_error(layout),
+    }
+}
 
 /// The object allocated by an Arc<T>
+///
+/// The allocator is stored first, so that the reference count always immediately precedes the data
+/// (up to padding, and the canary with the `header-canary` feature) and the data is always at least
+/// as aligned as the reference count.
+///
+/// The reference count is an [`AtomicUsize`][`atomic::AtomicUsize`] for an [`Arc`], and a
+/// [`Cell<usize>`][`core::cell::Cell`] for an [`Rc`][`crate::Rc`]; since these have the same
+/// layout, an `ArcInner` can switch between the two while it is uniquely owned.
 #[repr(C)]
-pub struct ArcInner<T: ?Sized> {
-    pub(crate) count: atomic::AtomicUsize,
+pub struct ArcInner<T: ?Sized, A = Global, C = ArcCount> {
+    pub(crate) alloc: A,
+    pub(crate) count: C,
+    #[cfg(feature = "header-canary")]
+    pub(crate) canary: Canary,
     pub(crate) data: T,
 }
 
-impl<T> ArcInner<T> {
+impl<T, A, C> ArcInner<T, A, C> {
     /// Get the offset of the data pointer from the beginning of the inner pointer
     #[inline]
     pub fn data_offset() -> usize {
-        Layout::new::<atomic::AtomicUsize>()
-            .extend(Layout::new::<T>())
-            .unwrap()
-            .1
+        Self::layout_with(Layout::new::<T>()).unwrap().1
     }
 
     /// Given the inner pointer, get a data pointer
@@ -52,7 +214,7 @@ impl<T> ArcInner<T> {
     /// # Safety
     /// This must be a pointer to a (potentially uninitialized) `ArcInner`
     #[inline]
-    pub unsafe fn data_ptr(this: *mut ArcInner<T>) -> *mut T {
+    pub unsafe fn data_ptr(this: *mut ArcInner<T, A, C>) -> *mut T {
         let ptr = (this as *mut u8).add(Self::data_offset()) as *mut _;
         debug_assert_eq!(ArcInner::from_data(ptr), this);
         ptr
@@ -64,19 +226,85 @@ impl<T> ArcInner<T> {
     /// This must be a pointer to the `data` field of a (potentially uninitialized) `ArcInner` with
     /// pointer provenance consisting of the entire `ArcInner`
     #[inline]
-    pub unsafe fn from_data(data: *mut T) -> *mut ArcInner<T> {
+    pub unsafe fn from_data(data: *mut T) -> *mut ArcInner<T, A, C> {
         (data as *mut u8).sub(Self::data_offset()) as *mut _
     }
 }
 
-impl<T: ?Sized> ArcInner<T> {
-    /// Get the layout of an `ArcInner<T>` given the data, along with the data offset
+impl<T> ArcInner<T> {
+    /// Fails to compile if there is padding between the header and the data, which a constant
+    /// initializer leaves uninitialized, but which the canary check may read. The header is a
+    /// whole number of words, so this is the case exactly when `T` is aligned to more than a word.
+    #[cfg(feature = "header-canary")]
+    const NO_PADDING: () = assert!(
+        mem::align_of::<T>() <= mem::align_of::<ArcCount>(),
+        "with the `header-canary` feature, a static `ArcInner` cannot hold a type aligned to more \
+         than its header"
+    );
+
+    /// Construct an immortal `ArcInner`, for use in a `static`
+    ///
+    /// An immortal `ArcInner` has a sentinel reference count which is never decremented to zero, so
+    /// its data is never dropped or freed. [`Arc::from_static`] and [`ArcBorrow::from_static`] can
+    /// then be used to hand out pointers to it without any allocation. See
+    /// [`static_arc!`][`crate::static_arc`].
+    ///
+    /// With the `header-canary` feature, this fails to compile if `T` is aligned to more than the
+    /// header, i.e. the reference count and canary.
+    #[cfg(not(loom))]
+    #[inline]
+    pub const fn new_static(data: T) -> Self {
+        #[cfg(feature = "header-canary")]
+        let () = Self::NO_PADDING;
+        ArcInner {
+            alloc: Global,
+            count: ArcCount::new(IMMORTAL_REFCOUNT),
+            #[cfg(feature = "header-canary")]
+            canary: Canary::new(Layout::new::<Self>(), Canary::ANY_TYPE),
+            data,
+        }
+    }
+
+    /// Construct an immortal `ArcInner`
+    // loom's atomics cannot be created in a `const fn`, so this cannot be used in a `static`
+    #[cfg(loom)]
+    pub fn new_static(data: T) -> Self {
+        #[cfg(feature = "header-canary")]
+        let () = Self::NO_PADDING;
+        ArcInner {
+            alloc: Global,
+            count: ArcCount::new(IMMORTAL_REFCOUNT),
+            #[cfg(feature = "header-canary")]
+            canary: Canary::new(Layout::new::<Self>(), Canary::ANY_TYPE),
+            data,
+        }
+    }
+}
+
+impl<T: ?Sized, A, C> ArcInner<T, A, C> {
+    /// Get the layout of an `ArcInner<T, A, C>` given the data, along with the data offset
     #[inline]
     pub fn layout(data: &T) -> (Layout, usize) {
-        let (unpadded_layout, data_offset) = Layout::new::<atomic::AtomicUsize>()
-            .extend(Layout::for_value(data))
-            .unwrap();
-        (unpadded_layout.pad_to_align(), data_offset)
+        Self::layout_with(Layout::for_value(data)).unwrap()
+    }
+
+    /// Get the layout of an `ArcInner<T, A, C>` given the layout of the data, along with the data
+    /// offset, or `None` on overflow
+    #[inline]
+    pub(crate) fn layout_with(data: Layout) -> Option<(Layout, usize)> {
+        let (header, _) = Self::header_layout();
+        let (unpadded_layout, data_offset) = header.extend(data).ok()?;
+        Some((unpadded_layout.pad_to_align(), data_offset))
+    }
+
+    /// Get the layout of the allocator, reference count and canary (if enabled) preceding the
+    /// data, along with the offset of the reference count
+    #[inline]
+    pub(crate) fn header_layout() -> (Layout, usize) {
+        let (header, count_offset) = Layout::new::<A>().extend(Layout::new::<C>()).unwrap();
+        #[cfg(feature = "header-canary")]
+        let header = header.extend(Layout::new::<Canary>()).unwrap().0;
+        (header, count_offset)
     }
 
     /// Get the offset of the data pointer from the beginning of the inner pointer, given the data
@@ -91,47 +319,298 @@ impl<T: ?Sized> ArcInner<T> {
     /// This must be a pointer to the `data` field of an initialized `ArcInner` with pointer
     /// provenance consisting of the entire `ArcInner`
     #[inline]
-    pub unsafe fn count_ptr(data: *mut T) -> *mut atomic::AtomicUsize {
+    pub unsafe fn count_ptr(data: *mut T) -> *mut C {
+        (Self::alloc_ptr(data) as *mut u8).add(Self::header_layout().1) as *mut _
+    }
+
+    /// Given a data pointer, get the allocator pointer, which is also the start of the allocation
+    ///
+    /// # Safety
+    /// This must be a pointer to the `data` field of an initialized `ArcInner` with pointer
+    /// provenance consisting of the entire `ArcInner`
+    #[inline]
+    pub unsafe fn alloc_ptr(data: *mut T) -> *mut A {
         (data as *mut u8).sub(Self::data_offset_value(&*data)) as *mut _
     }
+
+    /// Initialize the allocator and reference count of a freshly allocated `ArcInner`
+    ///
+    /// # Safety
+    /// `inner` must point to the start of an allocation with the layout of an `ArcInner<T, A, C>`
+    #[inline]
+    pub(crate) unsafe fn init_header(inner: ptr::NonNull<u8>, alloc: A, count: C) {
+        let inner = inner.as_ptr();
+        (inner as *mut A).write(alloc);
+        (inner.add(Self::header_layout().1) as *mut C).write(count);
+    }
 }
 
-unsafe impl<T: ?Sized + Sync + Send> Send for ArcInner<T> {}
-unsafe impl<T: ?Sized + Sync + Send> Sync for ArcInner<T> {}
+impl<T: ?Sized, C> ArcInner<T, Global, C> {
+    /// Move a boxed value into a new `ArcInner` with the given reference count, freeing the
+    /// original [`Box`], and return a pointer to its data
+    pub(crate) fn from_box(b: Box<T>, count: C) -> ptr::NonNull<T> {
+        let (layout, offset) = Self::layout(&*b);
+        unsafe {
+            let inner = allocate::<T, _, C>(&Global, layout);
+            let raw = Box::into_raw(b);
+            let value_layout = Layout::for_value(&*raw);
+            // Safety: `inner` is a fresh allocation with the layout of an `ArcInner` containing the
+            // value, which we move out of the box bitwise before freeing it without dropping it.
+            ptr::copy_nonoverlapping(
+                raw as *const u8,
+                inner.as_ptr().add(offset),
+                value_layout.size(),
+            );
+            Self::init_header(inner, Global, count);
+            if value_layout.size() != 0 {
+                alloc::alloc::dealloc(raw as *mut u8, value_layout);
+            }
+            ptr::NonNull::new_unchecked(with_data_ptr(raw, inner.as_ptr().add(offset)))
+        }
+    }
+}
+
+/// The reference count of an [`ArcInner`]
+pub(crate) trait Count {
+    /// The count of a freshly allocated [`ArcInner`], which has a single owner
+    fn one() -> Self;
+}
+
+/// The reference count of an [`Arc`]
+///
+/// Under `loom`, this is loom's atomic rather than the standard one, so that the model checks of
+/// [`AtomicArc`][`crate::AtomicArc`] cover the reference count as well as the cell.
+#[cfg(not(loom))]
+pub(crate) type ArcCount = atomic::AtomicUsize;
+#[cfg(loom)]
+pub(crate) type ArcCount = loom::sync::atomic::AtomicUsize;
+
+impl Count for ArcCount {
+    #[inline]
+    fn one() -> Self {
+        ArcCount::new(1)
+    }
+}
+
+// `Count` is crate-private, so it bounds the methods rather than the (public) impls
+impl<T, A: Allocator, C> ArcInner<T, A, C> {
+    /// Move `data` into a new `ArcInner` with a reference count of one, allocated from `alloc`,
+    /// and return a pointer to its data
+    #[inline]
+    pub(crate) fn new_in(data: T, alloc: A) -> ptr::NonNull<T>
+    where
+        C: Count,
+    {
+        let (layout, _offset) = Self::layout(&data);
+        unsafe { Self::init(allocate::<T, _, C>(&alloc, layout).cast(), data, alloc) }
+    }
+
+    /// Move `data` into a new `ArcInner` with a reference count of one, allocated from `alloc`,
+    /// and return a pointer to its data, or an error if the allocation fails
+    #[inline]
+    pub(crate) fn try_new_in(data: T, alloc: A) -> Result<ptr::NonNull<T>, AllocError>
+    where
+        C: Count,
+    {
+        let (layout, _offset) = Self::layout(&data);
+        unsafe {
+            Ok(Self::init(
+                try_allocate::<T, _, C>(&alloc, layout)?.cast(),
+                data,
+                alloc,
+            ))
+        }
+    }
+
+    /// Initialize a freshly allocated `ArcInner` with a reference count of one
+    ///
+    /// # Safety
+    /// `p` must point to an uninitialized allocation with the layout of an `ArcInner<T, A, C>`,
+    /// allocated from `alloc` by [`try_allocate`]
+    #[inline]
+    unsafe fn init(p: ptr::NonNull<Self>, data: T, alloc: A) -> ptr::NonNull<T>
+    where
+        C: Count,
+    {
+        // Write the fields one by one, leaving the canary and padding written by `try_allocate`
+        let inner = p.as_ptr();
+        ptr::addr_of_mut!((*inner).alloc).write(alloc);
+        ptr::addr_of_mut!((*inner).count).write(C::one());
+        ptr::addr_of_mut!((*inner).data).write(data);
+        ptr::NonNull::new_unchecked(Self::data_ptr(p.as_ptr()))
+    }
+}
+
+impl<T, A: Allocator, C> ArcInner<[MaybeUninit<T>], A, C> {
+    /// Allocate a new `ArcInner` containing `len` uninitialized elements with a reference count of
+    /// one from `alloc`, and return a pointer to its data
+    pub(crate) fn new_uninit_slice_in(len: usize, alloc: A) -> ptr::NonNull<[MaybeUninit<T>]>
+    where
+        C: Count,
+    {
+        let (layout, offset) = Self::slice_layout(len).expect("capacity overflow");
+        unsafe { Self::init_slice(allocate::<[T], _, C>(&alloc, layout), offset, len, alloc) }
+    }
+
+    /// Allocate a new `ArcInner` containing `len` uninitialized elements with a reference count of
+    /// one from `alloc`, and return a pointer to its data, or an error if the allocation fails or
+    /// the size of the allocation would overflow
+    pub(crate) fn try_new_uninit_slice_in(
+        len: usize,
+        alloc: A,
+    ) -> Result<ptr::NonNull<[MaybeUninit<T>]>, AllocError>
+    where
+        C: Count,
+    {
+        let (layout, offset) = Self::slice_layout(len).ok_or(AllocError)?;
+        unsafe {
+            Ok(Self::init_slice(
+                try_allocate::<[T], _, C>(&alloc, layout)?,
+                offset,
+                len,
+                alloc,
+            ))
+        }
+    }
+
+    /// Get the layout of an `ArcInner<[MaybeUninit<T>], A, C>` of length `len`, along with the
+    /// data offset, or `None` on overflow
+    #[inline]
+    fn slice_layout(len: usize) -> Option<(Layout, usize)> {
+        // layout should work as expected since ArcInner uses C representation.
+        Self::layout_with(Layout::array::<MaybeUninit<T>>(len).ok()?)
+    }
+
+    /// Initialize the header of a freshly allocated `ArcInner<[MaybeUninit<T>], A, C>`
+    ///
+    /// # Safety
+    /// `ptr` must point to an allocation from `alloc` with the layout returned by
+    /// `slice_layout(len)`, and `offset` must be the corresponding data offset
+    #[inline]
+    unsafe fn init_slice(
+        ptr: ptr::NonNull<u8>,
+        offset: usize,
+        len: usize,
+        alloc: A,
+    ) -> ptr::NonNull<[MaybeUninit<T>]>
+    where
+        C: Count,
+    {
+        Self::init_header(ptr, alloc, C::one());
+        let slice =
+            ptr::slice_from_raw_parts_mut(ptr.as_ptr().add(offset) as *mut MaybeUninit<T>, len);
+        ptr::NonNull::new_unchecked(slice)
+    }
+}
+
+impl<T: ?Sized, A: Allocator, C> ArcInner<T, A, C> {
+    /// Given a data pointer, get the layout of the `ArcInner` along with a pointer to its start
+    ///
+    /// This must be called before the data is dropped or moved out, since the layout is computed
+    /// from it.
+    ///
+    /// # Safety
+    /// This must be a pointer to the `data` field of an initialized `ArcInner` with pointer
+    /// provenance consisting of the entire `ArcInner`
+    #[inline]
+    pub(crate) unsafe fn raw_layout(data: *mut T) -> (Layout, *mut A) {
+        let (layout, offset) = Self::layout(&*data);
+        (layout, (data as *mut u8).sub(offset) as *mut A)
+    }
+
+    /// Drop the data of an `ArcInner` and free it. This is the non-inlined part of dropping the
+    /// last pointer to it.
+    ///
+    /// # Safety
+    /// This must be a pointer to the `data` field of an initialized `ArcInner`, as for
+    /// [`raw_layout`][`ArcInner::raw_layout`], to which there are no other references. It must
+    /// not be used afterwards.
+    #[inline(never)]
+    pub(crate) unsafe fn drop_slow(data: *mut T) {
+        let (layout, inner) = Self::raw_layout(data);
+        ptr::drop_in_place(data);
+        free_inner::<_, C>(inner, layout)
+    }
+}
+
+unsafe impl<T: ?Sized + Sync + Send, A: Sync + Send, C: Sync + Send> Send for ArcInner<T, A, C> {}
+unsafe impl<T: ?Sized + Sync + Send, A: Sync + Send, C: Sync + Send> Sync for ArcInner<T, A, C> {}
 
 /// An atomically reference counted shared pointer
 ///
 /// See the documentation for [`Arc`][aa] in the standard library. Unlike the
 /// standard library [`Arc`][aa], this [`Arc`] does not support weak reference counting.
 ///
+/// The allocator `A` is stored in the allocation alongside the reference count, so an [`Arc`] is
+/// always a single pointer wide (or two, for unsized `T`), whatever the allocator.
+///
 /// [aa]: https://doc.rust-lang.org/stable/std/sync/struct.Arc.html
 #[repr(transparent)]
-pub struct Arc<T: ?Sized> {
+pub struct Arc<T: ?Sized, A: Allocator = Global> {
     pub(crate) p: ptr::NonNull<T>,
-    phantom: PhantomData<T>,
+    phantom: PhantomData<ArcInner<T, A>>,
 }
 
-unsafe impl<T: ?Sized + Sync + Send> Send for Arc<T> {}
-unsafe impl<T: ?Sized + Sync + Send> Sync for Arc<T> {}
+unsafe impl<T: ?Sized + Sync + Send, A: Allocator + Sync + Send> Send for Arc<T, A> {}
+unsafe impl<T: ?Sized + Sync + Send, A: Allocator + Sync + Send> Sync for Arc<T, A> {}
+
+// An `Arc` never moves its data, so it may itself be moved even if the data is pinned
+impl<T: ?Sized, A: Allocator> Unpin for Arc<T, A> {}
 
 impl<T> Arc<T> {
     /// Construct an [`Arc`]
     #[inline]
     pub fn new(data: T) -> Self {
-        let (layout, _offset) = ArcInner::layout(&data);
-        unsafe {
-            let p = alloc::alloc::alloc(layout) as *mut ArcInner<T>;
-            p.write(ArcInner {
-                count: atomic::AtomicUsize::new(1),
-                data,
-            });
-            Arc::from_raw_inner(ptr::NonNull::new_unchecked(p))
+        Arc::new_in(data, Global)
+    }
+
+    /// Construct an [`Arc`], returning an error if the allocation fails
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Arc;
+    ///
+    /// let x = Arc::try_new(3).unwrap();
+    /// assert_eq!(*x, 3);
+    /// ```
+    #[inline]
+    pub fn try_new(data: T) -> Result<Self, AllocError> {
+        Arc::try_new_in(data, Global)
+    }
+}
+
+impl<T, A: Allocator> Arc<T, A> {
+    /// Construct an [`Arc`] in the given allocator
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Arc, Global};
+    ///
+    /// let x = Arc::new_in(3, Global);
+    /// assert_eq!(*x, 3);
+    /// ```
+    #[inline]
+    pub fn new_in(data: T, alloc: A) -> Self {
+        Arc {
+            p: ArcInner::<T, A>::new_in(data, alloc),
+            phantom: PhantomData,
         }
     }
 
+    /// Construct an [`Arc`] in the given allocator, returning an error if the allocation fails
+    #[inline]
+    pub fn try_new_in(data: T, alloc: A) -> Result<Self, AllocError> {
+        Ok(Arc {
+            p: ArcInner::<T, A>::try_new_in(data, alloc)?,
+            phantom: PhantomData,
+        })
+    }
+
     /// Transform an [`Arc`] into an allocated [`ArcInner`].
     #[inline]
-    pub(crate) fn into_raw_inner(self) -> ptr::NonNull<ArcInner<T>> {
+    pub(crate) fn into_raw_inner(self) -> ptr::NonNull<ArcInner<T, A>> {
         let p = self.p.as_ptr();
         core::mem::forget(self);
         unsafe { ptr::NonNull::new_unchecked(ArcInner::from_data(p)) }
@@ -142,7 +621,7 @@ impl<T> Arc<T> {
     /// The `ptr` must point to a valid instance, allocated by an [`Arc`]. The reference count will
     /// not be modified.
     #[inline]
-    pub(crate) unsafe fn from_raw_inner(p: ptr::NonNull<ArcInner<T>>) -> Self {
+    pub(crate) unsafe fn from_raw_inner(p: ptr::NonNull<ArcInner<T, A>>) -> Self {
         Arc {
             p: ptr::NonNull::new_unchecked(ArcInner::data_ptr(p.as_ptr())),
             phantom: PhantomData,
@@ -171,6 +650,53 @@ impl<T> Arc<T> {
     }
 }
 
+impl<T> Arc<T> {
+    /// Construct a new [`Pin<Arc<T>>`][`Pin`]. If `T` does not implement [`Unpin`], then `data`
+    /// will be pinned in memory and unable to be moved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Arc;
+    /// use core::marker::PhantomPinned;
+    ///
+    /// let x = Arc::pin((5, PhantomPinned));
+    /// let y = x.clone();
+    /// assert_eq!(y.0, 5);
+    /// ```
+    #[inline]
+    pub fn pin(data: T) -> Pin<Arc<T>> {
+        ArcBox::pin(data).into()
+    }
+
+    /// Get an [`Arc`] pointing to an immortal, statically allocated [`ArcInner`]
+    ///
+    /// Cloning and dropping the result never frees the [`ArcInner`], so this does not allocate.
+    /// Since it can never be uniquely owned, [`Arc::get_mut`] always returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{static_arc, Arc};
+    ///
+    /// static_arc! {
+    ///     static ANSWER: u32 = 42;
+    /// }
+    ///
+    /// let x = Arc::from_static(&ANSWER);
+    /// let y = x.clone();
+    /// assert_eq!(*y, 42);
+    /// assert!(Arc::ptr_eq(&x, &Arc::from_static(&ANSWER)));
+    /// assert!(!Arc::is_unique(&x));
+    /// ```
+    #[inline]
+    pub fn from_static(inner: &'static ArcInner<T>) -> Self {
+        // Safety: the count of `inner` is immortal, since it was constructed with `new_static`, so
+        // it is never freed. The pointer is derived from a reference to the entire `ArcInner`.
+        unsafe { Arc::from_raw(ptr::addr_of!((*(inner as *const ArcInner<T>)).data)) }
+    }
+}
+
 impl<T: ?Sized> Arc<T> {
     /// Reconstruct the [`Arc<T>`][`Arc`] from a raw pointer obtained from [`into_raw`][`Arc::into_raw`]
     ///
@@ -178,6 +704,23 @@ impl<T: ?Sized> Arc<T> {
     /// The pointer `ptr` must be the result of a call to [`into_raw`][`Arc::into_raw`]
     #[inline]
     pub unsafe fn from_raw(ptr: *const T) -> Self {
+        Arc::from_raw_in(ptr)
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Arc<T, A> {
+    /// Reconstruct the [`Arc<T, A>`][`Arc`] from a raw pointer obtained from
+    /// [`into_raw`][`Arc::into_raw`]
+    ///
+    /// Since the allocator is stored in the allocation itself, it does not need to be passed in.
+    ///
+    /// # Safety
+    /// The pointer `ptr` must be the result of a call to [`into_raw`][`Arc::into_raw`] on an
+    /// [`Arc<T, A>`][`Arc`] with the same allocator type `A`
+    #[inline]
+    pub unsafe fn from_raw_in(ptr: *const T) -> Self {
+        #[cfg(feature = "header-canary")]
+        ArcInner::<T, A>::check_canary(ptr, "Arc::from_raw");
         Arc {
             p: ptr::NonNull::new_unchecked(ptr as *mut T),
             phantom: PhantomData,
@@ -196,16 +739,22 @@ impl<T: ?Sized> Arc<T> {
     ///
     /// Same as into_raw except `self` isn't consumed.
     #[inline]
-    pub fn as_ptr(this: &Arc<T>) -> *const T {
+    pub fn as_ptr(this: &Self) -> *const T {
         this.p.as_ptr()
     }
 
+    /// Get a reference to the allocator this [`Arc`] was allocated with
+    #[inline]
+    pub fn allocator(this: &Self) -> &A {
+        unsafe { &*ArcInner::<T, A>::alloc_ptr(this.p.as_ptr()) }
+    }
+
     /// Produce a pointer to the data that can be converted back
     /// to an Arc. This is basically an [`&Arc<T>`][`Arc`], without the extra indirection.
     /// It has the benefits of an `&T` but also knows about the underlying refcount
     /// and can be converted into more [`Arc<T>`][`Arc`]s if necessary.
     #[inline]
-    pub fn borrow_arc(this: &Self) -> ArcBorrow<'_, T> {
+    pub fn borrow_arc(this: &Self) -> ArcBorrow<'_, T, A> {
         ArcBorrow {
             p: this.p,
             phantom: PhantomData,
@@ -215,14 +764,7 @@ impl<T: ?Sized> Arc<T> {
     /// Returns the address on the heap of the [`Arc`] itself -- not the `T` within it -- for memory
     /// reporting.
     pub fn heap_ptr(&self) -> *const c_void {
-        unsafe { ArcInner::count_ptr(self.p.as_ptr()) as *const c_void }
-    }
-
-    // Non-inlined part of [`drop`][`Arc::drop`]. Just invokes the destructor.
-    #[inline(never)]
-    pub(crate) unsafe fn drop_slow(&mut self) {
-        let (layout, data_offset) = ArcInner::layout(&**self);
-        alloc::alloc::dealloc((self.p.as_ptr() as *mut u8).sub(data_offset), layout)
+        unsafe { ArcInner::<T, A>::alloc_ptr(self.p.as_ptr()) as *const c_void }
     }
 
     /// Test pointer equality between the two [`Arc`]s, i.e. they must be the _same_
@@ -237,7 +779,7 @@ impl<T: ?Sized> Arc<T> {
     /// You can call the [`get`][`ArcBorrow::get`] method on the returned [`ArcBorrow`] to get an `&'static T`.
     /// Note that using this can (obviously) cause memory leaks!
     #[inline]
-    pub fn leak(this: Arc<T>) -> ArcBorrow<'static, T> {
+    pub fn leak(this: Self) -> ArcBorrow<'static, T, A> {
         let result = ArcBorrow {
             p: this.p,
             phantom: PhantomData,
@@ -250,7 +792,27 @@ impl<T: ?Sized> Arc<T> {
 impl<T> Arc<MaybeUninit<T>> {
     /// Create an [`Arc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`].
     pub fn new_uninit() -> Self {
-        Arc::new(MaybeUninit::<T>::uninit())
+        Arc::new_uninit_in(Global)
+    }
+
+    /// Create an [`Arc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`], returning an
+    /// error if the allocation fails
+    pub fn try_new_uninit() -> Result<Self, AllocError> {
+        Arc::try_new_uninit_in(Global)
+    }
+}
+
+impl<T, A: Allocator> Arc<MaybeUninit<T>, A> {
+    /// Create an [`Arc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`] in the given
+    /// allocator
+    pub fn new_uninit_in(alloc: A) -> Self {
+        Arc::new_in(MaybeUninit::<T>::uninit(), alloc)
+    }
+
+    /// Create an [`Arc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`] in the given
+    /// allocator, returning an error if the allocation fails
+    pub fn try_new_uninit_in(alloc: A) -> Result<Self, AllocError> {
+        Arc::try_new_in(MaybeUninit::<T>::uninit(), alloc)
     }
 
     /// Calls `MaybeUninit::write` on the value contained.
@@ -270,40 +832,112 @@ impl<T> Arc<MaybeUninit<T>> {
     ///
     /// Must initialize all fields before calling this function.
     #[inline]
-    pub unsafe fn assume_init(self) -> Arc<T> {
-        Arc::from_raw(Arc::into_raw(self) as *const T)
+    pub unsafe fn assume_init(self) -> Arc<T, A> {
+        #[cfg(feature = "track-allocations")]
+        crate::debug::retype::<T>(self.heap_ptr());
+        Arc::from_raw_in(Arc::into_raw(self) as *const T)
     }
 }
 
 impl<T> Arc<[MaybeUninit<T>]> {
     /// Create an [`Arc`] contains an array `[MaybeUninit<T>]` of `len`.
     pub fn new_uninit_slice(len: usize) -> Self {
-        // layout should work as expected since ArcInner uses C representation.
-        let layout = Layout::new::<atomic::AtomicUsize>();
-        let array_layout = Layout::array::<MaybeUninit<T>>(len).unwrap();
+        Arc::new_uninit_slice_in(len, Global)
+    }
 
-        let (layout, offset) = layout.extend(array_layout).unwrap();
-        let layout = layout.pad_to_align();
+    /// Create an [`Arc`] contains an array `[MaybeUninit<T>]` of `len`, returning an error if the
+    /// allocation fails or the size of the allocation would overflow
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::mem::MaybeUninit;
+    /// use elysees::Arc;
+    ///
+    /// assert!(Arc::<[MaybeUninit<u64>]>::try_new_uninit_slice(5).is_ok());
+    /// assert!(Arc::<[MaybeUninit<u64>]>::try_new_uninit_slice(usize::MAX).is_err());
+    /// ```
+    pub fn try_new_uninit_slice(len: usize) -> Result<Self, AllocError> {
+        Arc::try_new_uninit_slice_in(len, Global)
+    }
+}
 
-        // Allocate and initialize ArcInner
-        unsafe {
-            let ptr = alloc(layout);
-            (ptr as *mut atomic::AtomicUsize).write(atomic::AtomicUsize::new(1));
-            let slice = ptr::slice_from_raw_parts_mut(ptr.add(offset) as *mut MaybeUninit<T>, len);
-            Arc::from_raw(slice)
+impl<T, A: Allocator> Arc<[MaybeUninit<T>], A> {
+    /// Create an [`Arc`] contains an array `[MaybeUninit<T>]` of `len` in the given allocator.
+    pub fn new_uninit_slice_in(len: usize, alloc: A) -> Self {
+        Arc {
+            p: ArcInner::<[MaybeUninit<T>], A>::new_uninit_slice_in(len, alloc),
+            phantom: PhantomData,
         }
     }
 
+    /// Create an [`Arc`] contains an array `[MaybeUninit<T>]` of `len` in the given allocator,
+    /// returning an error if the allocation fails or the size of the allocation would overflow
+    pub fn try_new_uninit_slice_in(len: usize, alloc: A) -> Result<Self, AllocError> {
+        Ok(Arc {
+            p: ArcInner::<[MaybeUninit<T>], A>::try_new_uninit_slice_in(len, alloc)?,
+            phantom: PhantomData,
+        })
+    }
+
     /// # Safety
     ///
     /// Must initialize all fields before calling this function.
     #[inline]
-    pub unsafe fn assume_init(self) -> Arc<[T]> {
-        Arc::from_raw(Arc::into_raw(self) as *const [T])
+    pub unsafe fn assume_init(self) -> Arc<[T], A> {
+        Arc::from_raw_in(Arc::into_raw(self) as *const [T])
+    }
+}
+
+impl Arc<str> {
+    /// Convert an [`Arc<[u8]>`][`Arc`] into an [`Arc<str>`][`Arc`] without copying, if it contains
+    /// valid UTF-8
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Arc;
+    ///
+    /// let bytes: Arc<[u8]> = Arc::from(&b"hello"[..]);
+    /// let s = Arc::from_utf8(bytes.clone()).unwrap();
+    /// assert_eq!(&*s, "hello");
+    /// assert_eq!(Arc::count(&bytes), 2);
+    ///
+    /// let invalid: Arc<[u8]> = Arc::from(&[0xff, 0xfe][..]);
+    /// let err = Arc::from_utf8(invalid).unwrap_err();
+    /// assert_eq!(err.as_bytes(), [0xff, 0xfe]);
+    /// ```
+    #[inline]
+    pub fn from_utf8(bytes: Arc<[u8]>) -> Result<Arc<str>, FromUtf8Error> {
+        match str::from_utf8(&bytes) {
+            // Safety: we have just checked that the bytes are valid UTF-8
+            Ok(_) => Ok(unsafe { Arc::from_utf8_unchecked(bytes) }),
+            Err(error) => Err(FromUtf8Error { bytes, error }),
+        }
+    }
+
+    /// Convert an [`Arc<[u8]>`][`Arc`] into an [`Arc<str>`][`Arc`] without copying or checking
+    /// that it contains valid UTF-8
+    ///
+    /// # Safety
+    /// The bytes must be valid UTF-8, as for [`str::from_utf8_unchecked`]
+    #[inline]
+    pub unsafe fn from_utf8_unchecked(bytes: Arc<[u8]>) -> Arc<str> {
+        #[cfg(feature = "track-allocations")]
+        crate::debug::retype::<str>(bytes.heap_ptr());
+        Arc::from_raw(Arc::into_raw(bytes) as *const str)
+    }
+
+    /// Convert an [`Arc<str>`][`Arc`] into an [`Arc<[u8]>`][`Arc`] without copying
+    #[inline]
+    pub fn into_bytes(this: Self) -> Arc<[u8]> {
+        #[cfg(feature = "track-allocations")]
+        crate::debug::retype::<[u8]>(this.heap_ptr());
+        unsafe { Arc::from_raw(Arc::into_raw(this) as *const [u8]) }
     }
 }
 
-impl<T: ?Sized> Clone for Arc<T> {
+impl<T: ?Sized, A: Allocator> Clone for Arc<T, A> {
     #[inline]
     fn clone(&self) -> Self {
         // Using a relaxed ordering is alright here, as knowledge of the
@@ -317,7 +951,22 @@ impl<T: ?Sized> Clone for Arc<T> {
         // another must already provide any required synchronization.
         //
         // [1]: (www.boost.org/doc/libs/1_55_0/doc/html/atomic/usage_examples.html)
-        let old_size = unsafe { (*ArcInner::count_ptr(self.p.as_ptr())).fetch_add(1, Relaxed) };
+        #[cfg(feature = "header-canary")]
+        unsafe {
+            ArcInner::<T, A>::check_canary(self.p.as_ptr(), "Arc::clone")
+        };
+        let count = unsafe { &*ArcInner::<T, A>::count_ptr(self.p.as_ptr()) };
+
+        // Immortal allocations are never freed, so we skip the write to their
+        // count entirely, avoiding contention on hot shared objects.
+        if count.load(Relaxed) >= MIN_IMMORTAL_REFCOUNT {
+            return Arc {
+                p: self.p,
+                phantom: PhantomData,
+            };
+        }
+
+        let old_size = count.fetch_add(1, Relaxed);
 
         // However we need to guard against massive refcounts in case someone
         // is `mem::forget`ing Arcs. If we don't do this the count can overflow
@@ -326,10 +975,17 @@ impl<T: ?Sized> Clone for Arc<T> {
         // the reference count at once. This branch will never be taken in
         // any realistic program.
         //
-        // We abort because such a program is incredibly degenerate, and we
-        // don't care to support it.
-        if old_size > MAX_REFCOUNT {
-            abort();
+        // We apply the overflow policy, by default aborting, because such a
+        // program is incredibly degenerate, and we don't care to support it.
+        // Saturating makes the allocation immortal, so that the count is
+        // never written to again.
+        //
+        // The count may have been made immortal since we checked it, in
+        // which case it is free to drift.
+        if old_size > MAX_REFCOUNT && old_size < MIN_IMMORTAL_REFCOUNT {
+            count.fetch_sub(1, Relaxed);
+            refcount_overflow();
+            count.store(IMMORTAL_REFCOUNT, Relaxed);
         }
 
         Arc {
@@ -339,16 +995,7 @@ impl<T: ?Sized> Clone for Arc<T> {
     }
 }
 
-impl<T: ?Sized> Deref for Arc<T> {
-    type Target = T;
-
-    #[inline]
-    fn deref(&self) -> &T {
-        unsafe { self.p.as_ref() }
-    }
-}
-
-impl<T: Clone> Arc<T> {
+impl<T: Clone, A: Allocator + Clone> Arc<T, A> {
     /// Makes a mutable reference to the [`Arc`], cloning if necessary
     ///
     /// This is functionally equivalent to [`Arc::make_mut`][mm] from the standard library.
@@ -366,7 +1013,7 @@ impl<T: Clone> Arc<T> {
     pub fn make_mut(this: &mut Self) -> &mut T {
         if !Self::is_unique(this) {
             // Another pointer exists; clone
-            *this = Arc::new((**this).clone());
+            *this = Arc::new_in((**this).clone(), Arc::allocator(this).clone());
         }
         debug_assert!(Self::is_unique(this));
 
@@ -381,7 +1028,7 @@ impl<T: Clone> Arc<T> {
     }
 }
 
-impl<T: ?Sized> Arc<T> {
+impl<T: ?Sized, A: Allocator> Arc<T, A> {
     /// Provides mutable access to the contents _if_ the [`Arc`] is uniquely owned.
     #[inline]
     pub fn get_mut(this: &mut Self) -> Option<&mut T> {
@@ -395,6 +1042,37 @@ impl<T: ?Sized> Arc<T> {
         }
     }
 
+    /// Provides pinned mutable access to the contents of a [`Pin<Arc<T>>`][`Pin`] _if_ the [`Arc`]
+    /// is uniquely owned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Arc;
+    ///
+    /// let mut x = Arc::pin(5);
+    /// *Arc::get_pin_mut(&mut x).unwrap() += 1;
+    /// let y = x.clone();
+    /// assert!(Arc::get_pin_mut(&mut x).is_none());
+    /// assert_eq!(*y, 6);
+    /// ```
+    #[inline]
+    pub fn get_pin_mut(this: &mut Pin<Self>) -> Option<Pin<&mut T>> {
+        // Safety: `Pin` is `repr(transparent)`, and we never move the data out of the `Arc`, only
+        //         ever handing out a pinned reference to it
+        let arc = unsafe { &mut *(this as *mut Pin<Self> as *mut Self) };
+        Arc::get_mut(arc).map(|data| unsafe { Pin::new_unchecked(data) })
+    }
+
+    /// Borrow a [`Pin<Arc<T>>`][`Pin`] as a [`Pin<ArcBorrow<T>>`][`Pin`]. This does *not* bump the
+    /// refcount.
+    #[inline]
+    pub fn borrow_pin(this: &Pin<Self>) -> Pin<ArcBorrow<'_, T, A>> {
+        // Safety: `Pin` is `repr(transparent)`, and the data pointed to by `this` is pinned, which
+        //         an `ArcBorrow` cannot violate since it only provides shared access
+        unsafe { Pin::new_unchecked(Arc::borrow_arc(&*(this as *const Pin<Self> as *const Self))) }
+    }
+
     /// Whether or not the [`Arc`] is uniquely owned (is the refcount 1?).
     #[inline]
     pub fn is_unique(this: &Self) -> bool {
@@ -405,15 +1083,56 @@ impl<T: ?Sized> Arc<T> {
     }
 
     /// Gets the number of [`Arc`] pointers to this allocation
+    ///
+    /// This is [`IMMORTAL_COUNT`] if the allocation is immortal.
     #[inline]
     pub fn count(this: &Self) -> usize {
         Self::load_count(this, atomic::Ordering::Acquire)
     }
 
     /// Gets the number of [`Arc`] pointers to this allocation, with a given load ordering
+    ///
+    /// This is [`IMMORTAL_COUNT`] if the allocation is immortal.
     #[inline]
     pub fn load_count(this: &Self, order: atomic::Ordering) -> usize {
-        unsafe { (*ArcInner::count_ptr(this.p.as_ptr())).load(order) }
+        reported_count(unsafe { (*ArcInner::<T, A>::count_ptr(this.p.as_ptr())).load(order) })
+    }
+
+    /// Whether the allocation behind this [`Arc`] is immortal, i.e. will never be freed
+    ///
+    /// This is the case for [`Arc`]s created with [`Arc::from_static`] or [`Arc::empty`], and
+    /// after a call to [`Arc::make_immortal`].
+    #[inline]
+    pub fn is_immortal(this: &Self) -> bool {
+        Self::count(this) == IMMORTAL_COUNT
+    }
+
+    /// Make the allocation behind this [`Arc`] immortal, so that it is never freed
+    ///
+    /// Afterwards, cloning and dropping any [`Arc`] or [`ArcRef`][`crate::ArcRef`] pointing to it,
+    /// or calling [`ArcBorrow::clone_arc`] on it, no longer writes to its reference count. This
+    /// avoids contention on hot, long-lived shared objects, at the cost of leaking them, much like
+    /// [`Arc::leak`] but without having to give up existing [`Arc`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Arc, IMMORTAL_COUNT};
+    ///
+    /// let x = Arc::new(5);
+    /// let y = x.clone();
+    /// Arc::make_immortal(&x);
+    /// assert!(Arc::is_immortal(&y));
+    /// assert_eq!(Arc::count(&x.clone()), IMMORTAL_COUNT);
+    /// drop(x);
+    /// assert_eq!(*y, 5);
+    /// ```
+    #[inline]
+    pub fn make_immortal(this: &Self) {
+        // Clones and drops racing with this store may already have checked that the count is
+        // mortal, and so will move it slightly away from `IMMORTAL_REFCOUNT`, but never out of
+        // the immortal range. Since `this` holds a reference, the count cannot reach zero first.
+        unsafe { (*ArcInner::<T, A>::count_ptr(this.p.as_ptr())).store(IMMORTAL_REFCOUNT, Relaxed) }
     }
 
     /// Returns an [`ArcBox`] if the [`Arc`] has exactly one strong reference.
@@ -437,7 +1156,7 @@ impl<T: ?Sized> Arc<T> {
     /// );
     /// ```
     #[inline]
-    pub fn try_unique(this: Self) -> Result<ArcBox<T>, Self> {
+    pub fn try_unique(this: Self) -> Result<ArcBox<T, A>, Self> {
         if Self::is_unique(&this) {
             // Safety: The current arc is unique and making a `ArcBox`
             //         from it is sound
@@ -449,24 +1168,36 @@ impl<T: ?Sized> Arc<T> {
 
     /// Convert this [`Arc`] to an [`ArcBox`], cloning the internal data if necessary for uniqueness
     #[inline]
-    pub fn unique(this: Self) -> ArcBox<T>
+    pub fn unique(this: Self) -> ArcBox<T, A>
     where
         T: Clone,
+        A: Clone,
     {
         if Self::is_unique(&this) {
             ArcBox(this)
         } else {
-            ArcBox::new(this.deref().clone())
+            ArcBox::new_in(this.deref().clone(), Arc::allocator(&this).clone())
         }
     }
 }
 
-impl<T: ?Sized> Drop for Arc<T> {
+impl<T: ?Sized, A: Allocator> Drop for Arc<T, A> {
     #[inline]
     fn drop(&mut self) {
+        #[cfg(feature = "header-canary")]
+        unsafe {
+            ArcInner::<T, A>::check_canary(self.p.as_ptr(), "Arc::drop")
+        };
+        let count = unsafe { &*ArcInner::<T, A>::count_ptr(self.p.as_ptr()) };
+
+        // Immortal allocations are never freed; see `clone`.
+        if count.load(Relaxed) >= MIN_IMMORTAL_REFCOUNT {
+            return;
+        }
+
         // Because `fetch_sub` is already atomic, we do not need to synchronize
         // with other threads unless we are going to delete the object.
-        if unsafe { (*ArcInner::count_ptr(self.p.as_ptr())).fetch_sub(1, Release) != 1 } {
+        if count.fetch_sub(1, Release) != 1 {
             return;
         }
 
@@ -490,247 +1221,34 @@ impl<T: ?Sized> Drop for Arc<T> {
         //
         // [1]: (www.boost.org/doc/libs/1_55_0/doc/html/atomic/usage_examples.html)
         // [2]: https://github.com/rust-lang/rust/pull/41714
-        unsafe { (*ArcInner::count_ptr(self.p.as_ptr())).load(Acquire) };
+        count.load(Acquire);
 
         unsafe {
-            self.drop_slow();
+            ArcInner::<T, A>::drop_slow(self.p.as_ptr());
         }
     }
 }
 
-impl<T: ?Sized, U: ?Sized + PartialEq<T>> PartialEq<Arc<T>> for Arc<U> {
-    #[inline]
-    fn eq(&self, other: &Arc<T>) -> bool {
-        *(*self) == *(*other)
-    }
-
-    #[allow(clippy::partialeq_ne_impl)]
-    #[inline]
-    fn ne(&self, other: &Arc<T>) -> bool {
-        *(*self) != *(*other)
-    }
-}
-
-impl<T: ?Sized, U: ?Sized + PartialOrd<T>> PartialOrd<Arc<T>> for Arc<U> {
-    #[inline]
-    fn partial_cmp(&self, other: &Arc<T>) -> Option<Ordering> {
-        (**self).partial_cmp(&**other)
-    }
-
-    #[inline]
-    fn lt(&self, other: &Arc<T>) -> bool {
-        *(*self) < *(*other)
-    }
-
-    #[inline]
-    fn le(&self, other: &Arc<T>) -> bool {
-        *(*self) <= *(*other)
-    }
-
-    #[inline]
-    fn gt(&self, other: &Arc<T>) -> bool {
-        *(*self) > *(*other)
-    }
-
-    #[inline]
-    fn ge(&self, other: &Arc<T>) -> bool {
-        *(*self) >= *(*other)
-    }
-}
-
-impl<T: ?Sized + Ord> Ord for Arc<T> {
-    fn cmp(&self, other: &Arc<T>) -> Ordering {
-        (**self).cmp(&**other)
-    }
-}
-
-impl<T: ?Sized + Eq> Eq for Arc<T> {}
-
-impl<T: ?Sized + fmt::Display> fmt::Display for Arc<T> {
-    #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&**self, f)
-    }
-}
-
-impl<T: ?Sized + fmt::Debug> fmt::Debug for Arc<T> {
-    #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
-    }
-}
-
-impl<T: ?Sized> fmt::Pointer for Arc<T> {
-    #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Pointer::fmt(&self.p, f)
-    }
-}
-
-impl<T: Default> Default for Arc<T> {
-    #[inline]
-    fn default() -> Arc<T> {
-        Arc::new(Default::default())
-    }
-}
-
-impl<T: ?Sized + Hash> Hash for Arc<T> {
-    #[inline]
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        (**self).hash(state)
-    }
-}
-
-impl<T> From<T> for Arc<T> {
-    #[inline]
-    fn from(t: T) -> Self {
-        Arc::new(t)
-    }
-}
-
-impl<T: ?Sized> borrow::Borrow<T> for Arc<T> {
-    #[inline]
-    fn borrow(&self) -> &T {
-        self
-    }
-}
-
-impl<T: ?Sized> AsRef<T> for Arc<T> {
-    #[inline]
-    fn as_ref(&self) -> &T {
-        self
-    }
-}
-
-unsafe impl<T: ?Sized + Erasable> ErasablePtr for Arc<T> {
-    #[inline]
-    fn erase(this: Self) -> erasable::ErasedPtr {
-        T::erase(unsafe { ptr::NonNull::new_unchecked(Arc::into_raw(this) as *mut _) })
-    }
-
-    #[inline]
-    unsafe fn unerase(this: erasable::ErasedPtr) -> Self {
-        Arc::from_raw(T::unerase(this).as_ptr())
-    }
-}
-
-#[cfg(feature = "stable_deref_trait")]
-unsafe impl<T: ?Sized> StableDeref for Arc<T> {}
-#[cfg(feature = "stable_deref_trait")]
-unsafe impl<T: ?Sized> CloneStableDeref for Arc<T> {}
+impl_shared_ptr!(Arc, ArcInner, ArcBox);
 
 #[cfg(feature = "serde")]
-impl<'de, T: Deserialize<'de>> Deserialize<'de> for Arc<T> {
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Arc<[T]> {
+    /// Deserialize a sequence into an [`Arc<[T]>`][`Arc`]
+    ///
+    /// If the format reports the length of the sequence, the elements are written directly into a
+    /// single allocation; otherwise, they are first collected into a [`Vec`].
     #[inline]
-    fn deserialize<D>(deserializer: D) -> Result<Arc<T>, D::Error>
+    fn deserialize<D>(deserializer: D) -> Result<Arc<[T]>, D::Error>
     where
         D: ::serde::de::Deserializer<'de>,
     {
-        T::deserialize(deserializer).map(Arc::new)
-    }
-}
-
-#[cfg(feature = "serde")]
-impl<T: Serialize> Serialize for Arc<T> {
-    #[inline]
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: ::serde::ser::Serializer,
-    {
-        (**self).serialize(serializer)
-    }
-}
-
-#[cfg(feature = "unsize")]
-/// # Safety
-///
-/// This implementation must guarantee that it is sound to call replace_ptr with an unsized variant
-/// of the pointer retuned in `as_sized_ptr`. The basic property of Unsize coercion is that safety
-/// variants and layout is unaffected. The Arc does not rely on any other property of T. This makes
-/// any unsized ArcInner valid for being shared with the sized variant.
-/// This does _not_ mean that any T can be unsized into an U, but rather than if such unsizing is
-/// possible then it can be propagated into the Arc<T>.
-unsafe impl<T, U: ?Sized> unsize::CoerciblePtr<U> for Arc<T> {
-    type Pointee = T;
-    type Output = Arc<U>;
-
-    fn as_sized_ptr(&mut self) -> *mut T {
-        // Returns a pointer to the complete inner. The unsizing itself won't care about the
-        // pointer value and promises not to offset it.
-        self.p.as_ptr()
-    }
-
-    unsafe fn replace_ptr(self, new: *mut U) -> Arc<U> {
-        // Fix the provenance by ensuring that of `self` is used.
-        let old_layout = ArcInner::layout(&*self);
-        let inner = ManuallyDrop::new(self);
-        let p = inner.p.as_ptr();
-        // Safety: The caller upholds that `new` is an unsized version of the data in the previous ArcInner.
-        let result = Arc::from_raw(p.replace_ptr(new));
-        debug_assert_eq!(old_layout, ArcInner::layout(&*result));
-        result
-    }
-}
-
-#[cfg(feature = "slice-dst")]
-/// # Safety
-///
-/// `ArcInner<S>` is implemented as an additional header before `S`, consisting of the reference count
-unsafe impl<S: SliceDst + ?Sized> SliceDst for ArcInner<S> {
-    fn layout_for(len: usize) -> Layout {
-        Layout::new::<atomic::AtomicUsize>()
-            .extend(S::layout_for(len))
-            .unwrap()
-            .0
-            .pad_to_align()
-    }
-
-    #[allow(clippy::let_and_return)]
-    fn retype(ptr: ptr::NonNull<[()]>) -> ptr::NonNull<Self> {
-        let retype_inner = S::retype(ptr);
-        // Safety: the metadata for `S` is the same as for `ArcInner<S>`, since `ArcInner<S>` has `S` as it's last member.
-        // This is based on the implementation for `triomphe`.
-        let retyped = unsafe { core::ptr::NonNull::new_unchecked(retype_inner.as_ptr() as *mut _) };
-        //TODO: add correctness assertions
-        retyped
-    }
-}
-
-#[cfg(feature = "slice-dst")]
-/// # Safety
-///
-/// This function merely delegates to the [`TryAllocSliceDst`] implementation
-unsafe impl<S: ?Sized + SliceDst> AllocSliceDst<S> for Arc<S> {
-    unsafe fn new_slice_dst<I>(len: usize, init: I) -> Self
-    where
-        I: FnOnce(ptr::NonNull<S>),
-    {
-        #[allow(clippy::unit_arg)]
-        let init = |ptr| Ok::<(), core::convert::Infallible>(init(ptr));
-        #[allow(unreachable_patterns)]
-        match Self::try_new_slice_dst(len, init) {
-            Ok(a) => a,
-            Err(void) => match void {},
-        }
-    }
-}
-
-#[cfg(feature = "slice-dst")]
-/// # Safety
-///
-/// This function merely delegates to the [`ArcBox`] implementation
-unsafe impl<S: ?Sized + SliceDst> TryAllocSliceDst<S> for Arc<S> {
-    unsafe fn try_new_slice_dst<I, E>(len: usize, init: I) -> Result<Self, E>
-    where
-        I: FnOnce(ptr::NonNull<S>) -> Result<(), E>,
-    {
-        Ok(ArcBox::try_new_slice_dst(len, init)?.shareable())
+        ArcBox::<[T]>::deserialize(deserializer).map(ArcBox::shareable)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::arc::Arc;
+    use crate::arc::{AllocError, Arc, ArcInner};
     use core::mem::MaybeUninit;
     #[cfg(feature = "unsize")]
     use unsize::{CoerceUnsize, Coercion};
@@ -750,6 +1268,42 @@ mod tests {
         assert_eq!(Arc::try_unwrap(y), Ok(100));
     }
 
+    #[test]
+    fn make_immortal() {
+        use crate::arc::IMMORTAL_REFCOUNT;
+        use crate::{ArcBorrow, ArcRef, IMMORTAL_COUNT};
+        use alloc::sync::Arc as StdArc;
+        use core::sync::atomic::Ordering::Relaxed;
+
+        let canary = StdArc::new(());
+        let x = Arc::new(canary.clone());
+        let y = x.clone();
+        assert!(!Arc::is_immortal(&x));
+        Arc::make_immortal(&x);
+        #[cfg(miri)]
+        {
+            extern "Rust" {
+                fn miri_static_root(ptr: *const u8);
+            }
+            unsafe { miri_static_root(x.heap_ptr() as *const u8) };
+        }
+        assert!(Arc::is_immortal(&y));
+        assert_eq!(Arc::count(&x), IMMORTAL_COUNT);
+        assert_eq!(ArcBorrow::count(Arc::borrow_arc(&x)), IMMORTAL_COUNT);
+
+        // Neither cloning nor dropping touches the count any more
+        let count = unsafe { &*ArcInner::<StdArc<()>>::count_ptr(Arc::as_ptr(&x) as *mut _) };
+        let clones = [
+            ArcBorrow::clone_arc(Arc::borrow_arc(&x)),
+            ArcRef::into_arc(ArcRef::from_arc(y.clone()).clone()),
+        ];
+        assert_eq!(count.load(Relaxed), IMMORTAL_REFCOUNT);
+        drop((x, y, clones));
+        assert_eq!(count.load(Relaxed), IMMORTAL_REFCOUNT);
+        // The contents are leaked rather than dropped
+        assert_eq!(StdArc::strong_count(&canary), 2);
+    }
+
     #[test]
     #[cfg(feature = "unsize")]
     fn coerce_to_slice() {
@@ -764,14 +1318,92 @@ mod tests {
         use crate::ArcInner;
 
         let x: Arc<_> = Arc::new(|| 42u32);
-        let old_layout = ArcInner::layout(&*x);
+        let old_layout = ArcInner::<_>::layout(&*x);
         assert_eq!((*x)(), 42);
         let x: Arc<_> = x.unsize(Coercion::<_, dyn Fn() -> u32>::to_fn());
-        let new_layout = ArcInner::layout(&*x);
+        let new_layout = ArcInner::<_>::layout(&*x);
         assert_eq!(old_layout, new_layout);
         assert_eq!((*x)(), 42);
     }
 
+    #[test]
+    fn try_new() {
+        let x = Arc::try_new(100usize).unwrap();
+        assert_eq!(*x, 100);
+        assert!(Arc::is_unique(&x));
+
+        let mut y: Arc<MaybeUninit<usize>> = Arc::try_new_uninit().unwrap();
+        y.write(200);
+        assert_eq!(*unsafe { y.assume_init() }, 200);
+    }
+
+    #[test]
+    fn try_new_uninit_slice_overflow() {
+        assert_eq!(
+            Arc::<[MaybeUninit<u64>]>::try_new_uninit_slice(usize::MAX / 4).unwrap_err(),
+            AllocError
+        );
+        let arc = Arc::<[MaybeUninit<u64>]>::try_new_uninit_slice(3).unwrap();
+        assert_eq!(arc.len(), 3);
+        assert!(Arc::is_unique(&arc));
+    }
+
+    #[test]
+    fn str_conversions() {
+        use alloc::borrow::Cow;
+        use alloc::string::String;
+
+        let x: Arc<str> = Arc::from("hello");
+        let y: Arc<str> = Arc::from(String::from("hello"));
+        let z: Arc<str> = Arc::from(String::from("hello").into_boxed_str());
+        let w: Arc<str> = Arc::from(Cow::Borrowed("hello"));
+        assert_eq!(x, y);
+        assert_eq!(x, z);
+        assert_eq!(x, w);
+
+        let bytes = Arc::into_bytes(x);
+        assert_eq!(*bytes, *b"hello");
+        let x = Arc::from_utf8(bytes).unwrap();
+        assert_eq!(&*x, "hello");
+        assert!(Arc::is_unique(&x));
+
+        let err = Arc::from_utf8(Arc::from(&b"\xffhello"[..])).unwrap_err();
+        assert_eq!(err.utf8_error().valid_up_to(), 0);
+        assert_eq!(*err.into_bytes(), *b"\xffhello");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deserialize_str() {
+        use serde::de::value::{Error, StrDeserializer};
+        use serde::de::IntoDeserializer;
+        use serde::Deserialize;
+
+        let deserializer: StrDeserializer<'_, Error> = "hello".into_deserializer();
+        let x = Arc::<str>::deserialize(deserializer).unwrap();
+        assert_eq!(&*x, "hello");
+    }
+
+    #[test]
+    fn from_box() {
+        use alloc::boxed::Box;
+        use alloc::format;
+        use alloc::string::ToString;
+        use core::fmt::{Debug, Display};
+
+        let x: Arc<[u64]> = Arc::from(Box::from(&[1, 2, 3][..]));
+        assert_eq!(*x, [1, 2, 3]);
+        let y: Arc<str> = Arc::from(Box::from("hello"));
+        assert_eq!(&*y, "hello");
+        let z: Arc<dyn Display + Send + Sync> = Arc::from(Box::new(0xffu8) as Box<_>);
+        assert_eq!(z.to_string(), "255");
+        let w: Arc<dyn Debug + Send + Sync> = Arc::from(Box::new(()) as Box<_>);
+        assert_eq!(ArcInner::<_>::layout(&*w).1, ArcInner::<()>::data_offset());
+        assert_eq!(format!("{:?}", w), "()");
+        let u: Arc<[()]> = Arc::from(Box::from(&[(), ()][..]));
+        assert_eq!(u.len(), 2);
+    }
+
     #[test]
     fn maybeuninit() {
         let mut arc: Arc<MaybeUninit<_>> = Arc::new_uninit();
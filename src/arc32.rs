@@ -0,0 +1,376 @@
+use alloc::boxed::Box;
+use core::convert::From;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::ptr;
+use core::sync::atomic;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+use crate::shared_ptr::impl_shared_ptr;
+use crate::{
+    free_inner, refcount_overflow, AllocError, Allocator, ArcInner, Count, Global, SATURATE,
+};
+
+/// A soft limit on the amount of references that may be made to an [`Arc32`].
+///
+/// As for [`Arc`][`crate::Arc`], going above this limit will apply the
+/// [`OVERFLOW_POLICY`][`crate::OVERFLOW_POLICY`], leaving `u32::MAX - MAX_REFCOUNT_32` references
+/// of headroom for racing increments before the count could wrap around.
+pub(crate) const MAX_REFCOUNT_32: u32 = i32::MAX as u32;
+
+/// The count of an [`Arc32`] saturated by the [`OverflowPolicy::Saturate`][`crate::OverflowPolicy`]
+/// policy, which is never written to again
+///
+/// As for immortal [`Arc`][`crate::Arc`]s, this lies halfway between [`MAX_REFCOUNT_32`] and
+/// `u32::MAX`, so that racing increments cannot wrap it around.
+const SATURATED_REFCOUNT_32: u32 = MAX_REFCOUNT_32 + (u32::MAX - MAX_REFCOUNT_32) / 2;
+
+/// The object allocated by an [`Arc32<T>`][`Arc32`]
+///
+/// This is an [`ArcInner`] with a 32-bit reference count, so its header takes up 4 rather than 8
+/// bytes on 64-bit platforms, and `T` with an alignment of at most 4 is not padded.
+pub type ArcInner32<T, A = Global> = ArcInner<T, A, atomic::AtomicU32>;
+
+impl Count for atomic::AtomicU32 {
+    #[inline]
+    fn one() -> Self {
+        atomic::AtomicU32::new(1)
+    }
+}
+
+/// An atomically reference counted shared pointer with a 32-bit reference count
+///
+/// This behaves exactly like an [`Arc`][`crate::Arc`], except that its reference count is an
+/// [`AtomicU32`][`atomic::AtomicU32`] rather than an [`AtomicUsize`][`atomic::AtomicUsize`], which
+/// saves space in the allocation of small values at the cost of supporting at most
+/// `i32::MAX` references.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc32, ArcInner, ArcInner32};
+///
+/// let x = Arc32::new(5u32);
+/// let y = x.clone();
+/// assert_eq!(Arc32::count(&y), 2);
+/// # #[cfg(target_pointer_width = "64")]
+/// assert!(ArcInner32::<u32>::layout(&*x).0.size() < ArcInner::<u32>::layout(&*x).0.size());
+/// ```
+#[repr(transparent)]
+pub struct Arc32<T: ?Sized, A: Allocator = Global> {
+    pub(crate) p: ptr::NonNull<T>,
+    phantom: PhantomData<ArcInner32<T, A>>,
+}
+
+unsafe impl<T: ?Sized + Sync + Send, A: Allocator + Sync + Send> Send for Arc32<T, A> {}
+unsafe impl<T: ?Sized + Sync + Send, A: Allocator + Sync + Send> Sync for Arc32<T, A> {}
+
+impl<T> Arc32<T> {
+    /// Construct an [`Arc32`]
+    #[inline]
+    pub fn new(data: T) -> Self {
+        Arc32::new_in(data, Global)
+    }
+
+    /// Construct an [`Arc32`], returning an error if the allocation fails
+    #[inline]
+    pub fn try_new(data: T) -> Result<Self, AllocError> {
+        Arc32::try_new_in(data, Global)
+    }
+}
+
+impl<T, A: Allocator> Arc32<T, A> {
+    /// Construct an [`Arc32`] in the given allocator
+    #[inline]
+    pub fn new_in(data: T, alloc: A) -> Self {
+        Arc32 {
+            p: ArcInner32::<T, A>::new_in(data, alloc),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Construct an [`Arc32`] in the given allocator, returning an error if the allocation fails
+    #[inline]
+    pub fn try_new_in(data: T, alloc: A) -> Result<Self, AllocError> {
+        Ok(Arc32 {
+            p: ArcInner32::<T, A>::try_new_in(data, alloc)?,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the inner value, if the [`Arc32`] has exactly one strong reference.
+    ///
+    /// Otherwise, an [`Err`] is returned with the same [`Arc32`] that was
+    /// passed in.
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        if Self::is_unique(&this) {
+            let this = ManuallyDrop::new(this);
+            // Safety: we have exclusive access to the inner data, which we move out before
+            //         freeing the allocation.
+            unsafe {
+                let (layout, inner) = ArcInner32::<T, A>::raw_layout(this.p.as_ptr());
+                let data = ptr::read(this.p.as_ptr());
+                free_inner::<_, atomic::AtomicU32>(inner, layout);
+                Ok(data)
+            }
+        } else {
+            Err(this)
+        }
+    }
+}
+
+impl<T: ?Sized> Arc32<T> {
+    /// Reconstruct the [`Arc32<T>`][`Arc32`] from a raw pointer obtained from
+    /// [`into_raw`][`Arc32::into_raw`]
+    ///
+    /// # Safety
+    /// The pointer `ptr` must be the result of a call to [`into_raw`][`Arc32::into_raw`]
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const T) -> Self {
+        Arc32::from_raw_in(ptr)
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Arc32<T, A> {
+    /// Reconstruct the [`Arc32<T, A>`][`Arc32`] from a raw pointer obtained from
+    /// [`into_raw`][`Arc32::into_raw`]
+    ///
+    /// # Safety
+    /// The pointer `ptr` must be the result of a call to [`into_raw`][`Arc32::into_raw`] on an
+    /// [`Arc32<T, A>`][`Arc32`] with the same allocator type `A`
+    #[inline]
+    pub unsafe fn from_raw_in(ptr: *const T) -> Self {
+        #[cfg(feature = "header-canary")]
+        ArcInner32::<T, A>::check_canary(ptr, "Arc32::from_raw");
+        Arc32 {
+            p: ptr::NonNull::new_unchecked(ptr as *mut T),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Convert the [`Arc32`] to a raw pointer, suitable for use across FFI
+    #[inline]
+    pub fn into_raw(this: Self) -> *const T {
+        let ptr = Arc32::as_ptr(&this);
+        mem::forget(this);
+        ptr
+    }
+
+    /// Returns the raw pointer.
+    ///
+    /// Same as into_raw except `self` isn't consumed.
+    #[inline]
+    pub fn as_ptr(this: &Self) -> *const T {
+        this.p.as_ptr()
+    }
+
+    /// Get a reference to the allocator this [`Arc32`] was allocated with
+    #[inline]
+    pub fn allocator(this: &Self) -> &A {
+        unsafe { &*ArcInner32::<T, A>::alloc_ptr(this.p.as_ptr()) }
+    }
+
+    /// Returns the address on the heap of the [`Arc32`] itself -- not the `T` within it -- for
+    /// memory reporting.
+    pub fn heap_ptr(&self) -> *const c_void {
+        unsafe { ArcInner32::<T, A>::alloc_ptr(self.p.as_ptr()) as *const c_void }
+    }
+
+    /// Test pointer equality between the two [`Arc32`]s, i.e. they must be the _same_
+    /// allocation
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        core::ptr::eq(this.p.as_ptr(), other.p.as_ptr())
+    }
+
+    /// Provides mutable access to the contents _if_ the [`Arc32`] is uniquely owned.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if Self::is_unique(this) {
+            // Safety: see `Arc::make_mut`
+            unsafe { Some(this.p.as_mut()) }
+        } else {
+            None
+        }
+    }
+
+    /// Whether or not the [`Arc32`] is uniquely owned (is the refcount 1?).
+    #[inline]
+    pub fn is_unique(this: &Self) -> bool {
+        Self::count(this) == 1
+    }
+
+    /// Gets the number of [`Arc32`] pointers to this allocation
+    #[inline]
+    pub fn count(this: &Self) -> usize {
+        Self::load_count(this, Acquire)
+    }
+
+    /// Gets the number of [`Arc32`] pointers to this allocation, with a given load ordering
+    #[inline]
+    pub fn load_count(this: &Self, order: atomic::Ordering) -> usize {
+        unsafe { (*ArcInner32::<T, A>::count_ptr(this.p.as_ptr())).load(order) as usize }
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Arc32<T, A> {
+    /// Makes a mutable reference to the [`Arc32`], cloning if necessary
+    ///
+    /// See [`Arc::make_mut`][`crate::Arc::make_mut`].
+    #[inline]
+    pub fn make_mut(this: &mut Self) -> &mut T {
+        if !Self::is_unique(this) {
+            // Another pointer exists; clone
+            *this = Arc32::new_in((**this).clone(), Arc32::allocator(this).clone());
+        }
+        debug_assert!(Self::is_unique(this));
+        // Safety: our reference count is 1, and we have exclusive access to the `Arc32` itself
+        unsafe { this.p.as_mut() }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Clone for Arc32<T, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        #[cfg(feature = "header-canary")]
+        unsafe {
+            ArcInner32::<T, A>::check_canary(self.p.as_ptr(), "Arc32::clone")
+        };
+        // See `Arc::clone` for why a relaxed ordering is sufficient here
+        let count = unsafe { &*ArcInner32::<T, A>::count_ptr(self.p.as_ptr()) };
+
+        // Saturated counts are never written to again
+        if SATURATE && count.load(Relaxed) > MAX_REFCOUNT_32 {
+            return Arc32 {
+                p: self.p,
+                phantom: PhantomData,
+            };
+        }
+
+        let old_size = count.fetch_add(1, Relaxed);
+
+        // As for `Arc`, we racily saturate, here to `i32::MAX`, on the assumption that there
+        // aren't ~2 billion threads incrementing the reference count at once.
+        if old_size > MAX_REFCOUNT_32 {
+            count.fetch_sub(1, Relaxed);
+            refcount_overflow();
+            count.store(SATURATED_REFCOUNT_32, Relaxed);
+        }
+
+        Arc32 {
+            p: self.p,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Drop for Arc32<T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "header-canary")]
+        unsafe {
+            ArcInner32::<T, A>::check_canary(self.p.as_ptr(), "Arc32::drop")
+        };
+        // See `Arc::drop` for the choice of orderings here
+        let count = unsafe { &*ArcInner32::<T, A>::count_ptr(self.p.as_ptr()) };
+        if SATURATE && count.load(Relaxed) > MAX_REFCOUNT_32 {
+            return;
+        }
+        if count.fetch_sub(1, Release) != 1 {
+            return;
+        }
+        count.load(Acquire);
+
+        unsafe {
+            ArcInner32::<T, A>::drop_slow(self.p.as_ptr());
+        }
+    }
+}
+
+impl_shared_ptr!(Arc32, ArcInner32);
+
+impl<T: ?Sized> From<Box<T>> for Arc32<T> {
+    /// Move a boxed value into a new [`Arc32`], freeing the original [`Box`]
+    ///
+    /// This works for unsized values such as slices and trait objects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Arc32;
+    ///
+    /// let x: Arc32<str> = Arc32::from(Box::<str>::from("hello"));
+    /// assert_eq!(&*x, "hello");
+    /// ```
+    #[inline]
+    fn from(b: Box<T>) -> Self {
+        let data = ArcInner32::<T>::from_box(b, atomic::AtomicU32::new(1));
+        unsafe { Arc32::from_raw(data.as_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arc32::Arc32;
+    #[cfg(not(feature = "header-canary"))]
+    use crate::{arc32::ArcInner32, ArcInner};
+    use alloc::boxed::Box;
+
+    #[test]
+    #[cfg(not(feature = "header-canary"))]
+    fn layout() {
+        assert_eq!(ArcInner32::<()>::data_offset(), 4);
+        assert_eq!(ArcInner32::<u32>::layout(&0).0.size(), 8);
+        assert_eq!(ArcInner32::<u8>::layout(&0).0.size(), 8);
+        assert_eq!(ArcInner32::<u64>::data_offset(), 8);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(ArcInner::<u32>::layout(&0).0.size(), 16);
+        let x = Arc32::new(7u16);
+        unsafe {
+            let p = Arc32::as_ptr(&x) as *mut u16;
+            assert_eq!(
+                ArcInner32::<u16>::count_ptr(p) as *const u8,
+                (p as *const u8).sub(4)
+            );
+            assert_eq!(
+                ArcInner32::<u16>::from_data(p) as *const u8,
+                x.heap_ptr() as *const u8
+            );
+        }
+    }
+
+    #[test]
+    fn clone_and_drop() {
+        use alloc::sync::Arc as StdArc;
+
+        let canary = StdArc::new(());
+        let x = Arc32::new(canary.clone());
+        let y = x.clone();
+        assert_eq!(Arc32::count(&x), 2);
+        assert!(Arc32::try_unwrap(x).is_err());
+        assert_eq!(StdArc::strong_count(&canary), 2);
+        assert!(Arc32::is_unique(&y));
+        drop(y);
+        assert_eq!(StdArc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn make_mut() {
+        let mut x = Arc32::new(5);
+        let y = x.clone();
+        *Arc32::make_mut(&mut x) += 1;
+        assert_eq!((*x, *y), (6, 5));
+        assert!(!Arc32::ptr_eq(&x, &y));
+        assert_eq!(Arc32::try_unwrap(x), Ok(6));
+    }
+
+    #[test]
+    fn from_box() {
+        let x: Arc32<[u8]> = Arc32::from(Box::from(&[1, 2, 3][..]));
+        let y = x.clone();
+        assert_eq!(*y, [1, 2, 3]);
+        #[cfg(not(feature = "header-canary"))]
+        assert_eq!(ArcInner32::<_>::layout(&*x).0.size(), 8);
+    }
+}
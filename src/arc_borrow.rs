@@ -1,16 +1,13 @@
-use core::borrow::Borrow;
-use core::ffi::c_void;
-use core::hash::{Hash, Hasher};
-use core::ops::Deref;
+use core::marker::PhantomData;
+use core::pin::Pin;
 use core::ptr;
-use core::ptr::NonNull;
 use core::sync::atomic;
-use core::{cmp::Ordering, marker::PhantomData};
-use core::{fmt, mem};
 
-use erasable::{Erasable, ErasablePtr};
+#[cfg(feature = "serde")]
+use serde::Serialize;
 
-use super::{Arc, ArcInner, ArcRef};
+use super::{reported_count, Allocator, Arc, ArcInner, ArcRef, Global};
+use crate::shared_ptr::impl_borrow;
 
 /// A "borrowed [`Arc`]". This is essentially a reference to an `ArcInner<T>`
 ///
@@ -19,69 +16,27 @@ use super::{Arc, ArcInner, ArcRef};
 /// [`ArcBorrow`] lets us deal with borrows of known-refcounted objects
 /// without needing to worry about where the [`Arc<T>`][`Arc`] is.
 #[repr(transparent)]
-pub struct ArcBorrow<'a, T: ?Sized + 'a> {
+pub struct ArcBorrow<'a, T: ?Sized + 'a, A: Allocator + 'a = Global> {
     pub(crate) p: ptr::NonNull<T>,
-    pub(crate) phantom: PhantomData<&'a T>,
+    pub(crate) phantom: PhantomData<&'a ArcInner<T, A>>,
 }
 
-impl<'a, T: ?Sized> Copy for ArcBorrow<'a, T> {}
-impl<'a, T: ?Sized> Clone for ArcBorrow<'a, T> {
-    #[inline]
-    fn clone(&self) -> Self {
-        *self
-    }
-}
-
-impl<'a, T: ?Sized> ArcBorrow<'a, T> {
-    /// Clone this as an [`Arc<T>`]. This bumps the refcount.
-    #[inline]
-    pub fn clone_arc(this: Self) -> Arc<T> {
-        let arc = unsafe { Arc::from_raw(this.p.as_ptr()) };
-        // addref it!
-        mem::forget(arc.clone());
-        arc
-    }
-
-    /// Compare two [`ArcBorrow`]s via pointer equality. Will only return
-    /// true if they come from the same allocation
-    #[inline]
-    pub fn ptr_eq(this: Self, other: Self) -> bool {
-        core::ptr::eq(this.p.as_ptr(), other.p.as_ptr())
-    }
-
-    /// Similar to deref, but uses the lifetime `'a` rather than the lifetime of
-    /// `self`, which is incompatible with the signature of the [`Deref`] trait.
-    #[inline]
-    pub fn get(&self) -> &'a T {
-        unsafe { &*(self.p.as_ptr() as *const T) }
-    }
-
-    /// Borrow this as an [`Arc`]. This does *not* bump the refcount.
-    #[inline]
-    pub fn as_arc(this: &Self) -> &Arc<T> {
-        unsafe { &*(this as *const _ as *const Arc<T>) }
-    }
+impl_borrow!(ArcBorrow, Arc, ArcInner, ArcRef; clone_arc, as_arc, as_arc_ref);
 
-    /// Get the internal pointer of an [`ArcBorrow`]
+impl<T> ArcBorrow<'static, T> {
+    /// Borrow an immortal, statically allocated [`ArcInner`], as created with
+    /// [`static_arc!`][`crate::static_arc`]
     #[inline]
-    pub fn into_raw(this: Self) -> *const T {
-        let arc = Self::as_arc(&this);
-        Arc::as_ptr(arc)
-    }
-
-    /// Construct an [`ArcBorrow`] from an internal pointer
-    ///
-    /// # Safety
-    /// This pointer must be the result of `ArcBorrow::from_raw` or `Arc::from_raw`. In the latter case, the reference count is not incremented.
-    #[inline]
-    pub unsafe fn from_raw(raw: *const T) -> Self {
-        ArcBorrow {
-            p: NonNull::new_unchecked(raw as *mut T),
-            phantom: PhantomData,
-        }
+    pub fn from_static(inner: &'static ArcInner<T>) -> Self {
+        // Safety: see `Arc::from_static`
+        unsafe { ArcBorrow::from_raw(ptr::addr_of!((*(inner as *const ArcInner<T>)).data)) }
     }
+}
 
+impl<'a, T: ?Sized, A: Allocator> ArcBorrow<'a, T, A> {
     /// Gets the number of [`Arc`] pointers to this allocation
+    ///
+    /// This is [`IMMORTAL_COUNT`][`crate::IMMORTAL_COUNT`] if the allocation is immortal.
     #[inline]
     pub fn count(this: Self) -> usize {
         ArcBorrow::load_count(this, atomic::Ordering::Acquire)
@@ -90,128 +45,50 @@ impl<'a, T: ?Sized> ArcBorrow<'a, T> {
     /// Gets the number of [`Arc`] pointers to this allocation, with a given load ordering
     #[inline]
     pub fn load_count(this: Self, order: atomic::Ordering) -> usize {
-        unsafe {
-            (*(ArcInner::count_ptr(this.p.as_ptr()) as *const atomic::AtomicUsize)).load(order)
-        }
-    }
-
-    /// Returns the address on the heap of the [`ArcRef`] itself -- not the `T` within it -- for memory
-    /// reporting.
-    pub fn heap_ptr(self) -> *const c_void {
-        unsafe { ArcInner::count_ptr(self.p.as_ptr()) as *const c_void }
-    }
-}
-
-impl<'a, T> ArcBorrow<'a, T> {
-    /// Borrow this as an [`ArcRef`]. This does *not* bump the refcount.
-    #[inline]
-    pub fn as_arc_ref(this: &'a ArcBorrow<'a, T>) -> &'a ArcRef<'a, T> {
-        unsafe { &*(this as *const _ as *const ArcRef<'a, T>) }
-    }
-}
-
-impl<'a, T> Deref for ArcBorrow<'a, T> {
-    type Target = T;
-
-    #[inline]
-    fn deref(&self) -> &T {
-        self.get()
-    }
-}
-
-unsafe impl<T: ?Sized + Erasable> ErasablePtr for ArcBorrow<'_, T> {
-    #[inline]
-    fn erase(this: Self) -> erasable::ErasedPtr {
-        T::erase(unsafe { ptr::NonNull::new_unchecked(ArcBorrow::into_raw(this) as *mut _) })
-    }
-
-    #[inline]
-    unsafe fn unerase(this: erasable::ErasedPtr) -> Self {
-        ArcBorrow::from_raw(T::unerase(this).as_ptr())
-    }
-}
-
-impl<'a, 'b, T, U: PartialEq<T>> PartialEq<ArcBorrow<'a, T>> for ArcBorrow<'b, U> {
-    #[inline]
-    fn eq(&self, other: &ArcBorrow<'a, T>) -> bool {
-        *(*self) == *(*other)
+        reported_count(unsafe { (*ArcInner::<T, A>::count_ptr(this.p.as_ptr())).load(order) })
     }
 
-    #[allow(clippy::partialeq_ne_impl)]
+    /// Whether the allocation this borrows is immortal, i.e. will never be freed
     #[inline]
-    fn ne(&self, other: &ArcBorrow<'a, T>) -> bool {
-        *(*self) != *(*other)
+    pub fn is_immortal(this: Self) -> bool {
+        ArcBorrow::count(this) == crate::IMMORTAL_COUNT
     }
 }
 
-impl<'a, 'b, T, U: PartialOrd<T>> PartialOrd<ArcBorrow<'a, T>> for ArcBorrow<'b, U> {
-    #[inline]
-    fn partial_cmp(&self, other: &ArcBorrow<'a, T>) -> Option<Ordering> {
-        (**self).partial_cmp(&**other)
-    }
-
-    #[inline]
-    fn lt(&self, other: &ArcBorrow<'a, T>) -> bool {
-        *(*self) < *(*other)
-    }
-
-    #[inline]
-    fn le(&self, other: &ArcBorrow<'a, T>) -> bool {
-        *(*self) <= *(*other)
-    }
-
+impl<'a, T: ?Sized, A: Allocator> ArcBorrow<'a, T, A> {
+    /// Get a pinned reference to the data of a pinned [`ArcBorrow`], with the lifetime `'a`
     #[inline]
-    fn gt(&self, other: &ArcBorrow<'a, T>) -> bool {
-        *(*self) > *(*other)
+    pub fn get_pin(this: Pin<Self>) -> Pin<&'a T> {
+        // Safety: the data is pinned, since `this` is
+        unsafe { Pin::new_unchecked(Pin::into_inner_unchecked(this).get()) }
     }
 
+    /// Clone a pinned [`ArcBorrow`] as a pinned [`Arc`]. This bumps the refcount.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Arc, ArcBorrow};
+    ///
+    /// let x = Arc::pin(5);
+    /// let y = ArcBorrow::clone_pin(Arc::borrow_pin(&x));
+    /// assert_eq!(*ArcBorrow::get_pin(Arc::borrow_pin(&y)), 5);
+    /// ```
     #[inline]
-    fn ge(&self, other: &ArcBorrow<'a, T>) -> bool {
-        *(*self) >= *(*other)
-    }
-}
-
-impl<'a, T: Ord> Ord for ArcBorrow<'a, T> {
-    #[inline]
-    fn cmp(&self, other: &ArcBorrow<'a, T>) -> Ordering {
-        (**self).cmp(&**other)
-    }
-}
-
-impl<'a, T: Eq> Eq for ArcBorrow<'a, T> {}
-
-impl<'a, T: fmt::Display> fmt::Display for ArcBorrow<'a, T> {
-    #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&**self, f)
-    }
-}
-
-impl<'a, T: fmt::Debug> fmt::Debug for ArcBorrow<'a, T> {
-    #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
-    }
-}
-
-impl<T: Hash> Hash for ArcBorrow<'_, T> {
-    #[inline]
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        (**self).hash(state)
-    }
-}
-
-impl<T> Borrow<T> for ArcBorrow<'_, T> {
-    #[inline]
-    fn borrow(&self) -> &T {
-        self
+    pub fn clone_pin(this: Pin<Self>) -> Pin<Arc<T, A>> {
+        // Safety: the data is pinned, since `this` is, and cloning does not move it
+        unsafe { Pin::new_unchecked(ArcBorrow::clone_arc(Pin::into_inner_unchecked(this))) }
     }
 }
 
-impl<T> AsRef<T> for ArcBorrow<'_, T> {
+#[cfg(feature = "serde")]
+impl<T: ?Sized + Serialize, A: Allocator> Serialize for ArcBorrow<'_, T, A> {
     #[inline]
-    fn as_ref(&self) -> &T {
-        self
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::ser::Serializer,
+    {
+        (**self).serialize(serializer)
     }
 }
 
@@ -0,0 +1,288 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+#[cfg(feature = "stable_deref_trait")]
+use stable_deref_trait::{CloneStableDeref, StableDeref};
+
+use crate::{Allocator, Arc, Global};
+
+/// An owning projection of an [`Arc<T>`][`Arc`]: a shared pointer to some `U` borrowed from the `T`,
+/// such as one of its fields or a sub-slice, which keeps the whole allocation alive
+///
+/// An [`Arc`] always points to the start of the data of its allocation, since it uses this to find
+/// the reference count, so it cannot point to a part of it. An [`ArcMap`] instead stores the
+/// parent [`Arc`] alongside the projected pointer; cloning or dropping it updates the parent's
+/// reference count.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, ArcMap};
+///
+/// struct Config {
+///     name: String,
+///     values: Vec<u32>,
+/// }
+///
+/// let config = Arc::new(Config { name: "config".into(), values: vec![1, 2, 3, 4] });
+/// let values: ArcMap<Config, [u32]> = Arc::map(config.clone(), |c| &c.values[..]);
+/// let tail = ArcMap::map(values.clone(), |v| &v[2..]);
+/// assert_eq!(*tail, [3, 4]);
+/// assert_eq!(Arc::count(&config), 3);
+///
+/// let parent = ArcMap::into_parent(tail);
+/// assert!(Arc::ptr_eq(&parent, &config));
+/// assert_eq!(parent.name, "config");
+/// ```
+pub struct ArcMap<T: ?Sized, U: ?Sized, A: Allocator = Global> {
+    parent: Arc<T, A>,
+    p: NonNull<U>,
+}
+
+unsafe impl<T: ?Sized + Sync + Send, U: ?Sized + Sync, A: Allocator + Sync + Send> Send
+    for ArcMap<T, U, A>
+{
+}
+unsafe impl<T: ?Sized + Sync + Send, U: ?Sized + Sync, A: Allocator + Sync + Send> Sync
+    for ArcMap<T, U, A>
+{
+}
+
+impl<T: ?Sized, A: Allocator> Arc<T, A> {
+    /// Project this [`Arc`] to a part of its data, keeping the whole allocation alive
+    ///
+    /// See [`ArcMap`].
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U>(this: Self, f: F) -> ArcMap<T, U, A> {
+        let p = NonNull::from(f(&*this));
+        ArcMap { parent: this, p }
+    }
+
+    /// Try to project this [`Arc`] to a part of its data, keeping the whole allocation alive, or
+    /// give it back if `f` returns `None`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Arc;
+    ///
+    /// let x: Arc<[u8]> = Arc::from(&b"hello"[..]);
+    /// let first = Arc::try_map(x.clone(), |b| b.first()).unwrap();
+    /// assert_eq!(*first, b'h');
+    /// let x = Arc::try_map(x, |b| b.get(10)).unwrap_err();
+    /// assert_eq!(*x, *b"hello");
+    /// ```
+    #[inline]
+    pub fn try_map<U: ?Sized, F: FnOnce(&T) -> Option<&U>>(
+        this: Self,
+        f: F,
+    ) -> Result<ArcMap<T, U, A>, Self> {
+        match f(&*this) {
+            Some(data) => {
+                let p = NonNull::from(data);
+                Ok(ArcMap { parent: this, p })
+            }
+            None => Err(this),
+        }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, A: Allocator> ArcMap<T, U, A> {
+    /// Project this [`ArcMap`] further, keeping the parent allocation alive
+    #[inline]
+    pub fn map<V: ?Sized, F: FnOnce(&U) -> &V>(this: Self, f: F) -> ArcMap<T, V, A> {
+        let p = NonNull::from(f(&*this));
+        ArcMap {
+            parent: this.parent,
+            p,
+        }
+    }
+
+    /// Try to project this [`ArcMap`] further, or give it back if `f` returns `None`
+    #[inline]
+    pub fn try_map<V: ?Sized, F: FnOnce(&U) -> Option<&V>>(
+        this: Self,
+        f: F,
+    ) -> Result<ArcMap<T, V, A>, Self> {
+        match f(&*this) {
+            Some(data) => {
+                let p = NonNull::from(data);
+                Ok(ArcMap {
+                    parent: this.parent,
+                    p,
+                })
+            }
+            None => Err(this),
+        }
+    }
+
+    /// Get the parent [`Arc`] this [`ArcMap`] projects from
+    #[inline]
+    pub fn parent(this: &Self) -> &Arc<T, A> {
+        &this.parent
+    }
+
+    /// Convert this [`ArcMap`] back into its parent [`Arc`]
+    #[inline]
+    pub fn into_parent(this: Self) -> Arc<T, A> {
+        this.parent
+    }
+
+    /// Get the projected pointer of this [`ArcMap`]
+    #[inline]
+    pub fn as_ptr(this: &Self) -> *const U {
+        this.p.as_ptr()
+    }
+
+    /// Test pointer equality between two [`ArcMap`]s, i.e. they must point to the _same_ data in
+    /// the _same_ allocation
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.parent, &other.parent) && core::ptr::eq(this.p.as_ptr(), other.p.as_ptr())
+    }
+}
+
+impl<T: ?Sized, A: Allocator> From<Arc<T, A>> for ArcMap<T, T, A> {
+    /// The identity projection of an [`Arc`]
+    #[inline]
+    fn from(arc: Arc<T, A>) -> Self {
+        Arc::map(arc, |t| t)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, A: Allocator> Clone for ArcMap<T, U, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        ArcMap {
+            parent: self.parent.clone(),
+            p: self.p,
+        }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, A: Allocator> Deref for ArcMap<T, U, A> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &U {
+        // Safety: `p` was borrowed from the data of `parent`, which is kept alive and never
+        //         mutated while it is shared
+        unsafe { self.p.as_ref() }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, A: Allocator> Borrow<U> for ArcMap<T, U, A> {
+    #[inline]
+    fn borrow(&self) -> &U {
+        self
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, A: Allocator> AsRef<U> for ArcMap<T, U, A> {
+    #[inline]
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+impl<T: ?Sized, U: ?Sized + PartialEq, A: Allocator> PartialEq for ArcMap<T, U, A> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized, U: ?Sized + Eq, A: Allocator> Eq for ArcMap<T, U, A> {}
+
+impl<T: ?Sized, U: ?Sized + PartialOrd, A: Allocator> PartialOrd for ArcMap<T, U, A> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized + Ord, A: Allocator> Ord for ArcMap<T, U, A> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized + Hash, A: Allocator> Hash for ArcMap<T, U, A> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized + fmt::Debug, A: Allocator> fmt::Debug for ArcMap<T, U, A> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized + fmt::Display, A: Allocator> fmt::Display for ArcMap<T, U, A> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, A: Allocator> fmt::Pointer for ArcMap<T, U, A> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.p, f)
+    }
+}
+
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<T: ?Sized, U: ?Sized, A: Allocator> StableDeref for ArcMap<T, U, A> {}
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<T: ?Sized, U: ?Sized, A: Allocator> CloneStableDeref for ArcMap<T, U, A> {}
+
+#[cfg(feature = "serde")]
+impl<T: ?Sized, U: ?Sized + Serialize, A: Allocator> Serialize for ArcMap<T, U, A> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arc, ArcMap};
+    use alloc::sync::Arc as StdArc;
+
+    #[test]
+    fn map_keeps_parent_alive() {
+        let canary = StdArc::new(());
+        let x = Arc::new((canary.clone(), [1u8, 2, 3]));
+        let y = Arc::map(x, |x| &x.1[1..]);
+        let z = ArcMap::map(y.clone(), |y| &y[1]);
+        assert_eq!(*y, [2, 3]);
+        assert_eq!(*z, 3);
+        assert_eq!(Arc::count(ArcMap::parent(&z)), 2);
+        drop(y);
+        assert_eq!(StdArc::strong_count(&canary), 2);
+        let z = ArcMap::try_map(z, |_| None::<&u8>).unwrap_err();
+        assert!(ArcMap::ptr_eq(&z, &z.clone()));
+        drop(z);
+        assert_eq!(StdArc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn identity() {
+        let x: Arc<str> = Arc::from("hello");
+        let y = ArcMap::from(x.clone());
+        assert_eq!(&*y, "hello");
+        assert_eq!(ArcMap::as_ptr(&y), Arc::as_ptr(&x));
+        assert!(Arc::ptr_eq(&ArcMap::into_parent(y), &x));
+    }
+}
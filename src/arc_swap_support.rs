@@ -1,8 +1,25 @@
-use arc_swap::RefCnt;
+//! Support for storing `elysees` pointers in an [`ArcSwap`][`arc_swap::ArcSwap`]
+//!
+//! [`RefCnt`] is implemented for [`Arc<T>`][`Arc`] with sized `T`, [`ThinArc<H, T>`][`ThinArc`] and
+//! the equivalent [`Arc<HeaderSlice<H, [T]>>`][`Arc`], and [`ArcRef<'static, T>`][`ArcRef`] for
+//! any [`Erasable`] `T`. `arc-swap` stores thin pointers, so `Arc<str>` and `Arc<[T]>`, which do not
+//! store their length in the allocation, cannot be supported; use a [`ThinArc`] instead. Neither
+//! can [`ArcBox`][`crate::ArcBox`], as it cannot be cloned.
+//!
+//! `arc-swap`'s `AsRaw` trait is sealed, but is already implemented for `&P` for any [`RefCnt`]
+//! pointer `P`, as well as for raw pointers, so `compare_and_swap` can be called with an `&Arc<T>`
+//! or with [`ArcBorrow::into_raw`] without cloning. An [`ArcBorrow`] can be obtained from a
+//! [`Guard`] without touching the reference count using [`From`].
 
-use crate::Arc;
+use core::ffi::c_void;
+use core::ptr::NonNull;
 
-unsafe impl<T> RefCnt for Arc<T> {
+use arc_swap::{strategy::Strategy, Guard, RefCnt};
+use erasable::{Erasable, ErasablePtr};
+
+use crate::{Allocator, Arc, ArcBorrow, ArcRef, HeaderSlice, ThinArc};
+
+unsafe impl<T, A: Allocator> RefCnt for Arc<T, A> {
     type Base = T;
 
     #[inline]
@@ -17,6 +34,141 @@ unsafe impl<T> RefCnt for Arc<T> {
 
     #[inline]
     unsafe fn from_ptr(ptr: *const Self::Base) -> Self {
-        Arc::from_raw(ptr)
+        Arc::from_raw_in(ptr)
+    }
+}
+
+unsafe impl<H, T, A: Allocator> RefCnt for ThinArc<H, T, A> {
+    type Base = HeaderSlice<H, [T; 0]>;
+
+    #[inline]
+    fn into_ptr(me: Self) -> *mut Self::Base {
+        ErasablePtr::erase(me).cast().as_ptr()
+    }
+
+    #[inline]
+    fn as_ptr(me: &Self) -> *mut Self::Base {
+        ArcBorrow::into_raw(ThinArc::borrow_arc(me)) as *mut _
+    }
+
+    #[inline]
+    unsafe fn from_ptr(ptr: *const Self::Base) -> Self {
+        ErasablePtr::unerase(NonNull::new_unchecked(ptr as *mut Self::Base).cast())
+    }
+}
+
+unsafe impl<H, T, A: Allocator> RefCnt for Arc<HeaderSlice<H, [T]>, A> {
+    type Base = HeaderSlice<H, [T; 0]>;
+
+    #[inline]
+    fn into_ptr(me: Self) -> *mut Self::Base {
+        RefCnt::into_ptr(ThinArc::from_arc(me))
+    }
+
+    #[inline]
+    fn as_ptr(me: &Self) -> *mut Self::Base {
+        Arc::as_ptr(me) as *mut _
+    }
+
+    #[inline]
+    unsafe fn from_ptr(ptr: *const Self::Base) -> Self {
+        ThinArc::into_arc(RefCnt::from_ptr(ptr))
+    }
+}
+
+/// An [`ArcRef`] is stored as an owned reference, so borrowed [`ArcRef`]s have their reference
+/// count incremented when stored.
+unsafe impl<T: ?Sized + Erasable, A: Allocator> RefCnt for ArcRef<'static, T, A> {
+    type Base = c_void;
+
+    #[inline]
+    fn into_ptr(me: Self) -> *mut Self::Base {
+        let arc = ArcRef::into_arc(me);
+        // Safety: the pointer of an `Arc` is never null
+        let p = unsafe { NonNull::new_unchecked(Arc::into_raw(arc) as *mut T) };
+        T::erase(p).cast().as_ptr()
+    }
+
+    #[inline]
+    fn as_ptr(me: &Self) -> *mut Self::Base {
+        // Safety: the pointer of an `ArcRef` is never null
+        let p = unsafe { NonNull::new_unchecked(ArcRef::as_ptr(me) as *mut T) };
+        T::erase(p).cast().as_ptr()
+    }
+
+    #[inline]
+    unsafe fn from_ptr(ptr: *const Self::Base) -> Self {
+        let p = T::unerase(NonNull::new_unchecked(ptr as *mut c_void).cast());
+        ArcRef::from_arc(Arc::from_raw_in(p.as_ptr()))
+    }
+}
+
+impl<'a, T, A: Allocator, S: Strategy<Arc<T, A>>> From<&'a Guard<Arc<T, A>, S>>
+    for ArcBorrow<'a, T, A>
+{
+    /// Borrow the [`Arc`] protected by a [`Guard`], without touching the reference count
+    #[inline]
+    fn from(guard: &'a Guard<Arc<T, A>, S>) -> Self {
+        Arc::borrow_arc(guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arc, ArcBorrow, ArcRef, HeaderSlice, ThinArc};
+    use alloc::string::String;
+    use arc_swap::ArcSwapAny;
+
+    #[test]
+    fn thin_and_erased() {
+        let x = ThinArc::from_header_and_slice(String::from("header"), &[1, 2, 3]);
+        let swap: ArcSwapAny<_> = ArcSwapAny::new(x.clone());
+        assert_eq!(swap.load().slice, [1, 2, 3]);
+        let y = ThinArc::from_header_and_slice(String::from("other"), &[4]);
+        let old = swap.compare_and_swap(&x, y.clone());
+        assert!(ThinArc::ptr_eq(&*old, &x));
+        drop(old);
+        assert!(ThinArc::ptr_eq(&swap.swap(x.clone()), &y));
+        assert_eq!(ThinArc::count(&x), 2);
+
+        let z: Arc<HeaderSlice<(), [u8]>> = Arc::from_header_and_slice((), b"unsized");
+        let swap: ArcSwapAny<_> = ArcSwapAny::new(z.clone());
+        assert_eq!(swap.load().slice, *b"unsized");
+        drop(swap);
+        assert!(Arc::is_unique(&z));
+
+        let leaked = ArcRef::leak(ArcRef::new(5u32));
+        #[cfg(miri)]
+        {
+            extern "Rust" {
+                fn miri_static_root(ptr: *const u8);
+            }
+            unsafe { miri_static_root(ArcBorrow::heap_ptr(leaked) as *const u8) };
+        }
+        let r = ArcRef::from_borrow(leaked);
+        assert_eq!(ArcRef::count(&r), 1);
+        let swap: ArcSwapAny<_> = ArcSwapAny::new(r.clone());
+        assert_eq!(ArcRef::count(&r), 2);
+        assert_eq!(**swap.load(), 5);
+        swap.store(ArcRef::new(6));
+        assert_eq!(ArcRef::count(&r), 1);
+    }
+
+    #[test]
+    fn borrowed_compare_and_swap() {
+        let x = Arc::new(1);
+        let swap: ArcSwapAny<_> = ArcSwapAny::new(x.clone());
+        let guard = swap.load();
+        let borrowed = ArcBorrow::from(&guard);
+        assert!(ArcBorrow::ptr_eq(borrowed, Arc::borrow_arc(&x)));
+        assert_eq!(ArcBorrow::count(borrowed), 2);
+        let old = swap.compare_and_swap(ArcBorrow::into_raw(borrowed), Arc::new(2));
+        assert!(Arc::ptr_eq(&*old, &x));
+        drop((old, guard));
+        assert!(Arc::is_unique(&x));
+
+        let swap: ArcSwapAny<_> = ArcSwapAny::new(Some(x.clone()));
+        let old = swap.compare_and_swap(&Some(x.clone()), None);
+        assert!(Arc::ptr_eq(old.as_ref().unwrap(), &x));
     }
 }
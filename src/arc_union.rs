@@ -0,0 +1,330 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+use core::sync::atomic::AtomicUsize;
+use erasable::Erasable;
+
+use crate::{Arc, ArcBorrow};
+
+/// Define a tagged union of [`Arc`]s, along with an enum of [`ArcBorrow`]s to match on
+///
+/// The tag is stored in the low bits of the data pointer, which are always zero since the data of
+/// an `ArcInner` is at least as aligned as its [`AtomicUsize`] reference count.
+macro_rules! arc_union {
+    (
+        $(#[$attr:meta])*
+        $name:ident;
+        $(#[$battr:meta])*
+        $borrow:ident;
+        mask = $mask:literal;
+        $($variant:ident($ty:ident) = $tag:literal, $from:ident, $is:ident, $as:ident;)*
+    ) => {
+        $(#[$attr])*
+        pub struct $name<$($ty: ?Sized + Erasable),*> {
+            p: NonNull<u8>,
+            phantom: PhantomData<($(Arc<$ty>,)*)>,
+        }
+
+        unsafe impl<$($ty: ?Sized + Erasable + Sync + Send),*> Send for $name<$($ty),*> {}
+        unsafe impl<$($ty: ?Sized + Erasable + Sync + Send),*> Sync for $name<$($ty),*> {}
+
+        $(#[$battr])*
+        #[derive(Debug, PartialEq, Eq, Hash)]
+        pub enum $borrow<'a, $($ty: ?Sized + 'a),*> {
+            $(
+                #[doc = concat!("An [`ArcBorrow<", stringify!($ty), ">`][`ArcBorrow`]")]
+                $variant(ArcBorrow<'a, $ty>),
+            )*
+        }
+
+        impl<'a, $($ty: ?Sized),*> Copy for $borrow<'a, $($ty),*> {}
+        impl<'a, $($ty: ?Sized),*> Clone for $borrow<'a, $($ty),*> {
+            #[inline]
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<$($ty: ?Sized + Erasable),*> $name<$($ty),*> {
+            /// Fails to compile if the data pointer of an [`Arc`] has too few spare low bits to
+            /// hold the tag
+            const TAG_FITS: () = assert!(
+                mem::align_of::<AtomicUsize>() > $mask,
+                "the alignment of `AtomicUsize` is too small to tag an `Arc` pointer"
+            );
+
+            $(
+                #[doc = concat!(
+                    "Construct a [`", stringify!($name), "`] holding an [`Arc<",
+                    stringify!($ty), ">`][`Arc`]"
+                )]
+                #[inline]
+                pub fn $from(arc: Arc<$ty>) -> Self {
+                    // Safety: the pointer comes from an `Arc`, whose ownership we take
+                    unsafe { Self::from_raw(Arc::into_raw(arc), $tag) }
+                }
+
+                #[doc = concat!(
+                    "Whether this [`", stringify!($name), "`] holds an [`Arc<",
+                    stringify!($ty), ">`][`Arc`]"
+                )]
+                #[inline]
+                pub fn $is(&self) -> bool {
+                    self.tag() == $tag
+                }
+
+                #[doc = concat!(
+                    "Borrow this [`", stringify!($name), "`] as an [`ArcBorrow<",
+                    stringify!($ty), ">`][`ArcBorrow`], if that is what it holds"
+                )]
+                #[inline]
+                pub fn $as(&self) -> Option<ArcBorrow<'_, $ty>> {
+                    match self.borrow() {
+                        $borrow::$variant(arc) => Some(arc),
+                        _ => None,
+                    }
+                }
+            )*
+
+            /// Borrow the [`Arc`] this holds, without touching the reference count, to `match` on
+            #[inline]
+            pub fn borrow(&self) -> $borrow<'_, $($ty),*> {
+                let tag = self.tag();
+                let p = self.p.as_ptr().wrapping_sub(tag);
+                // Safety: untagging the pointer gives back the data pointer of an `Arc` of the
+                //         type given by the tag, which we own, with its original provenance
+                unsafe {
+                    let p = NonNull::new_unchecked(p).cast();
+                    match tag {
+                        $($tag => $borrow::$variant(ArcBorrow {
+                            p: Erasable::unerase(p),
+                            phantom: PhantomData,
+                        }),)*
+                        _ => unreachable!(),
+                    }
+                }
+            }
+
+            /// Test pointer equality between two unions, i.e. they must hold the _same_ variant
+            /// of the _same_ allocation
+            #[inline]
+            pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+                this.p == other.p
+            }
+
+            /// Get the tag of this union
+            #[inline]
+            fn tag(&self) -> usize {
+                self.p.as_ptr() as usize & $mask
+            }
+
+            /// Tag the data pointer of an [`Arc`]
+            ///
+            /// # Safety
+            /// `p` must come from [`Arc::into_raw`], and the resulting union takes ownership of it.
+            /// `tag` must be the tag of the variant holding an `Arc<T>`.
+            #[inline]
+            unsafe fn from_raw<T: ?Sized + Erasable>(p: *const T, tag: usize) -> Self {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::TAG_FITS;
+                let p = Erasable::erase(NonNull::new_unchecked(p as *mut T)).cast::<u8>();
+                debug_assert_eq!(p.as_ptr() as usize & $mask, 0);
+                let result = $name {
+                    // Offsetting the pointer rather than casting an integer preserves provenance
+                    p: NonNull::new_unchecked(p.as_ptr().wrapping_add(tag)),
+                    phantom: PhantomData,
+                };
+                debug_assert_eq!(result.tag(), tag);
+                result
+            }
+        }
+
+        impl<$($ty: ?Sized + Erasable),*> Clone for $name<$($ty),*> {
+            #[inline]
+            fn clone(&self) -> Self {
+                match self.borrow() {
+                    $($borrow::$variant(arc) => Self::$from(ArcBorrow::clone_arc(arc)),)*
+                }
+            }
+        }
+
+        impl<$($ty: ?Sized + Erasable),*> Drop for $name<$($ty),*> {
+            #[inline]
+            fn drop(&mut self) {
+                match self.borrow() {
+                    // Safety: we own this reference, and it is never used again
+                    $($borrow::$variant(arc) => unsafe {
+                        drop(Arc::from_raw(ArcBorrow::into_raw(arc)))
+                    },)*
+                }
+            }
+        }
+
+        impl<$($ty: ?Sized + Erasable + PartialEq),*> PartialEq for $name<$($ty),*> {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                self.borrow() == other.borrow()
+            }
+        }
+
+        impl<$($ty: ?Sized + Erasable + Eq),*> Eq for $name<$($ty),*> {}
+
+        impl<$($ty: ?Sized + Erasable + Hash),*> Hash for $name<$($ty),*> {
+            #[inline]
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.borrow().hash(state)
+            }
+        }
+
+        impl<$($ty: ?Sized + Erasable + fmt::Debug),*> fmt::Debug for $name<$($ty),*> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Debug::fmt(&self.borrow(), f)
+            }
+        }
+    };
+}
+
+arc_union! {
+    /// A single-word union of an [`Arc<A>`][`Arc`] and an [`Arc<B>`][`Arc`], using the low bit of
+    /// the pointer as a tag
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Arc, ArcUnion, ArcUnionBorrow};
+    ///
+    /// let x: ArcUnion<u32, String> = ArcUnion::from_first(Arc::new(5));
+    /// let y: ArcUnion<u32, String> = ArcUnion::from_second(Arc::new("five".into()));
+    /// assert_eq!(std::mem::size_of_val(&x), std::mem::size_of::<usize>());
+    ///
+    /// match y.borrow() {
+    ///     ArcUnionBorrow::First(_) => unreachable!(),
+    ///     ArcUnionBorrow::Second(s) => assert_eq!(*s, "five"),
+    /// }
+    /// assert_eq!(*x.as_first().unwrap(), 5);
+    /// assert!(x.as_second().is_none());
+    /// assert!(ArcUnion::ptr_eq(&y, &y.clone()));
+    /// ```
+    ArcUnion;
+    /// A borrowed [`ArcUnion`]
+    ArcUnionBorrow;
+    mask = 0b1;
+    First(A) = 0, from_first, is_first, as_first;
+    Second(B) = 1, from_second, is_second, as_second;
+}
+
+arc_union! {
+    /// A single-word union of an [`Arc<A>`][`Arc`], an [`Arc<B>`][`Arc`] and an
+    /// [`Arc<C>`][`Arc`], using the low two bits of the pointer as a tag
+    ///
+    /// See [`ArcUnion`].
+    ArcUnion3;
+    /// A borrowed [`ArcUnion3`]
+    ArcUnion3Borrow;
+    mask = 0b11;
+    First(A) = 0, from_first, is_first, as_first;
+    Second(B) = 1, from_second, is_second, as_second;
+    Third(C) = 2, from_third, is_third, as_third;
+}
+
+arc_union! {
+    /// A single-word union of an [`Arc<A>`][`Arc`], an [`Arc<B>`][`Arc`], an [`Arc<C>`][`Arc`]
+    /// and an [`Arc<D>`][`Arc`], using the low two bits of the pointer as a tag
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Arc, ArcUnion4, ArcUnion4Borrow};
+    ///
+    /// enum Expr {
+    ///     Int(i64),
+    ///     Add(Node, Node),
+    /// }
+    /// struct Var(String);
+    /// struct Let(String, Node, Node);
+    ///
+    /// type Node = ArcUnion4<i64, Expr, Var, Let>;
+    ///
+    /// fn eval(node: &Node, env: &mut Vec<(String, i64)>) -> i64 {
+    ///     match node.borrow() {
+    ///         ArcUnion4Borrow::First(i) => *i,
+    ///         ArcUnion4Borrow::Second(e) => match &*e {
+    ///             Expr::Int(i) => *i,
+    ///             Expr::Add(l, r) => eval(l, env) + eval(r, env),
+    ///         },
+    ///         ArcUnion4Borrow::Third(v) => env.iter().rev().find(|(n, _)| *n == v.0).unwrap().1,
+    ///         ArcUnion4Borrow::Fourth(l) => {
+    ///             let value = eval(&l.1, env);
+    ///             env.push((l.0.clone(), value));
+    ///             let result = eval(&l.2, env);
+    ///             env.pop();
+    ///             result
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let x = Node::from_third(Arc::new(Var("x".into())));
+    /// let sum = Node::from_second(Arc::new(Expr::Add(x.clone(), x)));
+    /// let two = Node::from_first(Arc::new(2));
+    /// let expr = Node::from_fourth(Arc::new(Let("x".into(), two, sum)));
+    /// assert_eq!(eval(&expr, &mut vec![]), 4);
+    /// ```
+    ArcUnion4;
+    /// A borrowed [`ArcUnion4`]
+    ArcUnion4Borrow;
+    mask = 0b11;
+    First(A) = 0, from_first, is_first, as_first;
+    Second(B) = 1, from_second, is_second, as_second;
+    Third(C) = 2, from_third, is_third, as_third;
+    Fourth(D) = 3, from_fourth, is_fourth, as_fourth;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arc, ArcUnion, ArcUnion3, ArcUnion3Borrow};
+    use alloc::string::String;
+    use alloc::sync::Arc as StdArc;
+
+    #[test]
+    fn union_clone_drop() {
+        let canary = StdArc::new(());
+        let x = Arc::new(canary.clone());
+        let u: ArcUnion<StdArc<()>, String> = ArcUnion::from_first(x.clone());
+        let v = u.clone();
+        assert_eq!(Arc::count(&x), 3);
+        assert!(ArcUnion::ptr_eq(&u, &v));
+        assert!(u.is_first() && !u.is_second());
+        assert_eq!(u, v);
+        drop((x, u));
+        assert_eq!(StdArc::strong_count(&canary), 2);
+        drop(v);
+        assert_eq!(StdArc::strong_count(&canary), 1);
+    }
+
+    #[test]
+    fn union3_variants() {
+        let a: Arc<u8> = Arc::new(1);
+        let b: Arc<String> = Arc::new("two".into());
+        let c: Arc<[u64; 1]> = Arc::new([3]);
+        let unions: [ArcUnion3<u8, String, [u64; 1]>; 3] = [
+            ArcUnion3::from_first(a.clone()),
+            ArcUnion3::from_second(b.clone()),
+            ArcUnion3::from_third(c.clone()),
+        ];
+        for (i, u) in unions.iter().enumerate() {
+            match (i, u.borrow()) {
+                (0, ArcUnion3Borrow::First(x)) => assert_eq!(Arc::as_ptr(&a), &*x as *const u8),
+                (1, ArcUnion3Borrow::Second(x)) => assert_eq!(*x, "two"),
+                (2, ArcUnion3Borrow::Third(x)) => assert_eq!(*x, [3]),
+                _ => panic!("wrong variant"),
+            }
+        }
+        assert!(unions[2].as_third().is_some());
+        assert!(!ArcUnion3::ptr_eq(&unions[0], &unions[1]));
+        drop(unions);
+        assert!(Arc::is_unique(&a) && Arc::is_unique(&b) && Arc::is_unique(&c));
+    }
+}
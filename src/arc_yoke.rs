@@ -0,0 +1,252 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+use crate::{Allocator, Arc, ArcBorrow, Global};
+
+/// A type constructor taking a lifetime `'a` to the type [`Of<'a>`][`Yokeable::Of`], used to
+/// describe the data borrowed from the cart of an [`ArcYoke`]
+///
+/// # Safety
+/// `Of<'a>` must be covariant in `'a`, i.e. it must be sound to convert an `Of<'long>` into an
+/// `Of<'short>`. This is the case for types like `&'a str` and `Vec<&'a str>`, but not for
+/// `Cell<&'a str>` or `fn(&'a str)`.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::Yokeable;
+///
+/// /// The words of a string
+/// struct Words;
+///
+/// // Safety: `Vec<&'a str>` is covariant in `'a`
+/// unsafe impl Yokeable for Words {
+///     type Of<'a> = Vec<&'a str>;
+/// }
+/// ```
+pub unsafe trait Yokeable {
+    /// The type for the lifetime `'a`
+    type Of<'a>;
+}
+
+/// An [`Arc<T>`][`Arc`], called the cart, along with a value of type `D::Of<'a>` borrowing from it
+///
+/// This allows storing zero-copy views of shared data, such as a list of words borrowed from an
+/// [`Arc<str>`][`Arc`], alongside it without an external self-referential crate. This is sound
+/// since the data of an [`Arc`] never moves, even when the [`Arc`] itself does (see
+/// `StableDeref`).
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, ArcYoke, Yokeable};
+///
+/// struct Words;
+///
+/// unsafe impl Yokeable for Words {
+///     type Of<'a> = Vec<&'a str>;
+/// }
+///
+/// let text: Arc<str> = Arc::from("the quick brown fox");
+/// let words: ArcYoke<str, Words> = ArcYoke::attach(text, |t| t.split(' ').collect());
+/// assert_eq!(words.get().len(), 4);
+///
+/// let longest = words.clone().map_project::<Words, _>(|mut w, _| {
+///     w.retain(|w| w.len() == 5);
+///     w
+/// });
+/// assert_eq!(longest.get().join(" "), "quick brown");
+/// assert_eq!(Arc::count(ArcYoke::backing_cart(&words)), 2);
+/// ```
+pub struct ArcYoke<T: ?Sized, D: Yokeable, A: Allocator = Global> {
+    // Declared before `cart` so that it is dropped first
+    data: D::Of<'static>,
+    cart: Arc<T, A>,
+}
+
+impl<T: ?Sized, D: Yokeable, A: Allocator> ArcYoke<T, D, A> {
+    /// Construct an [`ArcYoke`] from a cart and a function computing the data borrowed from it
+    #[inline]
+    pub fn attach<F>(cart: Arc<T, A>, f: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a T) -> D::Of<'a>,
+    {
+        // Safety: the data is borrowed from the data of `cart`, which lives at least as long as
+        //         `cart` does, and never moves
+        let data = unsafe { erase_lifetime::<D>(f(&*(Arc::as_ptr(&cart)))) };
+        ArcYoke { data, cart }
+    }
+
+    /// Try to construct an [`ArcYoke`] from a cart and a fallible function computing the data
+    /// borrowed from it
+    #[inline]
+    pub fn try_attach<F, E>(cart: Arc<T, A>, f: F) -> Result<Self, E>
+    where
+        F: for<'a> FnOnce(&'a T) -> Result<D::Of<'a>, E>,
+    {
+        // Safety: see `attach`
+        let data = unsafe { erase_lifetime::<D>(f(&*(Arc::as_ptr(&cart)))?) };
+        Ok(ArcYoke { data, cart })
+    }
+
+    /// Get a reference to the borrowed data
+    #[inline]
+    pub fn get(&self) -> &D::Of<'_> {
+        // Safety: the lifetime of `self` is no longer than that of the cart, and `D::Of` is
+        //         covariant
+        unsafe { shorten_lifetime::<D>(&self.data) }
+    }
+
+    /// Get the cart as an [`ArcBorrow`], without touching the reference count
+    #[inline]
+    pub fn cart(&self) -> ArcBorrow<'_, T, A> {
+        Arc::borrow_arc(&self.cart)
+    }
+
+    /// Get a reference to the cart
+    #[inline]
+    pub fn backing_cart(this: &Self) -> &Arc<T, A> {
+        &this.cart
+    }
+
+    /// Drop the borrowed data, returning the cart
+    #[inline]
+    pub fn into_cart(self) -> Arc<T, A> {
+        let this = ManuallyDrop::new(self);
+        // Safety: `this` is never used again, and we drop the data before moving out the cart
+        unsafe {
+            ptr::drop_in_place(&this.data as *const D::Of<'static> as *mut D::Of<'static>);
+            ptr::read(&this.cart)
+        }
+    }
+
+    /// Transform the borrowed data, keeping the same cart
+    ///
+    /// The [`PhantomData`] argument to `f` ties its lifetime to that of the data, and may be
+    /// ignored.
+    #[inline]
+    pub fn map_project<E, F>(self, f: F) -> ArcYoke<T, E, A>
+    where
+        E: Yokeable,
+        F: for<'a> FnOnce(D::Of<'a>, PhantomData<&'a ()>) -> E::Of<'a>,
+    {
+        let this = ManuallyDrop::new(self);
+        // Safety: we move both fields out of `this`, which is never used again. The new data is
+        //         borrowed from the same cart, as `f` is generic over the lifetime of the data.
+        unsafe {
+            let data = ptr::read(&this.data);
+            let cart = ptr::read(&this.cart);
+            let data = erase_lifetime::<E>(f(data, PhantomData));
+            ArcYoke { data, cart }
+        }
+    }
+
+    /// Transform the borrowed data, keeping the same cart, or return an error
+    ///
+    /// The cart is dropped on error.
+    #[inline]
+    pub fn try_map_project<E, F, Err>(self, f: F) -> Result<ArcYoke<T, E, A>, Err>
+    where
+        E: Yokeable,
+        F: for<'a> FnOnce(D::Of<'a>, PhantomData<&'a ()>) -> Result<E::Of<'a>, Err>,
+    {
+        let this = ManuallyDrop::new(self);
+        // Safety: see `map_project`
+        unsafe {
+            let data = ptr::read(&this.data);
+            let cart = ptr::read(&this.cart);
+            let data = erase_lifetime::<E>(f(data, PhantomData)?);
+            Ok(ArcYoke { data, cart })
+        }
+    }
+}
+
+impl<T: ?Sized, D: Yokeable, A: Allocator> Clone for ArcYoke<T, D, A>
+where
+    for<'a> D::Of<'a>: Clone,
+{
+    /// Clone the cart, bumping its reference count, along with the borrowed data
+    #[inline]
+    fn clone(&self) -> Self {
+        let data = self.get().clone();
+        // Safety: the cloned data borrows from the same cart, which we clone
+        let data = unsafe { erase_lifetime::<D>(data) };
+        ArcYoke {
+            data,
+            cart: self.cart.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized, D: Yokeable, A: Allocator> fmt::Debug for ArcYoke<T, D, A>
+where
+    for<'a> D::Of<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.get(), f)
+    }
+}
+
+/// Extend the lifetime of some borrowed data to `'static`
+///
+/// # Safety
+/// The result must not be used after the data it borrows from is freed, and may only be accessed
+/// after shortening its lifetime again
+#[inline]
+unsafe fn erase_lifetime<'a, D: Yokeable>(data: D::Of<'a>) -> D::Of<'static> {
+    let data = ManuallyDrop::new(data);
+    // `D::Of<'a>` and `D::Of<'static>` only differ in lifetimes, and so have the same layout
+    ptr::read((&*data as *const D::Of<'a>).cast::<D::Of<'static>>())
+}
+
+/// Shorten the lifetime of some borrowed data stored with [`erase_lifetime`]
+///
+/// # Safety
+/// The data must live at least as long as `'a`
+#[inline]
+unsafe fn shorten_lifetime<'a, D: Yokeable>(data: &'a D::Of<'static>) -> &'a D::Of<'a> {
+    &*(data as *const D::Of<'static>).cast::<D::Of<'a>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    struct Words;
+
+    unsafe impl Yokeable for Words {
+        type Of<'a> = Vec<&'a str>;
+    }
+
+    struct FirstWord;
+
+    unsafe impl Yokeable for FirstWord {
+        type Of<'a> = &'a str;
+    }
+
+    #[test]
+    fn attach_and_project() {
+        let text: Arc<str> = Arc::from("hello yoked world");
+        let words: ArcYoke<str, Words> = ArcYoke::attach(text.clone(), |t| t.split(' ').collect());
+        let first: ArcYoke<str, FirstWord> = words.clone().map_project(|w, _| w[0]);
+        assert_eq!(*first.get(), "hello");
+        assert_eq!(ArcBorrow::count(first.cart()), 3);
+        assert!(ArcBorrow::ptr_eq(first.cart(), Arc::borrow_arc(&text)));
+        drop(words);
+        assert!(Arc::ptr_eq(&first.into_cart(), &text));
+        assert!(Arc::is_unique(&text));
+    }
+
+    #[test]
+    fn try_attach() {
+        let bytes: Arc<[u8]> = Arc::from(&b"\xff"[..]);
+        let result: Result<ArcYoke<[u8], Words>, _> = ArcYoke::try_attach(bytes.clone(), |b| {
+            core::str::from_utf8(b).map(|s| alloc::vec![s])
+        });
+        assert!(result.is_err());
+        assert!(Arc::is_unique(&bytes));
+    }
+}
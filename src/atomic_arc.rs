@@ -0,0 +1,834 @@
+use alloc::boxed::Box;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release, SeqCst};
+use erasable::Erasable;
+
+#[cfg(not(loom))]
+use core::sync::atomic::{fence, AtomicBool, AtomicPtr};
+#[cfg(loom)]
+use loom::sync::atomic::{fence, AtomicBool, AtomicPtr};
+
+use crate::{Allocator, Arc, ArcBorrow, Global};
+
+/// An atomic cell holding an [`Option<Arc<T>>`][`Arc`], which can be loaded from and replaced
+/// concurrently
+///
+/// # Algorithm
+///
+/// Besides the [`Arc`] pointer, the cell owns a list of *debt slots*, in the manner of hazard
+/// pointers. A reader claims a free slot, records the pointer it has loaded there, and then checks
+/// that the cell still holds the same pointer. If so, it has borrowed the value without touching
+/// its reference count: it owes the cell a reference, which it settles by clearing its slot.
+///
+/// A writer which removes a value from the cell scans the slots, and pays every debt on that value
+/// by incrementing its reference count on the borrower's behalf and clearing the slot. A borrower
+/// which finds its slot already cleared therefore owns a reference, which it drops once done with
+/// the value. A pair of `SeqCst` fences guarantees that either the writer sees the debt, or the
+/// reader sees that the value was removed and tries again.
+///
+/// # Progress
+///
+/// Every operation is lock-free. Replacing the value is a single atomic operation on the cell,
+/// followed by a scan of the slots. A reader only tries again when the value was replaced while it
+/// was recording its debt, in which case the writer made progress, and claims a slot by trying
+/// each of the existing ones once before allocating a new one. The list of slots is only freed
+/// with the cell, and grows to the largest number of loads and guards held at once.
+///
+/// # Unsized values
+///
+/// The cell stores a thin pointer, so `T` may be any [`Erasable`] type, such as a
+/// [`HeaderSlice`][`crate::HeaderSlice`] whose length is stored in the allocation. `Arc<str>` and
+/// `Arc<[T]>` do not store their length in the allocation, and so still need a thin wrapper, such
+/// as an `Arc<HeaderSlice<(), [T]>>`.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, AtomicOptionArc};
+///
+/// let cell = AtomicOptionArc::new(Some(Arc::new(5)));
+/// let five = cell.load().unwrap();
+/// assert_eq!(cell.swap(Some(Arc::new(6))), Some(five));
+/// assert_eq!(*cell.load_guard().unwrap(), 6);
+/// let six = cell.take().unwrap();
+/// assert!(cell.load().is_none());
+/// assert!(Arc::is_unique(&six));
+/// ```
+pub struct AtomicOptionArc<T: ?Sized + Erasable, A: Allocator = Global> {
+    /// The erased data pointer of the current value, or null if there is none
+    p: AtomicPtr<u8>,
+    /// The head of the list of debt slots
+    debts: AtomicPtr<Debt>,
+    phantom: PhantomData<Option<Arc<T, A>>>,
+}
+
+unsafe impl<T: ?Sized + Erasable + Sync + Send, A: Allocator + Sync + Send> Send
+    for AtomicOptionArc<T, A>
+{
+}
+unsafe impl<T: ?Sized + Erasable + Sync + Send, A: Allocator + Sync + Send> Sync
+    for AtomicOptionArc<T, A>
+{
+}
+
+/// An atomic cell holding an [`Arc<T>`][`Arc`], which can be loaded from and replaced concurrently
+///
+/// This is an [`AtomicOptionArc`] which is never empty; see it for details.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, AtomicArc};
+///
+/// let config = AtomicArc::new(Arc::new(String::from("v1")));
+/// let old = config.load();
+/// let new = Arc::new(String::from("v2"));
+/// assert!(config.compare_exchange(Arc::as_ptr(&old), new.clone()).is_ok());
+/// assert_eq!(*config.load_guard(), "v2");
+/// assert_eq!(config.compare_exchange(Arc::as_ptr(&old), new), Err(Arc::new("v2".into())));
+/// ```
+pub struct AtomicArc<T: ?Sized + Erasable, A: Allocator = Global> {
+    inner: AtomicOptionArc<T, A>,
+}
+
+/// An [`Arc`] loaded from an [`AtomicArc`] or [`AtomicOptionArc`] with
+/// [`load_guard`][`AtomicArc::load_guard`]
+///
+/// This holds a debt slot of the cell rather than a reference, and so only writes to the reference
+/// count of the value if it is removed from the cell while the guard is held. Each guard occupies
+/// a slot until it is dropped, so it should be short-lived.
+pub struct AtomicArcGuard<'a, T: ?Sized + Erasable, A: Allocator = Global> {
+    debt: &'a Debt,
+    p: NonNull<u8>,
+    phantom: PhantomData<&'a AtomicOptionArc<T, A>>,
+}
+
+/// A debt slot of an [`AtomicOptionArc`]
+struct Debt {
+    /// The erased data pointer of the value borrowed by the holder of this slot, or null if it has
+    /// no debt
+    p: AtomicPtr<u8>,
+    /// Whether this slot is held
+    held: AtomicBool,
+    /// The next slot, which never changes once this slot is in the list
+    next: *mut Debt,
+}
+
+impl Debt {
+    /// Try to claim this slot
+    #[inline]
+    fn claim(&self) -> bool {
+        !self.held.load(Relaxed)
+            && self
+                .held
+                .compare_exchange(false, true, Acquire, Relaxed)
+                .is_ok()
+    }
+
+    /// Settle the debt of this slot on `p` and release the slot, returning whether a writer paid
+    /// the debt, in which case the caller owns a reference to `p`
+    #[inline]
+    fn settle(&self, p: *mut u8) -> bool {
+        let paid = self
+            .p
+            .compare_exchange(p, ptr::null_mut(), AcqRel, Acquire)
+            .is_err();
+        self.held.store(false, Release);
+        paid
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> AtomicOptionArc<T, A> {
+    /// Create an empty [`AtomicOptionArc`]
+    #[cfg(not(loom))]
+    #[inline]
+    pub const fn none() -> Self {
+        AtomicOptionArc {
+            p: AtomicPtr::new(ptr::null_mut()),
+            debts: AtomicPtr::new(ptr::null_mut()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Create an empty [`AtomicOptionArc`]
+    // loom's atomics cannot be created in a `const fn`
+    #[cfg(loom)]
+    pub fn none() -> Self {
+        AtomicOptionArc {
+            p: AtomicPtr::new(ptr::null_mut()),
+            debts: AtomicPtr::new(ptr::null_mut()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Create a new [`AtomicOptionArc`]
+    #[inline]
+    pub fn new(arc: Option<Arc<T, A>>) -> Self {
+        AtomicOptionArc {
+            p: AtomicPtr::new(Self::into_data(arc)),
+            debts: AtomicPtr::new(ptr::null_mut()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Load the current value, incrementing its reference count
+    #[inline]
+    pub fn load(&self) -> Option<Arc<T, A>> {
+        self.load_guard().map(AtomicArcGuard::into_arc)
+    }
+
+    /// Load the current value as a guard, which usually does not touch its reference count
+    #[inline]
+    pub fn load_guard(&self) -> Option<AtomicArcGuard<'_, T, A>> {
+        let mut p = self.p.load(Acquire);
+        if p.is_null() {
+            return None;
+        }
+        let debt = self.claim_debt();
+        loop {
+            // Release our previous uses of this slot, so that a writer which sees this debt also
+            // sees that they have been settled
+            debt.p.store(p, Release);
+            // Pairs with the fence in `pay_debts`: either the writer removing `p` sees our debt, or
+            // we see that it was removed
+            fence(SeqCst);
+            let current = self.p.load(Acquire);
+            if current == p {
+                return Some(AtomicArcGuard {
+                    debt,
+                    // Safety: `p` is the non-null erased data pointer of an `Arc`
+                    p: unsafe { NonNull::new_unchecked(p) },
+                    phantom: PhantomData,
+                });
+            }
+            if debt
+                .p
+                .compare_exchange(p, ptr::null_mut(), AcqRel, Acquire)
+                .is_err()
+            {
+                // Safety: a writer paid our debt, so we own a reference we no longer need
+                drop(unsafe { Self::from_data(p) });
+            }
+            if current.is_null() {
+                debt.held.store(false, Release);
+                return None;
+            }
+            p = current;
+        }
+    }
+
+    /// Store a new value, dropping the old one
+    #[inline]
+    pub fn store(&self, arc: Option<Arc<T, A>>) {
+        drop(self.swap(arc))
+    }
+
+    /// Store a new value, returning the old one
+    #[inline]
+    pub fn swap(&self, arc: Option<Arc<T, A>>) -> Option<Arc<T, A>> {
+        let old = self.p.swap(Self::into_data(arc), AcqRel);
+        // Safety: the cell's reference to the old value is transferred to us
+        unsafe {
+            self.pay_debts(old);
+            Self::from_data(old)
+        }
+    }
+
+    /// Store a new value if the current value has data pointer `current`, which is null for
+    /// `None`
+    ///
+    /// Only the address of `current` is compared, so any metadata it carries is ignored.
+    ///
+    /// Returns the old value on success, or gives back `new` on failure.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub fn compare_exchange(
+        &self,
+        current: *const T,
+        new: Option<Arc<T, A>>,
+    ) -> Result<Option<Arc<T, A>>, Option<Arc<T, A>>> {
+        let current = NonNull::new(current as *mut T)
+            .map_or(ptr::null_mut(), |p| T::erase(p).cast().as_ptr());
+        let new = Self::into_data(new);
+        match self.p.compare_exchange(current, new, AcqRel, Acquire) {
+            // Safety: the cell's reference to the old value is transferred to us
+            Ok(old) => unsafe {
+                self.pay_debts(old);
+                Ok(Self::from_data(old))
+            },
+            // Safety: `new` was never stored, so we still own it
+            Err(_) => Err(unsafe { Self::from_data(new) }),
+        }
+    }
+
+    /// Take the current value, leaving the cell empty
+    #[inline]
+    pub fn take(&self) -> Option<Arc<T, A>> {
+        self.swap(None)
+    }
+
+    /// Get the current value, consuming the cell
+    #[inline]
+    pub fn into_inner(self) -> Option<Arc<T, A>> {
+        let mut this = mem::ManuallyDrop::new(self);
+        this.free_debts();
+        let p = get_mut(&mut this.p);
+        // Safety: the cell owns a reference, and is never used again
+        unsafe { Self::from_data(p) }
+    }
+
+    /// Claim a free debt slot, adding a new one to the list if there is none
+    #[inline]
+    fn claim_debt(&self) -> &Debt {
+        let mut head = self.debts.load(Acquire);
+        let mut debt = head;
+        // Safety: slots are only freed along with the cell
+        while let Some(d) = unsafe { debt.as_ref() } {
+            if d.claim() {
+                return d;
+            }
+            debt = d.next;
+        }
+        let debt = Box::into_raw(Box::new(Debt {
+            p: AtomicPtr::new(ptr::null_mut()),
+            held: AtomicBool::new(true),
+            next: head,
+        }));
+        // Safety: `debt` is not yet shared, so we may write to its `next` field, and once it is
+        //         in the list it is only freed along with the cell
+        unsafe {
+            while let Err(current) = self
+                .debts
+                .compare_exchange_weak(head, debt, Release, Acquire)
+            {
+                head = current;
+                (*debt).next = head;
+            }
+            &*debt
+        }
+    }
+
+    /// Pay every debt on the value with erased data pointer `old`, which was just removed from the
+    /// cell
+    ///
+    /// # Safety
+    /// `old` must be null, or the erased data pointer of an `Arc` whose reference we own
+    #[inline]
+    unsafe fn pay_debts(&self, old: *mut u8) {
+        if old.is_null() {
+            return;
+        }
+        // Pairs with the fence in `load_guard`
+        fence(SeqCst);
+        let mut debt = self.debts.load(Acquire);
+        while let Some(d) = debt.as_ref() {
+            // Acquire any debt settled on `old`, so that the borrower's last use of it happens
+            // before we drop it
+            if d.p.load(Acquire) == old {
+                // Increment the reference count before clearing the slot, so that the borrower
+                // never owns a reference which does not exist yet
+                mem::forget(ArcBorrow::clone_arc(Self::borrow_data(old)));
+                if d.p
+                    .compare_exchange(old, ptr::null_mut(), AcqRel, Acquire)
+                    .is_err()
+                {
+                    // The borrower settled its debt first
+                    drop(Self::from_data(old));
+                }
+            }
+            debt = d.next;
+        }
+    }
+
+    /// Free the list of debt slots, given exclusive access to the cell
+    #[inline]
+    fn free_debts(&mut self) {
+        let mut debt = get_mut(&mut self.debts);
+        while !debt.is_null() {
+            // Safety: no guard borrows the cell, so no slot is held, and we never use it again
+            let mut d = unsafe { Box::from_raw(debt) };
+            debug_assert!(get_mut(&mut d.p).is_null());
+            debt = d.next;
+        }
+    }
+
+    #[inline]
+    fn into_data(arc: Option<Arc<T, A>>) -> *mut u8 {
+        arc.map_or(ptr::null_mut(), |arc| {
+            // Safety: the pointer of an `Arc` is never null
+            T::erase(unsafe { NonNull::new_unchecked(Arc::into_raw(arc) as *mut T) })
+                .cast()
+                .as_ptr()
+        })
+    }
+
+    /// # Safety
+    /// `p` must be the erased data pointer of an `Arc` which is kept alive for `'b`
+    #[inline]
+    unsafe fn borrow_data<'b>(p: *mut u8) -> ArcBorrow<'b, T, A> {
+        ArcBorrow {
+            p: T::unerase(NonNull::new_unchecked(p).cast()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// # Safety
+    /// `p` must be null, or the erased data pointer of an `Arc` whose reference we take
+    #[inline]
+    unsafe fn from_data(p: *mut u8) -> Option<Arc<T, A>> {
+        NonNull::new(p).map(|p| Arc::from_raw_in(T::unerase(p.cast()).as_ptr()))
+    }
+}
+
+/// Get the value of an atomic pointer, given exclusive access to it
+#[inline]
+fn get_mut<T>(p: &mut AtomicPtr<T>) -> *mut T {
+    #[cfg(not(loom))]
+    return *p.get_mut();
+    #[cfg(loom)]
+    return p.with_mut(|p| *p);
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> AtomicArc<T, A> {
+    /// Create a new [`AtomicArc`]
+    #[inline]
+    pub fn new(arc: Arc<T, A>) -> Self {
+        AtomicArc {
+            inner: AtomicOptionArc::new(Some(arc)),
+        }
+    }
+
+    /// Load the current value, incrementing its reference count
+    #[inline]
+    pub fn load(&self) -> Arc<T, A> {
+        never_empty(self.inner.load())
+    }
+
+    /// Load the current value as a guard, which usually does not touch its reference count
+    #[inline]
+    pub fn load_guard(&self) -> AtomicArcGuard<'_, T, A> {
+        never_empty(self.inner.load_guard())
+    }
+
+    /// Store a new value, dropping the old one
+    #[inline]
+    pub fn store(&self, arc: Arc<T, A>) {
+        self.inner.store(Some(arc))
+    }
+
+    /// Store a new value, returning the old one
+    #[inline]
+    pub fn swap(&self, arc: Arc<T, A>) -> Arc<T, A> {
+        never_empty(self.inner.swap(Some(arc)))
+    }
+
+    /// Store a new value if the current value has data pointer `current`
+    ///
+    /// Returns the old value on success, or gives back `new` on failure.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub fn compare_exchange(
+        &self,
+        current: *const T,
+        new: Arc<T, A>,
+    ) -> Result<Arc<T, A>, Arc<T, A>> {
+        match self.inner.compare_exchange(current, Some(new)) {
+            Ok(old) => Ok(never_empty(old)),
+            Err(new) => Err(never_empty(new)),
+        }
+    }
+
+    /// Take the current value, leaving the default value in its place
+    #[inline]
+    pub fn take(&self) -> Arc<T, A>
+    where
+        Arc<T, A>: Default,
+    {
+        self.swap(Arc::default())
+    }
+
+    /// Get the current value, consuming the cell
+    #[inline]
+    pub fn into_inner(self) -> Arc<T, A> {
+        never_empty(self.inner.into_inner())
+    }
+}
+
+#[inline]
+fn never_empty<T>(value: Option<T>) -> T {
+    match value {
+        Some(value) => value,
+        None => unreachable!("an `AtomicArc` is never empty"),
+    }
+}
+
+impl<'a, T: ?Sized + Erasable, A: Allocator> AtomicArcGuard<'a, T, A> {
+    /// Borrow the loaded value as an [`ArcBorrow`]
+    #[inline]
+    pub fn borrow_arc(this: &Self) -> ArcBorrow<'_, T, A> {
+        // Safety: our debt, or the reference paid for it, keeps the value alive
+        unsafe { AtomicOptionArc::borrow_data(this.p.as_ptr()) }
+    }
+
+    /// Convert this guard into an [`Arc`], incrementing the reference count
+    #[inline]
+    pub fn into_arc(this: Self) -> Arc<T, A> {
+        ArcBorrow::clone_arc(AtomicArcGuard::borrow_arc(&this))
+    }
+}
+
+impl<'a, T: ?Sized + Erasable, A: Allocator> Drop for AtomicArcGuard<'a, T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.debt.settle(self.p.as_ptr()) {
+            // Safety: a writer paid our debt, so we own a reference, and never use it again
+            drop(unsafe { AtomicOptionArc::<T, A>::from_data(self.p.as_ptr()) })
+        }
+    }
+}
+
+impl<'a, T: ?Sized + Erasable, A: Allocator> Deref for AtomicArcGuard<'a, T, A> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // Safety: our debt, or the reference paid for it, keeps the value alive
+        unsafe { T::unerase(self.p.cast()).as_ref() }
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> Drop for AtomicOptionArc<T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        self.free_debts();
+        let p = get_mut(&mut self.p);
+        // Safety: the cell owns a reference, and is never used again
+        drop(unsafe { Self::from_data(p) })
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> Default for AtomicOptionArc<T, A> {
+    #[inline]
+    fn default() -> Self {
+        AtomicOptionArc::none()
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> Default for AtomicArc<T, A>
+where
+    Arc<T, A>: Default,
+{
+    #[inline]
+    fn default() -> Self {
+        AtomicArc::new(Arc::default())
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> From<Option<Arc<T, A>>> for AtomicOptionArc<T, A> {
+    #[inline]
+    fn from(arc: Option<Arc<T, A>>) -> Self {
+        AtomicOptionArc::new(arc)
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> From<Arc<T, A>> for AtomicOptionArc<T, A> {
+    #[inline]
+    fn from(arc: Arc<T, A>) -> Self {
+        AtomicOptionArc::new(Some(arc))
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> From<Arc<T, A>> for AtomicArc<T, A> {
+    #[inline]
+    fn from(arc: Arc<T, A>) -> Self {
+        AtomicArc::new(arc)
+    }
+}
+
+impl<T: ?Sized + Erasable + fmt::Debug, A: Allocator> fmt::Debug for AtomicOptionArc<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AtomicOptionArc")
+            .field(&self.load_guard())
+            .finish()
+    }
+}
+
+impl<T: ?Sized + Erasable + fmt::Debug, A: Allocator> fmt::Debug for AtomicArc<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AtomicArc")
+            .field(&self.load_guard())
+            .finish()
+    }
+}
+
+impl<'a, T: ?Sized + Erasable + fmt::Debug, A: Allocator> fmt::Debug for AtomicArcGuard<'a, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use crate::{Arc, AtomicArc, AtomicArcGuard, AtomicOptionArc, HeaderSlice, ThinArc};
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn atomic_ops() {
+        let x = Arc::new(1);
+        let y = Arc::new(2);
+        let cell = AtomicArc::new(x.clone());
+        assert_eq!(Arc::count(&x), 2);
+        assert!(Arc::ptr_eq(&cell.load(), &x));
+        assert_eq!(
+            cell.compare_exchange(Arc::as_ptr(&y), y.clone()),
+            Err(y.clone())
+        );
+        let old = cell.compare_exchange(Arc::as_ptr(&x), y.clone()).unwrap();
+        assert!(Arc::ptr_eq(&old, &x));
+        drop(old);
+        assert!(Arc::is_unique(&x));
+        assert_eq!(Arc::count(&y), 2);
+        cell.store(x.clone());
+        assert!(Arc::is_unique(&y));
+        assert!(Arc::ptr_eq(&cell.take(), &x));
+        assert_eq!(*cell.load_guard(), 0);
+        assert!(Arc::is_unique(&x));
+
+        let cell = AtomicOptionArc::from(x.clone());
+        assert_eq!(cell.compare_exchange(core::ptr::null(), None), Err(None));
+        assert_eq!(cell.take(), Some(x.clone()));
+        assert!(cell
+            .compare_exchange(core::ptr::null(), Some(y.clone()))
+            .unwrap()
+            .is_none());
+        assert!(Arc::ptr_eq(&cell.into_inner().unwrap(), &y));
+        assert!(Arc::is_unique(&x) && Arc::is_unique(&y));
+    }
+
+    #[test]
+    fn many_guards() {
+        let x = Arc::new(5u8);
+        let cell = AtomicArc::new(x.clone());
+        let guards: Vec<_> = (0..100).map(|_| cell.load_guard()).collect();
+        // Each guard holds a debt slot rather than a reference
+        assert_eq!(Arc::count(&x), 2);
+        assert!(Arc::ptr_eq(&cell.load(), &x));
+        // Swapping the value out pays every debt on it
+        let y = cell.swap(Arc::new(6));
+        assert_eq!(Arc::count(&x), 102);
+        assert_eq!(*cell.load_guard(), 6);
+        assert!(guards.iter().all(|g| **g == 5));
+        let arcs: Vec<_> = guards.into_iter().map(AtomicArcGuard::into_arc).collect();
+        assert_eq!(Arc::count(&x), 102);
+        drop((arcs, y));
+        assert!(Arc::is_unique(&x));
+    }
+
+    #[test]
+    fn unsized_values() {
+        let x: Arc<HeaderSlice<String, [u32]>> =
+            Arc::from_header_and_slice(String::from("x"), &[1, 2, 3]);
+        let y = ThinArc::from_header_and_slice(String::from("y"), &[4, 5]);
+        let cell = AtomicArc::new(x.clone());
+        {
+            let guard = cell.load_guard();
+            assert_eq!(guard.header, "x");
+            assert_eq!(guard.slice, [1, 2, 3]);
+        }
+        let old = cell
+            .compare_exchange(Arc::as_ptr(&x), ThinArc::into_arc(y.clone()))
+            .unwrap();
+        assert!(Arc::ptr_eq(&old, &x));
+        drop(old);
+        assert!(Arc::is_unique(&x));
+        assert_eq!(cell.load().slice, [4, 5]);
+        assert!(ThinArc::ptr_eq(
+            &ThinArc::from_arc(cell.swap(x.clone())),
+            &y
+        ));
+        assert_eq!(ThinArc::count(&y), 1);
+        drop(cell);
+
+        let cell = AtomicOptionArc::new(Some(x.clone()));
+        let guards: Vec<_> = (0..10).map(|_| cell.load_guard().unwrap()).collect();
+        assert_eq!(cell.take(), Some(x.clone()));
+        assert_eq!(Arc::count(&x), 11);
+        assert!(guards.iter().all(|g| g.slice == [1, 2, 3]));
+        drop(guards);
+        assert!(cell.load().is_none());
+        assert!(Arc::is_unique(&x));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn concurrent_swaps() {
+        use std::sync::Arc as StdArc;
+        use std::thread;
+
+        const THREADS: usize = 4;
+        const ITERATIONS: usize = if cfg!(miri) { 20 } else { 10_000 };
+        let canary = StdArc::new(());
+        let cell = StdArc::new(AtomicOptionArc::new(Some(Arc::new(canary.clone()))));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|i| {
+                let cell = cell.clone();
+                let canary = canary.clone();
+                thread::spawn(move || {
+                    for j in 0..ITERATIONS {
+                        match (i + j) % 4 {
+                            0 => drop(cell.swap(Some(Arc::new(canary.clone())))),
+                            1 => drop(cell.load()),
+                            2 => {
+                                let current = cell.load_guard().map(|g| {
+                                    assert_eq!(StdArc::as_ptr(&g), StdArc::as_ptr(&canary));
+                                    AtomicArcGuard::into_arc(g)
+                                });
+                                let current =
+                                    current.as_ref().map_or(core::ptr::null(), Arc::as_ptr);
+                                drop(cell.compare_exchange(current, None));
+                            }
+                            _ => drop(cell.compare_exchange(
+                                core::ptr::null(),
+                                Some(Arc::new(canary.clone())),
+                            )),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        cell.store(None);
+        assert_eq!(StdArc::strong_count(&canary), 1);
+    }
+}
+
+/// Model checks of the debt protocol, run with
+/// `RUSTFLAGS="--cfg loom" cargo test --release --lib atomic_arc::loom_tests`
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use crate::{Arc, AtomicArc, AtomicArcGuard, AtomicOptionArc};
+    use loom::cell::UnsafeCell;
+    use loom::sync::Arc as LoomArc;
+    use loom::thread;
+
+    fn model(f: impl Fn() + Sync + Send + 'static) {
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound.get_or_insert(3);
+        builder.check(f)
+    }
+
+    #[test]
+    fn swap_load() {
+        model(|| {
+            let x = Arc::new(1);
+            let y = Arc::new(2);
+            let cell = LoomArc::new(AtomicArc::new(x.clone()));
+            let writer = {
+                let (cell, y) = (cell.clone(), y.clone());
+                thread::spawn(move || assert_eq!(*cell.swap(y), 1))
+            };
+            let loaded = cell.load();
+            let guard = cell.load_guard();
+            assert!(*loaded == 1 || *loaded == 2);
+            assert!(*guard >= *loaded);
+            drop(guard);
+            writer.join().unwrap();
+            drop(loaded);
+            assert!(Arc::is_unique(&x));
+            assert_eq!(Arc::count(&y), 2);
+        })
+    }
+
+    #[test]
+    fn compare_exchange_load_guard() {
+        model(|| {
+            let x = Arc::new(1);
+            let y = Arc::new(2);
+            let cell = LoomArc::new(AtomicArc::new(x.clone()));
+            let writer = {
+                let (cell, x, y) = (cell.clone(), x.clone(), y.clone());
+                thread::spawn(move || {
+                    let old = cell.compare_exchange(Arc::as_ptr(&x), y).unwrap();
+                    assert!(Arc::ptr_eq(&old, &x));
+                })
+            };
+            let guard = cell.load_guard();
+            let arc = AtomicArcGuard::into_arc(cell.load_guard());
+            assert!(*arc >= *guard);
+            drop(guard);
+            writer.join().unwrap();
+            assert!(Arc::ptr_eq(&cell.load(), &y));
+            drop(arc);
+            assert!(Arc::is_unique(&x));
+            assert_eq!(Arc::count(&y), 2);
+        })
+    }
+
+    /// A value is swapped out and stored back while a guard on it may be held
+    #[test]
+    fn aba() {
+        model(|| {
+            let x = Arc::new(1);
+            let cell = LoomArc::new(AtomicArc::new(x.clone()));
+            let writer = {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    let old = cell.swap(Arc::new(2));
+                    cell.store(old);
+                })
+            };
+            let guard = cell.load_guard();
+            let loaded = cell.load();
+            assert!(*guard == 1 || *guard == 2);
+            drop(guard);
+            writer.join().unwrap();
+            assert!(*loaded == 1 || *loaded == 2);
+            drop(loaded);
+            assert_eq!(Arc::count(&x), 2);
+            drop(cell);
+            assert!(Arc::is_unique(&x));
+        })
+    }
+
+    /// A value is only owned by the cell, which drops it while it may be borrowed. loom reports
+    /// the access in `Drop` if it may race with a borrower's.
+    #[test]
+    fn drop_while_borrowed() {
+        struct Value(UnsafeCell<usize>);
+
+        impl Drop for Value {
+            fn drop(&mut self) {
+                self.0.with_mut(|v| unsafe { *v = 0 })
+            }
+        }
+
+        model(|| {
+            let cell = LoomArc::new(AtomicOptionArc::new(Some(Arc::new(Value(
+                UnsafeCell::new(1),
+            )))));
+            let writer = {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    cell.store(Some(Arc::new(Value(UnsafeCell::new(2)))));
+                    cell.store(None);
+                })
+            };
+            if let Some(guard) = cell.load_guard() {
+                let v = guard.0.with(|v| unsafe { *v });
+                assert!(v == 1 || v == 2);
+            }
+            if let Some(arc) = cell.load() {
+                let v = arc.0.with(|v| unsafe { *v });
+                assert!(v == 1 || v == 2);
+            }
+            writer.join().unwrap();
+        })
+    }
+}
@@ -0,0 +1,235 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use erasable::Erasable;
+
+use crate::{Allocator, Arc, ArcBorrow, ArcRef};
+
+/// A pointer with an address, which [`ByAddress`] uses to compare and hash it
+///
+/// For the pointers in this crate, this is the address of the data, as returned by e.g.
+/// [`Arc::as_ptr`], with any metadata discarded, so that an [`Arc`], an [`ArcBorrow`] and an
+/// [`ArcRef`] to the same allocation, as well as a raw pointer to their data, all have the same
+/// address.
+///
+/// `dyn AsAddress` is compared and hashed by address, and every [`ByAddress<P>`][`ByAddress`]
+/// implements [`Borrow<dyn AsAddress>`][`Borrow`], so that a map keyed by [`ByAddress<P>`] can be
+/// looked up using any other pointer to the same data.
+pub trait AsAddress {
+    /// Get the address this pointer points to
+    fn as_address(&self) -> *const ();
+}
+
+impl<T: ?Sized, A: Allocator> AsAddress for Arc<T, A> {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        Arc::as_ptr(self) as *const ()
+    }
+}
+
+impl<T: ?Sized, A: Allocator> AsAddress for ArcBorrow<'_, T, A> {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        ArcBorrow::into_raw(*self) as *const ()
+    }
+}
+
+impl<T: ?Sized + Erasable, A: Allocator> AsAddress for ArcRef<'_, T, A> {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        ArcRef::as_ptr(self) as *const ()
+    }
+}
+
+impl<T: ?Sized> AsAddress for &T {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        *self as *const T as *const ()
+    }
+}
+
+impl<T: ?Sized> AsAddress for *const T {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        *self as *const ()
+    }
+}
+
+impl<T: ?Sized> AsAddress for *mut T {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        *self as *const ()
+    }
+}
+
+impl<T: ?Sized> AsAddress for NonNull<T> {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        self.as_ptr() as *const ()
+    }
+}
+
+impl PartialEq for dyn AsAddress + '_ {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_address() == other.as_address()
+    }
+}
+
+impl Eq for dyn AsAddress + '_ {}
+
+impl PartialOrd for dyn AsAddress + '_ {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for dyn AsAddress + '_ {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_address().cmp(&other.as_address())
+    }
+}
+
+impl Hash for dyn AsAddress + '_ {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_address().hash(state)
+    }
+}
+
+/// A wrapper around a pointer which compares, orders and hashes it by address, rather than by the
+/// value it points to
+///
+/// This is useful for using large or recursive shared values as keys in identity-keyed maps, such
+/// as memo tables and visited sets, without deep-hashing them.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, AsAddress, ByAddress};
+/// use std::collections::HashMap;
+///
+/// let x = Arc::new(vec![0; 1000]);
+/// let y = Arc::new(vec![0; 1000]);
+/// let mut memo = HashMap::new();
+/// memo.insert(ByAddress(x.clone()), "x");
+/// memo.insert(ByAddress(y.clone()), "y");
+/// assert_eq!(memo.len(), 2);
+///
+/// // Look up by an `ArcBorrow` or a raw pointer, without cloning
+/// let borrowed = Arc::borrow_arc(&x);
+/// assert_eq!(memo.get(&borrowed as &dyn AsAddress), Some(&"x"));
+/// assert_eq!(memo.get(&Arc::as_ptr(&y) as &dyn AsAddress), Some(&"y"));
+/// ```
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(transparent)]
+pub struct ByAddress<P>(pub P);
+
+impl<P> ByAddress<P> {
+    /// Get the underlying pointer
+    #[inline]
+    pub fn into_inner(this: Self) -> P {
+        this.0
+    }
+}
+
+impl<P> Deref for ByAddress<P> {
+    type Target = P;
+
+    #[inline]
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P> From<P> for ByAddress<P> {
+    #[inline]
+    fn from(p: P) -> Self {
+        ByAddress(p)
+    }
+}
+
+impl<P: AsAddress> AsAddress for ByAddress<P> {
+    #[inline]
+    fn as_address(&self) -> *const () {
+        self.0.as_address()
+    }
+}
+
+impl<P: AsAddress, Q: AsAddress> PartialEq<ByAddress<Q>> for ByAddress<P> {
+    #[inline]
+    fn eq(&self, other: &ByAddress<Q>) -> bool {
+        self.as_address() == other.as_address()
+    }
+}
+
+impl<P: AsAddress> Eq for ByAddress<P> {}
+
+impl<P: AsAddress, Q: AsAddress> PartialOrd<ByAddress<Q>> for ByAddress<P> {
+    #[inline]
+    fn partial_cmp(&self, other: &ByAddress<Q>) -> Option<Ordering> {
+        Some(self.as_address().cmp(&other.as_address()))
+    }
+}
+
+impl<P: AsAddress> Ord for ByAddress<P> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_address().cmp(&other.as_address())
+    }
+}
+
+impl<P: AsAddress> Hash for ByAddress<P> {
+    /// Hash the address of this pointer, consistently with [`dyn AsAddress`][`AsAddress`]
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_address().hash(state)
+    }
+}
+
+impl<'a, P: AsAddress + 'a> Borrow<dyn AsAddress + 'a> for ByAddress<P> {
+    #[inline]
+    fn borrow(&self) -> &(dyn AsAddress + 'a) {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arc, ArcRef, AsAddress, ByAddress};
+    use alloc::collections::BTreeSet;
+    use alloc::vec;
+
+    #[test]
+    fn identity_keys() {
+        let x = Arc::new(vec![1, 2, 3]);
+        let y = Arc::new(vec![1, 2, 3]);
+        assert_eq!(x, y);
+        assert_ne!(ByAddress(x.clone()), ByAddress(y.clone()));
+        assert_eq!(ByAddress(x.clone()), ByAddress(Arc::borrow_arc(&x)));
+        assert_eq!(ByAddress(ArcRef::from_arc(y.clone())), ByAddress(&*y));
+
+        let set: BTreeSet<_> = [
+            ByAddress(x.clone()),
+            ByAddress(y.clone()),
+            ByAddress(x.clone()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&Arc::borrow_arc(&y) as &dyn AsAddress));
+        let z = Arc::new(vec![1, 2, 3]);
+        assert!(!set.contains(&Arc::as_ptr(&z) as &dyn AsAddress));
+
+        let s: Arc<str> = Arc::from("hello");
+        let key = ByAddress(s.clone());
+        assert_eq!(key.as_address(), s.as_ptr() as *const ());
+        assert_eq!(key, ByAddress(&*s));
+        assert_eq!(ByAddress::into_inner(key), s);
+    }
+}
@@ -0,0 +1,387 @@
+//! Header canaries, for diagnosing invalid pointers passed to [`Arc::from_raw`] and friends
+//!
+//! With the `header-canary` feature enabled, every [`ArcInner`] carries a [`Canary`] between its
+//! reference count and its data, holding a magic number and a fingerprint of the type it was
+//! allocated for. [`Arc::from_raw`], [`ArcBorrow::from_raw`][`crate::ArcBorrow::from_raw`],
+//! [`Arc::clone`] and dropping an [`Arc`] check the canary before touching the reference count, and
+//! panic with a diagnostic if the pointer does not point into an [`ArcInner`], points into one which
+//! has been freed, or points into one allocated for a different type, as after an
+//! [`Erasable::unerase`][`erasable::Erasable::unerase`] to the wrong type. [`Rc`][`crate::Rc`],
+//! [`RcBorrow`][`crate::RcBorrow`] and [`Arc32`][`crate::Arc32`] check it in the same places.
+//! Since every other pointer in this crate is built on top of these, this also covers
+//! [`ArcRef`][`crate::ArcRef`], [`ThinArc`][`crate::ThinArc`] and the rest.
+//!
+//! The fingerprint is the layout of the [`ArcInner`] together with a hash of the name of the type of
+//! its data, so that type confusion is caught even between types with the same layout, such as
+//! `u64` and `f64`. Some legitimate conversions change the type of an [`Arc`] without changing its
+//! allocation, so the type is only compared for sized data, which rules out unsizing, and a
+//! `MaybeUninit<T>` is considered to have the same type as a `T`, which allows
+//! [`Arc::assume_init`]. The type of a `static` [`ArcInner`] is unknown, since its canary is
+//! computed in a constant, so only its layout is checked. The checks are best-effort: a dangling pointer into
+//! memory which has since been reused cannot be detected, and a double free or use after free is
+//! only recognized as such if the allocator leaves the freed canary intact. Allocators commonly
+//! store their free lists in the first words of a freed block (glibc uses the first two), so the
+//! magic number is stored last in the canary.
+
+use core::alloc::Layout;
+use core::any;
+use core::fmt;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+
+#[cfg(doc)]
+use crate::Arc;
+use crate::ArcInner;
+
+/// The magic number of a live [`ArcInner`]: "ArcInner" in ASCII
+const MAGIC: usize = 0x4172_6349_6e6e_6572_u64 as usize;
+
+/// The magic number of a freed [`ArcInner`]
+const FREED: usize = !MAGIC;
+
+/// A type fingerprint and magic number stored in the header of every [`ArcInner`]
+///
+/// This is packed to an alignment of 4 bytes, so that it fits right after the 32-bit reference
+/// count of an [`Arc32`][`crate::Arc32`] without padding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C, packed(4))]
+pub(crate) struct Canary {
+    size: usize,
+    align: usize,
+    /// The [`type_hash`] of the data, or [`Canary::ANY_TYPE`]
+    ty: usize,
+    /// Stored last, where the allocator is least likely to overwrite it once freed
+    magic: usize,
+}
+
+impl Canary {
+    /// The type hash of an [`ArcInner`] whose type is unknown, which is accepted for any type
+    pub(crate) const ANY_TYPE: usize = 0;
+
+    /// The canary for an [`ArcInner`] with the given layout and type hash
+    #[inline]
+    pub(crate) const fn new(layout: Layout, ty: usize) -> Canary {
+        Canary {
+            size: layout.size(),
+            align: layout.align(),
+            ty,
+            magic: MAGIC,
+        }
+    }
+
+    /// The canary of the immortal header shared by every empty [`Arc<[T]>`][`Arc`], whose layout
+    /// depends on `T`, and which is therefore accepted for any empty data
+    pub(crate) const EMPTY: Canary = Canary {
+        size: 0,
+        align: 0,
+        ty: Canary::ANY_TYPE,
+        magic: MAGIC,
+    };
+
+    /// The offset of the canary in an [`ArcInner`] with allocator type `A` and reference count
+    /// type `C`
+    #[inline]
+    pub(crate) fn offset<A, C>() -> usize {
+        ArcInner::<(), A, C>::header_layout().0.size() - mem::size_of::<Canary>()
+    }
+
+    /// Write the canary of a freshly allocated [`ArcInner`] containing a `T`, with allocator type
+    /// `A`, reference count type `C` and the given layout
+    ///
+    /// This also zeroes the padding between the canary and the data, which
+    /// [`check_canary`][`ArcInner::check_canary`] may read while looking for the canary.
+    ///
+    /// # Safety
+    /// `inner` must point to the start of an allocation with the given layout, whose data has not
+    /// been written yet
+    #[inline]
+    pub(crate) unsafe fn init<T: ?Sized, A, C>(inner: *mut u8, layout: Layout) {
+        let canary = inner.add(Self::offset::<A, C>()) as *mut Canary;
+        canary.write(Canary::new(layout, type_hash::<T>()));
+        // The data starts less than its alignment past the end of the canary
+        let start = Self::offset::<A, C>() + mem::size_of::<Canary>();
+        let end = (start + layout.align()).min(layout.size());
+        ptr::write_bytes(inner.add(start), 0, end - start);
+    }
+
+    /// Mark an [`ArcInner`] with allocator type `A` and reference count type `C` as freed
+    ///
+    /// # Safety
+    /// `inner` must point to the start of an [`ArcInner`] which is about to be freed
+    #[inline]
+    pub(crate) unsafe fn poison<A, C>(inner: *mut u8) {
+        (*(inner.add(Self::offset::<A, C>()) as *mut Canary)).magic = FREED
+    }
+}
+
+/// Whether `T` is sized, i.e. pointers to it are thin
+#[inline]
+fn is_sized<T: ?Sized>() -> bool {
+    mem::size_of::<*const T>() == mem::size_of::<*const ()>()
+}
+
+/// A hash of the name of `T`, which is never [`Canary::ANY_TYPE`]
+///
+/// A `MaybeUninit<T>` has the same hash as a `T`, since [`Arc::assume_init`] converts one into the
+/// other without touching the allocation.
+fn type_hash<T: ?Sized>() -> usize {
+    let name = any::type_name::<T>();
+    let uninit = any::type_name::<MaybeUninit<()>>();
+    let uninit = &uninit[..uninit.len() - "()>".len()];
+    let name = match name.strip_prefix(uninit) {
+        Some(inner) => inner.strip_suffix('>').unwrap_or(name),
+        None => name,
+    };
+    // 64-bit FNV-1a, truncated to a word on 32-bit targets
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for byte in name.bytes() {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash as usize).max(1)
+}
+
+/// What is wrong with a pointer whose canary check failed
+enum Problem {
+    Freed,
+    NotArc(usize),
+    Layout(Canary, Layout),
+    Type,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Freed => write!(
+                f,
+                "its allocation has already been freed (double free or use after free)"
+            ),
+            Problem::NotArc(magic) => write!(
+                f,
+                "it does not point into an `ArcInner` (found {:#x} instead of the canary); was it \
+                 obtained from `into_raw`?",
+                magic
+            ),
+            Problem::Layout(canary, expected) => write!(
+                f,
+                "it was allocated with size {} and alignment {}, but an `ArcInner` of this type has \
+                 size {} and alignment {} (type confusion)",
+                { canary.size },
+                { canary.align },
+                expected.size(),
+                expected.align()
+            ),
+            Problem::Type => write!(
+                f,
+                "it was allocated for a different type with the same layout (type confusion)"
+            ),
+        }
+    }
+}
+
+impl<T: ?Sized, A, C> ArcInner<T, A, C> {
+    /// Check the canary of the [`ArcInner`] containing `data`, panicking with a diagnostic naming
+    /// `operation` if it is invalid
+    ///
+    /// # Safety
+    /// `data` must point into readable memory at least as far back as the start of the header,
+    /// which is always the case for a pointer into a live or freed-but-not-unmapped [`ArcInner`]
+    #[inline]
+    #[track_caller]
+    pub(crate) unsafe fn check_canary(data: *const T, operation: &str) {
+        let problem = match Self::find_canary(data) {
+            Ok(canary) if canary.magic == FREED => Problem::Freed,
+            Ok(canary) => {
+                // Safety: `data` points into a live `ArcInner`, so we may now compute its layout
+                let (layout, _) = Self::layout(&*data);
+                if canary == Canary::EMPTY && mem::size_of_val(&*data) == 0 {
+                    return;
+                }
+                if canary.size != layout.size() || canary.align != layout.align() {
+                    Problem::Layout(canary, layout)
+                } else if canary.ty == Canary::ANY_TYPE
+                    || !is_sized::<T>()
+                    || canary.ty == type_hash::<T>()
+                {
+                    return;
+                } else {
+                    Problem::Type
+                }
+            }
+            Err(magic) => Problem::NotArc(magic),
+        };
+        panic!(
+            "elysees: `{}` on an invalid pointer to a `{}` at {:p}: {}",
+            operation,
+            any::type_name::<T>(),
+            data as *const u8,
+            problem
+        )
+    }
+
+    /// Find the canary of the [`ArcInner`] containing `data` without dereferencing `data`, which
+    /// may be dangling, returning the first word found instead of a magic number if there is none
+    ///
+    /// The offset of the data depends on its alignment, which stable Rust can only compute from a
+    /// reference. Instead, this tries the offset for each alignment which the address of `data`
+    /// satisfies, from the smallest. Each offset smaller than the right one lands the canary
+    /// further into the allocation, within the real canary or the padding after it, which
+    /// [`Canary::init`] zeroes, so the first canary found with a magic number is the right one.
+    /// Since the reference count precedes the canary, this never reads it while another thread
+    /// may be writing it. A `static` [`ArcInner`] has no such padding, since
+    /// [`ArcInner::new_static`] rejects types aligned to more than the header, whose padding a
+    /// constant initializer would leave uninitialized.
+    ///
+    /// # Safety
+    /// As for [`check_canary`][`ArcInner::check_canary`]
+    unsafe fn find_canary(data: *const T) -> Result<Canary, usize> {
+        let header = Self::header_layout().0.size();
+        let address = data as *const u8 as usize;
+        let mut first = None;
+        let mut previous = None;
+        let mut align = 1usize;
+        while address & (align - 1) == 0 {
+            let offset = (header + align - 1) & !(align - 1);
+            if offset > address {
+                break;
+            }
+            if previous != Some(offset) {
+                previous = Some(offset);
+                let inner = (data as *const u8).sub(offset);
+                let canary =
+                    (inner.add(Canary::offset::<A, C>()) as *const Canary).read_unaligned();
+                if canary.magic == MAGIC || canary.magic == FREED {
+                    return Ok(canary);
+                }
+                first.get_or_insert(canary.magic);
+            }
+            align = match align.checked_mul(2) {
+                Some(align) => align,
+                None => break,
+            };
+        }
+        Err(first.unwrap_or(0))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{AllocError, Allocator, Arc, Arc32, ArcBorrow, Global, Rc, RcBorrow};
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::alloc::Layout;
+    use core::cell::RefCell;
+    use core::mem::MaybeUninit;
+    use core::ptr::NonNull;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn panic_message(f: impl FnOnce()) -> String {
+        let err = catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+        *err.downcast::<String>().unwrap()
+    }
+
+    #[test]
+    fn invalid_pointers() {
+        let x = Arc::new([5u64; 2]);
+        let p = Arc::into_raw(x);
+        let message = panic_message(|| drop(unsafe { Arc::from_raw(p as *const u64) }));
+        assert!(message.contains("`Arc::from_raw`"));
+        assert!(message.contains("type confusion"));
+        // The failed check does not touch the reference count
+        let x = unsafe { Arc::from_raw(p) };
+        assert!(Arc::is_unique(&x));
+
+        let x = Rc::new([5u64; 2]);
+        let p = Rc::into_raw(x);
+        let message = panic_message(|| {
+            let _ = unsafe { RcBorrow::from_raw(p as *const u32) };
+        });
+        assert!(message.contains("`RcBorrow::from_raw`"));
+        assert!(message.contains("type confusion"));
+        drop(unsafe { Rc::from_raw(p) });
+
+        let x = Arc32::new([5u64; 2]);
+        let p = Arc32::into_raw(x);
+        let message = panic_message(|| drop(unsafe { Arc32::from_raw(p as *const u64) }));
+        assert!(message.contains("`Arc32::from_raw`"));
+        assert!(message.contains("type confusion"));
+        drop(unsafe { Arc32::from_raw(p) });
+
+        let not_arc = Box::new([0usize; 8]);
+        let message = panic_message(|| {
+            let _ = unsafe { ArcBorrow::from_raw(&not_arc[6] as *const usize) };
+        });
+        assert!(message.contains("does not point into an `ArcInner`"));
+    }
+
+    #[test]
+    fn same_layout_type_confusion() {
+        let p = Arc::into_raw(Arc::new(5u64));
+        let message = panic_message(|| drop(unsafe { Arc::from_raw(p as *const f64) }));
+        assert!(message.contains("`Arc::from_raw`"));
+        assert!(message.contains("different type with the same layout"));
+        drop(unsafe { Arc::from_raw(p) });
+
+        let p = Rc::into_raw(Rc::new(String::from("elysees")));
+        let message = panic_message(|| drop(unsafe { Rc::from_raw(p as *const Vec<u8>) }));
+        assert!(message.contains("different type with the same layout"));
+        drop(unsafe { Rc::from_raw(p) });
+
+        // Conversions which legitimately change the type are not reported
+        let mut x = Arc::<MaybeUninit<u64>>::new_uninit();
+        let y = x.clone();
+        drop(y);
+        x.write(5);
+        let x = unsafe { x.assume_init() };
+        assert_eq!(*x.clone(), 5);
+        let x = Arc::new([5u64; 2]);
+        let p = Arc::into_raw(x) as *const [u64];
+        assert_eq!(unsafe { Arc::from_raw(p) }.clone().len(), 2);
+    }
+
+    /// An allocator which keeps freed blocks allocated and unmodified until it is dropped, so that
+    /// reading through a dangling pointer into one is well-defined
+    #[derive(Default)]
+    struct Quarantine(RefCell<Vec<(NonNull<u8>, Layout)>>);
+
+    unsafe impl Allocator for Quarantine {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.borrow_mut().push((ptr, layout))
+        }
+    }
+
+    impl Drop for Quarantine {
+        fn drop(&mut self) {
+            for (ptr, layout) in self.0.get_mut().drain(..) {
+                unsafe { Global.deallocate(ptr, layout) }
+            }
+        }
+    }
+
+    #[test]
+    fn double_free() {
+        let quarantine = Quarantine::default();
+
+        let p = Arc::into_raw(Arc::new_in([5u64; 2], &quarantine));
+        drop(unsafe { Arc::<_, &Quarantine>::from_raw_in(p) });
+        let message = panic_message(|| drop(unsafe { Arc::<_, &Quarantine>::from_raw_in(p) }));
+        assert!(message.contains("`Arc::from_raw`"));
+        assert!(message.contains("already been freed"));
+
+        let p = Rc::into_raw(Rc::new_in(5u64, &quarantine));
+        drop(unsafe { Rc::<_, &Quarantine>::from_raw_in(p) });
+        let message = panic_message(|| drop(unsafe { Rc::<_, &Quarantine>::from_raw_in(p) }));
+        assert!(message.contains("`Rc::from_raw`"));
+        assert!(message.contains("already been freed"));
+
+        let p = Arc32::into_raw(Arc32::new_in(5u32, &quarantine));
+        drop(unsafe { Arc32::<_, &Quarantine>::from_raw_in(p) });
+        let message = panic_message(|| drop(unsafe { Arc32::<_, &Quarantine>::from_raw_in(p) }));
+        assert!(message.contains("`Arc32::from_raw`"));
+        assert!(message.contains("already been freed"));
+    }
+}
@@ -0,0 +1,255 @@
+//! Tracking of live allocations, for finding leaked [`Arc`]s
+//!
+//! With the `track-allocations` feature enabled, every [`ArcInner`] allocation made by this crate,
+//! whether for an [`Arc`], an [`ArcBox`][`crate::ArcBox`], an [`Rc`][`crate::Rc`], or any other
+//! pointer, is recorded in a global registry until it is freed. Allocations which are never freed,
+//! for example because of [`Arc::leak`], [`ArcRef::leak`][`crate::ArcRef::leak`] or an
+//! [`Arc::into_raw`] without a matching [`Arc::from_raw`], therefore remain in
+//! [`live_allocations`], along with their type and size, and optionally a backtrace of where they
+//! were created (see [`capture_backtraces`]).
+//!
+//! The type of an allocation is the type it was created with: an [`Arc<[T; N]>`][`Arc`] coerced
+//! to an [`Arc<[T]>`][`Arc`] is still reported as a `[T; N]`. Immortal [`Arc`]s declared with
+//! [`static_arc!`][`crate::static_arc`] are not heap-allocated, and are never reported.
+//!
+//! Since the registry is global, tests running in parallel see each other's allocations; use a
+//! type local to the test with [`assert_no_live`] to avoid spurious failures.
+//!
+//! # Examples
+//!
+//! ```
+//! use elysees::{debug, Arc};
+//!
+//! struct Node(u32);
+//!
+//! let x = Arc::new(Node(5));
+//! let leaked = Arc::into_raw(x.clone());
+//! drop(x);
+//! assert_eq!(debug::live_allocations_of::<Node>().len(), 1);
+//!
+//! drop(unsafe { Arc::from_raw(leaked) });
+//! debug::assert_no_live::<Node>();
+//! ```
+
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc as StdArc, Mutex, MutexGuard, PoisonError};
+use std::vec::Vec;
+
+#[cfg(doc)]
+use crate::{Arc, ArcInner};
+
+/// A live allocation
+#[derive(Debug, Clone)]
+pub struct LiveAllocation {
+    /// The address of the allocation, as returned by [`Arc::heap_ptr`]
+    pub address: usize,
+    /// The name of the type the allocation was created with, as returned by
+    /// [`core::any::type_name`]
+    pub type_name: &'static str,
+    /// The size of the allocation, including the reference count
+    pub size: usize,
+    /// Where the allocation was created, if [`capture_backtraces`] was enabled at the time
+    pub backtrace: Option<StdArc<Backtrace>>,
+}
+
+impl fmt::Display for LiveAllocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes at {:#x}: {}",
+            self.size, self.address, self.type_name
+        )?;
+        if let Some(backtrace) = &self.backtrace {
+            write!(f, "\ncreated at:\n{}", backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+/// The live allocations, by address
+static LIVE: Mutex<BTreeMap<usize, LiveAllocation>> = Mutex::new(BTreeMap::new());
+
+static CAPTURE_BACKTRACES: AtomicBool = AtomicBool::new(false);
+
+/// Lock the registry. The registry is never left in an invalid state, so poisoning is ignored.
+fn lock() -> MutexGuard<'static, BTreeMap<usize, LiveAllocation>> {
+    LIVE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Record a new allocation for an `ArcInner<T>`
+pub(crate) fn register<T: ?Sized>(inner: *mut u8, size: usize) {
+    let backtrace = if CAPTURE_BACKTRACES.load(Relaxed) {
+        Some(StdArc::new(Backtrace::force_capture()))
+    } else {
+        None
+    };
+    let allocation = LiveAllocation {
+        address: inner as usize,
+        type_name: core::any::type_name::<T>(),
+        size,
+        backtrace,
+    };
+    lock().insert(inner as usize, allocation);
+}
+
+/// Record that the allocation at `inner` now holds a `T`
+pub(crate) fn retype<T: ?Sized>(inner: *const c_void) {
+    if let Some(allocation) = lock().get_mut(&(inner as usize)) {
+        allocation.type_name = core::any::type_name::<T>();
+    }
+}
+
+/// Record that the allocation at `inner` has been freed
+pub(crate) fn unregister(inner: *mut u8) {
+    let allocation = lock().remove(&(inner as usize));
+    // Drop the backtrace, if any, outside of the lock
+    drop(allocation)
+}
+
+/// Set whether to capture a backtrace for each new allocation, which is expensive. Disabled by
+/// default.
+pub fn capture_backtraces(enabled: bool) {
+    CAPTURE_BACKTRACES.store(enabled, Relaxed)
+}
+
+/// Get all currently live allocations, in order of address
+pub fn live_allocations() -> Vec<LiveAllocation> {
+    lock().values().cloned().collect()
+}
+
+/// Get all currently live allocations created with type `T`, in order of address
+pub fn live_allocations_of<T: ?Sized>() -> Vec<LiveAllocation> {
+    let type_name = core::any::type_name::<T>();
+    lock()
+        .values()
+        .filter(|allocation| allocation.type_name == type_name)
+        .cloned()
+        .collect()
+}
+
+/// Panic, listing the offending allocations, if any allocations created with type `T` are live
+#[track_caller]
+pub fn assert_no_live<T: ?Sized>() {
+    let live = live_allocations_of::<T>();
+    if !live.is_empty() {
+        let mut message = std::format!(
+            "{} live allocation(s) of type {}:",
+            live.len(),
+            core::any::type_name::<T>()
+        );
+        for allocation in &live {
+            message.push('\n');
+            message.push_str(&allocation.to_string());
+        }
+        panic!("{}", message)
+    }
+}
+
+/// Write a report of all currently live allocations to `w`
+pub fn write_report<W: Write>(mut w: W) -> io::Result<()> {
+    let live = live_allocations();
+    let total: usize = live.iter().map(|allocation| allocation.size).sum();
+    writeln!(
+        w,
+        "{} live allocation(s), totalling {} bytes",
+        live.len(),
+        total
+    )?;
+    for allocation in &live {
+        writeln!(w, "{}", allocation)?;
+    }
+    Ok(())
+}
+
+/// Print a report of all currently live allocations to standard error
+pub fn report() {
+    // There is nowhere left to report a failure to write to standard error
+    let _ = write_report(io::stderr().lock());
+}
+
+/// Print a report of all allocations which are still live when this guard is dropped, e.g. at the
+/// end of `main`
+///
+/// # Examples
+///
+/// ```
+/// let _report = elysees::debug::ReportOnDrop;
+/// let leaked = elysees::Arc::leak(elysees::Arc::new(5));
+/// // The leaked allocation is reported when `_report` goes out of scope
+/// ```
+#[derive(Debug, Default)]
+pub struct ReportOnDrop;
+
+impl Drop for ReportOnDrop {
+    fn drop(&mut self) {
+        report()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Arc, ArcBox, ArcRef, Rc};
+    use core::mem::MaybeUninit;
+    use std::string::String;
+
+    #[test]
+    fn tracks_leaks() {
+        struct Tracked(#[allow(dead_code)] u64);
+
+        assert_no_live::<Tracked>();
+        let x = Arc::new(Tracked(1));
+        let y = ArcBox::new(Tracked(2));
+        let z = Rc::new(Tracked(3));
+        let live = live_allocations_of::<Tracked>();
+        assert_eq!(live.len(), 3);
+        assert!(live
+            .iter()
+            .any(|allocation| allocation.address == x.heap_ptr() as usize));
+        drop((y, z));
+        assert_eq!(live_allocations_of::<Tracked>().len(), 1);
+
+        let leaked = ArcRef::leak(ArcRef::from_arc(x));
+        let mut report = Vec::new();
+        write_report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains(core::any::type_name::<Tracked>()));
+        drop(unsafe { Arc::from_raw(crate::ArcBorrow::into_raw(leaked)) });
+        assert_no_live::<Tracked>();
+    }
+
+    #[test]
+    fn assume_init() {
+        struct Node(#[allow(dead_code)] u64);
+
+        let mut x = Arc::<MaybeUninit<Node>>::new_uninit();
+        Arc::get_mut(&mut x).unwrap().write(Node(1));
+        let x = Arc::into_raw(unsafe { x.assume_init() });
+        let mut y = Rc::<MaybeUninit<Node>>::new_uninit();
+        Rc::get_mut(&mut y).unwrap().write(Node(2));
+        let y = unsafe { y.assume_init() };
+        assert_eq!(live_allocations_of::<Node>().len(), 2);
+        assert!(live_allocations_of::<MaybeUninit<Node>>().is_empty());
+        drop((unsafe { Arc::from_raw(x) }, y));
+        assert_no_live::<Node>();
+    }
+
+    #[test]
+    fn strings() {
+        let x: Arc<str> = Arc::from("tracked");
+        let address = x.heap_ptr() as usize;
+        assert!(live_allocations_of::<str>()
+            .iter()
+            .any(|allocation| allocation.address == address));
+        let bytes = Arc::into_bytes(x);
+        assert!(live_allocations_of::<[u8]>()
+            .iter()
+            .any(|allocation| allocation.address == address));
+        drop(bytes);
+    }
+}
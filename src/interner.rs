@@ -0,0 +1,281 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::vec::Vec;
+
+use crate::{Arc, ArcBorrow};
+
+/// A thread-safe hash-consing table, which returns a single canonical [`Arc<T>`][`Arc`] for each
+/// distinct value
+///
+/// Since every interned value is stored in exactly one allocation, two [`Arc`]s obtained from the
+/// same [`Interner`] are equal if and only if they are [`Arc::ptr_eq`], and may be compared and
+/// hashed by address instead of by value.
+///
+/// The interner holds one reference to each of its values. Since an [`Arc`] has no weak count,
+/// values are not removed automatically when the last outside reference is dropped; instead,
+/// [`gc`][`Interner::gc`] sweeps the table for values whose only remaining reference is the
+/// interner's own, i.e. whose [`Arc::count`] is `1`, and frees them.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, Interner};
+///
+/// let interner = Interner::<str>::new();
+/// let x = interner.intern_ref("hello");
+/// let y = interner.intern_ref(&*String::from("hello"));
+/// assert!(Arc::ptr_eq(&x, &y));
+/// assert_eq!(interner.len(), 1);
+///
+/// drop(x);
+/// assert_eq!(interner.gc(), 0);
+/// drop(y);
+/// assert_eq!(interner.gc(), 1);
+/// assert!(interner.is_empty());
+/// ```
+pub struct Interner<T: ?Sized, S = RandomState> {
+    set: Mutex<HashSet<Arc<T>, S>>,
+}
+
+impl<T: ?Sized> Interner<T> {
+    /// Create a new, empty [`Interner`]
+    #[inline]
+    pub fn new() -> Self {
+        Interner::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: ?Sized, S> Interner<T, S> {
+    /// Create a new, empty [`Interner`] using the given hasher
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Interner {
+            set: Mutex::new(HashSet::with_hasher(hasher)),
+        }
+    }
+
+    /// Lock the table. A panic while the lock is held cannot leave the table in an invalid state,
+    /// so poisoning is ignored.
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, HashSet<Arc<T>, S>> {
+        self.set.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the number of values in this [`Interner`], including those which are no longer
+    /// referenced elsewhere but have not yet been collected
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check whether this [`Interner`] is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Free every value which is only referenced by this [`Interner`], returning how many were
+    /// removed
+    ///
+    /// Values are dropped after the table is unlocked, so their destructors may themselves use
+    /// this [`Interner`]. If dropping a value leaves other values unreferenced, as when interned
+    /// values contain other interned values, these are collected as well.
+    pub fn gc(&self) -> usize {
+        let mut removed = 0;
+        loop {
+            let mut garbage = Vec::new();
+            self.lock().retain(|arc| {
+                // An interned value with no other references cannot gain new ones while the table
+                // is locked, since it can only be reached through the table
+                let unique = Arc::count(arc) == 1;
+                if unique {
+                    garbage.push(arc.clone());
+                }
+                !unique
+            });
+            if garbage.is_empty() {
+                return removed;
+            }
+            removed += garbage.len();
+        }
+    }
+
+    /// Remove every value from this [`Interner`]
+    ///
+    /// [`Arc`]s previously returned by this [`Interner`] remain valid, but are no longer canonical:
+    /// interning an equal value will allocate a new [`Arc`].
+    pub fn clear(&self) {
+        let values: Vec<Arc<T>> = self.lock().drain().collect();
+        drop(values)
+    }
+}
+
+impl<T: ?Sized + Hash + Eq, S: BuildHasher> Interner<T, S> {
+    /// Get the canonical [`Arc`] for `value`, moving it into a new allocation if it has not been
+    /// interned yet
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Arc, Interner};
+    ///
+    /// let interner = Interner::new();
+    /// let x = interner.intern(vec![1, 2, 3]);
+    /// assert!(Arc::ptr_eq(&x, &interner.intern(vec![1, 2, 3])));
+    /// assert!(!Arc::ptr_eq(&x, &interner.intern(vec![4])));
+    /// ```
+    pub fn intern(&self, value: T) -> Arc<T>
+    where
+        T: Sized,
+    {
+        let mut set = self.lock();
+        if let Some(arc) = set.get(&value) {
+            return arc.clone();
+        }
+        let arc = Arc::new(value);
+        set.insert(arc.clone());
+        arc
+    }
+
+    /// Get the canonical [`Arc`] for `value`, copying it into a new allocation if it has not been
+    /// interned yet
+    ///
+    /// This allows interning unsized values such as [`str`] and slices without first allocating
+    /// them.
+    pub fn intern_ref<Q>(&self, value: &Q) -> Arc<T>
+    where
+        Q: ?Sized + Hash + Eq,
+        Arc<T>: Borrow<Q> + for<'a> From<&'a Q>,
+    {
+        let mut set = self.lock();
+        if let Some(arc) = set.get(value) {
+            return arc.clone();
+        }
+        let arc = Arc::from(value);
+        set.insert(arc.clone());
+        arc
+    }
+
+    /// Get the canonical [`Arc`] for the value of `arc`, which becomes canonical if the value has
+    /// not been interned yet
+    pub fn intern_arc(&self, arc: Arc<T>) -> Arc<T> {
+        let mut set = self.lock();
+        if let Some(canonical) = set.get(&*arc) {
+            return canonical.clone();
+        }
+        set.insert(arc.clone());
+        arc
+    }
+
+    /// Get the canonical [`Arc`] for `value`, if it has been interned
+    pub fn get<Q>(&self, value: &Q) -> Option<Arc<T>>
+    where
+        Q: ?Sized + Hash + Eq,
+        Arc<T>: Borrow<Q>,
+    {
+        self.lock().get(value).cloned()
+    }
+
+    /// Check whether `arc` is the canonical [`Arc`] for its value in this [`Interner`]
+    pub fn is_interned(&self, arc: ArcBorrow<'_, T>) -> bool {
+        self.lock()
+            .get(&*arc)
+            .is_some_and(|canonical| ArcBorrow::ptr_eq(Arc::borrow_arc(canonical), arc))
+    }
+}
+
+impl<T: ?Sized, S: Default> Default for Interner<T, S> {
+    #[inline]
+    fn default() -> Self {
+        Interner::with_hasher(S::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, S> fmt::Debug for Interner<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.lock().iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arc, Interner};
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn intern_and_collect() {
+        let interner = Interner::<[u8]>::new();
+        let x = interner.intern_ref(&[1, 2, 3][..]);
+        let y = interner.intern_arc(Arc::from(&[1, 2, 3][..]));
+        assert!(Arc::ptr_eq(&x, &y));
+        let z: Arc<[u8]> = Arc::from(&[4][..]);
+        assert!(Arc::ptr_eq(&interner.intern_arc(z.clone()), &z));
+        assert!(interner.is_interned(Arc::borrow_arc(&z)));
+        let w: Arc<[u8]> = Arc::from(&[4][..]);
+        assert!(!interner.is_interned(Arc::borrow_arc(&w)));
+        assert_eq!(Arc::count(&x), 3);
+        assert_eq!(interner.len(), 2);
+
+        drop(z);
+        assert_eq!(interner.gc(), 1);
+        assert!(interner.get(&[4][..]).is_none());
+        assert!(Arc::ptr_eq(&interner.get(&[1, 2, 3][..]).unwrap(), &x));
+        drop((x, y));
+        assert_eq!(interner.gc(), 1);
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn nested() {
+        #[derive(PartialEq, Eq, Hash)]
+        enum Term {
+            Leaf(String),
+            Pair(Arc<Term>, Arc<Term>),
+        }
+
+        let interner = Interner::new();
+        let leaf = interner.intern(Term::Leaf(String::from("a")));
+        let pair = interner.intern(Term::Pair(leaf.clone(), leaf.clone()));
+        let other = interner.intern(Term::Pair(
+            interner.intern(Term::Leaf(String::from("a"))),
+            leaf.clone(),
+        ));
+        assert!(Arc::ptr_eq(&pair, &other));
+        drop((leaf, other));
+        assert_eq!(interner.gc(), 0);
+        drop(pair);
+        // Collecting the pair leaves the leaf unreferenced, so it is collected by the same sweep
+        assert_eq!(interner.gc(), 2);
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn concurrent_interning() {
+        use std::thread;
+
+        let interner = Interner::<str>::new();
+        let arcs: Vec<Vec<Arc<str>>> = thread::scope(|s| {
+            let threads: Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(|| {
+                        (0..100)
+                            .map(|i| interner.intern_ref(&*alloc::format!("{}", i % 10)))
+                            .collect()
+                    })
+                })
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+        assert_eq!(interner.len(), 10);
+        for arc in arcs.iter().flatten() {
+            assert!(Arc::ptr_eq(arc, &interner.intern_ref(&**arc)));
+        }
+        drop(arcs);
+        assert_eq!(interner.gc(), 10);
+    }
+}
@@ -14,6 +14,22 @@
 //! * [`elysees::ArcBox`][`ArcBox`] allows one to construct a temporarily-mutable [`Arc`] which can be converted to a regular [`elysees::Arc`][`Arc`] later
 //! * [`elysees::ArcBorrow`][`ArcBorrow`] is functionally similar to [`&elysees::Arc<T>`][`Arc`], however in memory it's simply a (non-owned) pointer to the inner [`Arc`]. This helps avoid pointer-chasing.
 //! * [`elysees::ArcRef`][`ArcRef`] is a union of an [`Arc`] and an [`ArcBorrow`]
+//! * All of the above are generic over an [`Allocator`], defaulting to [`Global`], which is stored in the allocation alongside the reference count so that every pointer remains a single word
+//! * [`elysees::ArcUnion`][`ArcUnion`], [`ArcUnion3`] and [`ArcUnion4`] hold one of several kinds of [`Arc`] in a single word, with the variant stored in the low bits of the pointer
+//! * [`elysees::TaggedArc`][`TaggedArc`] and [`TaggedArcBorrow`] store a small integer tag in the spare low bits of an [`Arc`] pointer
+//! * [`elysees::AtomicArc`][`AtomicArc`] and [`AtomicOptionArc`] are lock-free atomic cells holding an [`Arc`], which may be loaded and swapped concurrently
+//! * [`elysees::ByAddress`][`ByAddress`] compares and hashes a pointer by address rather than by value, and can be looked up by any other pointer to the same data
+//! * [`SizeOf`] reports the heap memory used by a value, counting allocations shared through an [`Arc`] only once, or dividing them between their owners
+//! * [`elysees::Interner`][`Interner`] hash-conses values into canonical [`Arc`]s, which can be compared by address, and frees values which are no longer used elsewhere
+//! * With the `track-allocations` feature, `elysees::debug` records every live allocation, to help find leaked [`Arc`]s
+//! * By default, overflowing a reference count aborts the process, through a handler which can be replaced with [`set_abort_handler`]; the `overflow-saturate` and `overflow-panic` features instead leak the allocation or panic. See [`OverflowPolicy`]
+//! * With the `header-canary` feature, every allocation carries a canary which is checked when an [`Arc`], [`Rc`] or [`Arc32`] is created from a raw pointer, cloned or dropped, turning invalid pointers, type confusion and (on a best-effort basis) double frees into panics with a diagnostic
+//! * [`elysees::serde_shared`][`serde_shared`] (de)serializes graphs of [`Arc`]s while preserving which of them point to the same allocation
+//! * [`elysees::ArcMap`][`ArcMap`] is a shared pointer to part of the data of an [`Arc`], such as a field, which keeps the whole allocation alive
+//! * [`elysees::ArcYoke`][`ArcYoke`] stores an [`Arc`] along with data borrowed from it, such as a zero-copy parse of a shared buffer
+//! * Immortal [`Arc`]s can be declared in `static`s with [`static_arc!`], and empty [`Arc<[T]>`][`Arc`]s and [`Arc<str>`][`Arc`]s share a single static allocation
+//! * [`elysees::Arc32`][`Arc32`] is an [`Arc`] with a 32-bit reference count, for a smaller header
+//! * [`elysees::Rc`][`Rc`], [`RcBox`], [`RcBorrow`] and [`RcRef`] are single-threaded counterparts of the above with a non-atomic reference count. An [`ArcBox`] and an [`RcBox`] can be converted into one another for free, since neither can be shared yet
 
 #![allow(missing_docs)]
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -32,30 +48,48 @@ extern crate stable_deref_trait;
 extern crate unsize;
 
 mod arc;
+mod arc32;
 mod arc_borrow;
+mod arc_map;
 mod arc_ref;
 #[cfg(feature = "arc-swap")]
 mod arc_swap_support;
+mod arc_union;
+mod arc_yoke;
+mod atomic_arc;
+mod by_address;
+#[cfg(feature = "header-canary")]
+mod canary;
+#[cfg(feature = "track-allocations")]
+pub mod debug;
+#[cfg(feature = "std")]
+mod interner;
+mod mem_size;
+mod overflow;
+mod rc;
+#[cfg(all(feature = "serde", feature = "std"))]
+pub mod serde_shared;
+mod shared_ptr;
+mod static_arc;
+mod tagged_arc;
+mod thin_arc;
 mod unique_arc;
 
+pub use allocator_api2::alloc::{AllocError, Allocator, Global};
 pub use arc::*;
+pub use arc32::*;
 pub use arc_borrow::*;
+pub use arc_map::*;
 pub use arc_ref::*;
-pub use unique_arc::*;
-
+pub use arc_union::*;
+pub use arc_yoke::*;
+pub use atomic_arc::*;
+pub use by_address::*;
 #[cfg(feature = "std")]
-use std::process::abort;
-
-// `no_std`-compatible abort by forcing a panic while already panicing.
-#[cfg(not(feature = "std"))]
-#[cold]
-fn abort() -> ! {
-    struct PanicOnDrop;
-    impl Drop for PanicOnDrop {
-        fn drop(&mut self) {
-            panic!()
-        }
-    }
-    let _double_panicer = PanicOnDrop;
-    panic!();
-}
+pub use interner::*;
+pub use mem_size::*;
+pub use overflow::*;
+pub use rc::*;
+pub use tagged_arc::*;
+pub use thin_arc::*;
+pub use unique_arc::*;
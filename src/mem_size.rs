@@ -0,0 +1,372 @@
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::mem;
+
+use erasable::Erasable;
+
+use crate::{Allocator, Arc, ArcBorrow, ArcBox, ArcInner, ArcRef};
+
+/// How [`SizeOfOps`] attributes the size of a shared allocation to its owners
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum SharedAttribution {
+    /// The whole allocation is attributed to the first owner reached during the traversal, and
+    /// nothing to the others
+    #[default]
+    FirstOwner,
+    /// Each owner is attributed an equal share of the allocation, rounded down, according to its
+    /// reference count. The allocation is only measured the first time it is reached.
+    Proportional,
+}
+
+/// The state of a memory-reporting traversal, which tracks the shared allocations seen so far
+///
+/// Immortal allocations, such as those declared with [`static_arc!`][`crate::static_arc`], are
+/// never freed and are not counted, under either [`SharedAttribution`]; only the heap memory owned
+/// by their data is, the first time they are reached.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, SizeOf, SizeOfOps, SharedAttribution};
+///
+/// let shared: Arc<[u64]> = Arc::from(&[1, 2, 3, 4][..]);
+/// let owners = vec![shared.clone(), shared.clone()];
+///
+/// // The allocation is counted once, for the first owner
+/// let mut ops = SizeOfOps::new(SharedAttribution::FirstOwner);
+/// let first = owners[0].size_of(&mut ops);
+/// assert!(first >= 32);
+/// assert_eq!(owners[1].size_of(&mut ops), 0);
+///
+/// // The allocation is split between its three owners
+/// let mut ops = SizeOfOps::new(SharedAttribution::Proportional);
+/// assert_eq!(owners[1].size_of(&mut ops), first / 3);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SizeOfOps {
+    attribution: SharedAttribution,
+    seen: BTreeSet<*const c_void>,
+    /// The full size of each shared allocation measured so far under
+    /// [`SharedAttribution::Proportional`]
+    sizes: BTreeMap<*const c_void, usize>,
+}
+
+impl SizeOfOps {
+    /// Start a new traversal
+    #[inline]
+    pub fn new(attribution: SharedAttribution) -> Self {
+        SizeOfOps {
+            attribution,
+            seen: BTreeSet::new(),
+            sizes: BTreeMap::new(),
+        }
+    }
+
+    /// Get how this traversal attributes shared allocations
+    #[inline]
+    pub fn attribution(&self) -> SharedAttribution {
+        self.attribution
+    }
+
+    /// Record that the allocation at `heap_ptr` has been reached, returning whether this is the
+    /// first time during this traversal
+    #[inline]
+    pub fn visit(&mut self, heap_ptr: *const c_void) -> bool {
+        self.seen.insert(heap_ptr)
+    }
+
+    /// Measure the [`ArcInner`] allocation behind `arc`, along with the heap memory owned by its
+    /// data, for one of its owners
+    pub fn measure_shared<T: ?Sized + SizeOf, A: Allocator>(
+        &mut self,
+        arc: ArcBorrow<'_, T, A>,
+    ) -> usize {
+        let heap_ptr = ArcBorrow::heap_ptr(arc);
+        if ArcBorrow::is_immortal(arc) {
+            return if self.visit(heap_ptr) {
+                arc.get().size_of(self)
+            } else {
+                0
+            };
+        }
+        match self.attribution {
+            SharedAttribution::FirstOwner => {
+                if self.visit(heap_ptr) {
+                    self.measure_unique(arc)
+                } else {
+                    0
+                }
+            }
+            SharedAttribution::Proportional => {
+                let size = match self.sizes.get(&heap_ptr) {
+                    Some(&size) => size,
+                    None => {
+                        let size = self.measure_unique(arc);
+                        self.sizes.insert(heap_ptr, size);
+                        size
+                    }
+                };
+                size / ArcBorrow::count(arc).max(1)
+            }
+        }
+    }
+
+    /// Measure the [`ArcInner`] allocation behind `arc`, along with the heap memory owned by its
+    /// data, as if it had a single owner
+    pub fn measure_unique<T: ?Sized + SizeOf, A: Allocator>(
+        &mut self,
+        arc: ArcBorrow<'_, T, A>,
+    ) -> usize {
+        let data = arc.get();
+        ArcInner::<T, A>::layout(data).0.size() + data.size_of(self)
+    }
+}
+
+/// A value which can report how much heap memory it owns
+///
+/// The size of the value itself, i.e. [`mem::size_of_val`], is not included, since it is either
+/// on the stack or already counted as part of the allocation containing it. Allocations shared
+/// through an [`Arc`] are attributed according to the [`SizeOfOps`] passed in.
+pub trait SizeOf {
+    /// Get the heap memory owned by this value, in bytes
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize;
+}
+
+impl<T: ?Sized + SizeOf, A: Allocator> SizeOf for Arc<T, A> {
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        ops.measure_shared(Arc::borrow_arc(self))
+    }
+}
+
+impl<T: ?Sized + SizeOf, A: Allocator> SizeOf for ArcBox<T, A> {
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        ops.measure_unique(Arc::borrow_arc(&self.0))
+    }
+}
+
+impl<T: ?Sized + Erasable + SizeOf, A: Allocator> SizeOf for ArcRef<'_, T, A> {
+    /// Measure an owned [`ArcRef`] like an [`Arc`]; a borrowed [`ArcRef`] owns nothing
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        if ArcRef::is_owned(self) {
+            ops.measure_shared(ArcRef::borrow_arc(self))
+        } else {
+            0
+        }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> SizeOf for ArcBorrow<'_, T, A> {
+    /// An [`ArcBorrow`] does not own its allocation
+    #[inline]
+    fn size_of(&self, _ops: &mut SizeOfOps) -> usize {
+        0
+    }
+}
+
+impl<T: SizeOf> SizeOf for [T] {
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        self.iter().map(|x| x.size_of(ops)).sum()
+    }
+}
+
+impl<T: SizeOf, const N: usize> SizeOf for [T; N] {
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        self[..].size_of(ops)
+    }
+}
+
+impl SizeOf for str {
+    #[inline]
+    fn size_of(&self, _ops: &mut SizeOfOps) -> usize {
+        0
+    }
+}
+
+impl SizeOf for String {
+    #[inline]
+    fn size_of(&self, _ops: &mut SizeOfOps) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: SizeOf> SizeOf for Vec<T> {
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        self.capacity() * mem::size_of::<T>() + self[..].size_of(ops)
+    }
+}
+
+impl<T: ?Sized + SizeOf> SizeOf for Box<T> {
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        mem::size_of_val(&**self) + (**self).size_of(ops)
+    }
+}
+
+impl<T: SizeOf> SizeOf for Option<T> {
+    #[inline]
+    fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+        self.as_ref().map_or(0, |x| x.size_of(ops))
+    }
+}
+
+impl<T: ?Sized> SizeOf for &T {
+    /// A reference does not own the value it points to
+    #[inline]
+    fn size_of(&self, _ops: &mut SizeOfOps) -> usize {
+        0
+    }
+}
+
+macro_rules! size_of_plain {
+    ($($t:ty),*) => {
+        $(
+            impl SizeOf for $t {
+                #[inline]
+                fn size_of(&self, _ops: &mut SizeOfOps) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+size_of_plain!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64
+);
+
+macro_rules! size_of_tuple {
+    ($($t:ident),*) => {
+        impl<$($t: SizeOf),*> SizeOf for ($($t,)*) {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+                let ($($t,)*) = self;
+                0 $(+ $t.size_of(ops))*
+            }
+        }
+    };
+}
+
+size_of_tuple!(A);
+size_of_tuple!(A, B);
+size_of_tuple!(A, B, C);
+size_of_tuple!(A, B, C, D);
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use crate::{static_arc, Arc, ArcBox, ArcInner, ArcRef, SharedAttribution, SizeOf, SizeOfOps};
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::mem::size_of;
+
+    #[test]
+    fn shared_once() {
+        let s: Arc<str> = Arc::from("hello");
+        let s_size = ArcInner::<str>::layout("hello").0.size();
+        let inner = Arc::new((s.clone(), String::with_capacity(10)));
+        let inner_size = ArcInner::<(Arc<str>, String)>::layout(&inner).0.size();
+        let outer = vec![inner.clone(), inner.clone()];
+
+        let mut ops = SizeOfOps::new(SharedAttribution::FirstOwner);
+        assert_eq!(s.size_of(&mut ops), s_size);
+        assert_eq!(
+            outer.size_of(&mut ops),
+            outer.capacity() * size_of::<Arc<(Arc<str>, String)>>() + inner_size + 10
+        );
+        assert_eq!(inner.size_of(&mut ops), 0);
+
+        // `inner` has three owners, and `s` two
+        let mut ops = SizeOfOps::new(SharedAttribution::Proportional);
+        assert_eq!(inner.size_of(&mut ops), (inner_size + 10 + s_size / 2) / 3);
+
+        let unique = ArcBox::new(String::with_capacity(4));
+        let unique_size = ArcInner::<String>::layout(&unique).0.size() + 4;
+        let mut ops = SizeOfOps::default();
+        assert_eq!(unique.size_of(&mut ops), unique_size);
+        assert_eq!(unique.size_of(&mut ops), unique_size);
+
+        let x = Arc::new(vec![1u8; 3]);
+        let x_size = ArcInner::<Vec<u8>>::layout(&x).0.size() + x.capacity();
+        let owned = ArcRef::from_arc(x.clone());
+        let borrowed = ArcRef::from_borrow(Arc::borrow_arc(&x));
+        let mut ops = SizeOfOps::default();
+        assert_eq!(borrowed.size_of(&mut ops), 0);
+        assert_eq!(owned.size_of(&mut ops), x_size);
+        assert_eq!(x.size_of(&mut ops), 0);
+    }
+
+    #[test]
+    fn immortal() {
+        static_arc! {
+            static NUMBERS: [u64; 4] = [1, 2, 3, 4];
+        }
+        let x = Arc::from_static(&NUMBERS);
+        let empty: Arc<[u64]> = Arc::default();
+        let names = Arc::new(String::with_capacity(8));
+        Arc::make_immortal(&names);
+        for attribution in [
+            SharedAttribution::FirstOwner,
+            SharedAttribution::Proportional,
+        ] {
+            let mut ops = SizeOfOps::new(attribution);
+            assert_eq!(x.size_of(&mut ops), 0);
+            assert_eq!(empty.size_of(&mut ops), 0);
+            // Only the data of an immortal allocation is counted, once
+            assert_eq!(names.size_of(&mut ops), 8);
+            assert_eq!(names.size_of(&mut ops), 0);
+        }
+    }
+
+    #[test]
+    fn deep_dag() {
+        struct Node(Vec<Arc<Node>>);
+
+        impl SizeOf for Node {
+            fn size_of(&self, ops: &mut SizeOfOps) -> usize {
+                self.0.size_of(ops)
+            }
+        }
+
+        // A chain of 100 diamonds, in which each node is reached along 2^depth paths
+        let node_size = ArcInner::<Node>::layout(&Node(Vec::new())).0.size();
+        let edges_size = 2 * size_of::<Arc<Node>>();
+        let mut node = Arc::new(Node(Vec::new()));
+        let mut first_owner = node_size;
+        let mut proportional = node_size;
+        for _ in 0..100 {
+            node = Arc::new(Node(vec![node.clone(), node]));
+            first_owner += node_size + edges_size;
+            proportional = node_size + edges_size + 2 * (proportional / 2);
+        }
+        let mut ops = SizeOfOps::new(SharedAttribution::FirstOwner);
+        assert_eq!(node.size_of(&mut ops), first_owner);
+        let mut ops = SizeOfOps::new(SharedAttribution::Proportional);
+        assert_eq!(node.size_of(&mut ops), proportional);
+    }
+}
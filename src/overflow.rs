@@ -0,0 +1,186 @@
+//! What happens when a reference count overflows
+//!
+//! A reference count can only overflow if a program [`mem::forget`][`core::mem::forget`]s or
+//! leaks an enormous number of pointers to the same allocation, and never does in any realistic
+//! program. When it happens, continuing would lead to a use after free, so every pointer in this
+//! crate instead applies the [`OVERFLOW_POLICY`], which is selected by feature:
+//!
+//! * By default, the process is aborted. With the `std` feature, this calls
+//!   [`std::process::abort`]; without it, this panics while already panicking, unless a custom
+//!   handler has been installed with [`set_abort_handler`].
+//! * With the `overflow-saturate` feature, the count is saturated instead, and the allocation is
+//!   leaked: for an [`Arc`][`crate::Arc`], the allocation becomes immortal, as after
+//!   [`Arc::make_immortal`][`crate::Arc::make_immortal`].
+//! * With the `overflow-panic` feature, the clone which overflowed the count panics, leaving the
+//!   count as it was before.
+//!
+//! Since features are additive, different crates in the same build may enable both. In that case,
+//! `overflow-panic` takes precedence over `overflow-saturate`, which takes precedence over aborting.
+
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering::Acquire, Ordering::Release};
+
+/// What to do when a reference count overflows
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Abort the process, through the handler installed with [`set_abort_handler`] if any
+    Abort,
+    /// Leak the allocation, which stays valid forever
+    Saturate,
+    /// Panic, without changing the count
+    Panic,
+}
+
+/// The policy applied when a reference count overflows in this build
+#[cfg(feature = "overflow-panic")]
+pub const OVERFLOW_POLICY: OverflowPolicy = OverflowPolicy::Panic;
+
+/// The policy applied when a reference count overflows in this build
+#[cfg(all(feature = "overflow-saturate", not(feature = "overflow-panic")))]
+pub const OVERFLOW_POLICY: OverflowPolicy = OverflowPolicy::Saturate;
+
+/// The policy applied when a reference count overflows in this build
+#[cfg(not(any(feature = "overflow-saturate", feature = "overflow-panic")))]
+pub const OVERFLOW_POLICY: OverflowPolicy = OverflowPolicy::Abort;
+
+/// Whether overflowed counts are saturated, in which case saturated counts must never be written
+/// to again
+pub(crate) const SATURATE: bool = matches!(OVERFLOW_POLICY, OverflowPolicy::Saturate);
+
+/// The custom abort handler, if any, as a `fn() -> !`
+static ABORT_HANDLER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Install a custom handler to abort the process, replacing the default one
+///
+/// This is called whenever this crate needs to abort, i.e. when a reference count overflows with
+/// the default [`OverflowPolicy::Abort`]. Without the `std` feature, the default handler aborts by
+/// panicking while already panicking, which may be unacceptable in embedded environments; these
+/// should install a handler which resets or halts the device.
+///
+/// # Examples
+///
+/// ```
+/// fn halt() -> ! {
+///     // e.g. reset the device
+///     loop {}
+/// }
+///
+/// elysees::set_abort_handler(halt);
+/// ```
+pub fn set_abort_handler(handler: fn() -> !) {
+    ABORT_HANDLER.store(handler as *mut (), Release)
+}
+
+/// Abort the process, through the custom handler if one has been installed
+#[cold]
+pub(crate) fn abort() -> ! {
+    let handler = ABORT_HANDLER.load(Acquire);
+    if !handler.is_null() {
+        // Safety: the only non-null values ever stored are `fn() -> !`s
+        let handler = unsafe { mem::transmute::<*mut (), fn() -> !>(handler) };
+        handler()
+    }
+    default_abort()
+}
+
+#[cfg(feature = "std")]
+use std::process::abort as default_abort;
+
+// `no_std`-compatible abort by forcing a panic while already panicing.
+#[cfg(not(feature = "std"))]
+#[cold]
+fn default_abort() -> ! {
+    struct PanicOnDrop;
+    impl Drop for PanicOnDrop {
+        fn drop(&mut self) {
+            panic!()
+        }
+    }
+    let _double_panicer = PanicOnDrop;
+    panic!();
+}
+
+/// Apply the [`OVERFLOW_POLICY`] to a reference count which has overflowed, and which the caller
+/// has already restored to its previous value
+///
+/// This only returns if the policy is to saturate, in which case the caller should saturate the
+/// count.
+#[cold]
+#[inline(never)]
+pub(crate) fn refcount_overflow() {
+    match OVERFLOW_POLICY {
+        OverflowPolicy::Abort => abort(),
+        OverflowPolicy::Panic => panic!("elysees: reference count overflow"),
+        OverflowPolicy::Saturate => {}
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::arc32::MAX_REFCOUNT_32;
+    use crate::{Arc, Arc32, ArcBorrow, ArcInner, ArcInner32, Rc, RcInner, MAX_REFCOUNT};
+    use core::sync::atomic::Ordering::{AcqRel, Relaxed};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    /// Restores the previous abort handler when dropped
+    struct RestoreAbortHandler(*mut ());
+
+    impl Drop for RestoreAbortHandler {
+        fn drop(&mut self) {
+            ABORT_HANDLER.store(self.0, Release)
+        }
+    }
+
+    #[test]
+    fn overflow() {
+        // Abort by panicking, so that the default policy can be tested too. Other tests in this
+        // binary may run concurrently, but none of them aborts.
+        let handler: fn() -> ! = || panic!("aborted");
+        let _restore = RestoreAbortHandler(ABORT_HANDLER.swap(handler as *mut (), AcqRel));
+
+        let x = Arc::new(5);
+        let count = unsafe { &*ArcInner::<i32>::count_ptr(Arc::as_ptr(&x) as *mut i32) };
+        count.store(MAX_REFCOUNT + 1, Relaxed);
+        let y = catch_unwind(AssertUnwindSafe(|| {
+            ArcBorrow::clone_arc(Arc::borrow_arc(&x))
+        }));
+        if SATURATE {
+            assert!(Arc::is_immortal(&x));
+            assert!(Arc::ptr_eq(&x, &y.unwrap()));
+        } else {
+            assert!(y.is_err());
+            assert_eq!(count.load(Relaxed), MAX_REFCOUNT + 1);
+        }
+        count.store(1, Relaxed);
+        drop(x);
+
+        let x = Arc32::new(5);
+        let count = unsafe { &*ArcInner32::<i32>::count_ptr(Arc32::as_ptr(&x) as *mut i32) };
+        count.store(MAX_REFCOUNT_32 + 1, Relaxed);
+        let y = catch_unwind(AssertUnwindSafe(|| x.clone()));
+        if SATURATE {
+            drop(y.unwrap());
+            assert!(count.load(Relaxed) > MAX_REFCOUNT_32);
+        } else {
+            assert!(y.is_err());
+            assert_eq!(count.load(Relaxed), MAX_REFCOUNT_32 + 1);
+        }
+        count.store(1, Relaxed);
+        drop(x);
+
+        let x = Rc::new(5);
+        let count = unsafe { &*RcInner::<i32>::count_ptr(Rc::as_ptr(&x) as *mut i32) };
+        count.set(MAX_REFCOUNT + 1);
+        let y = catch_unwind(AssertUnwindSafe(|| x.clone()));
+        if SATURATE {
+            drop(y.unwrap());
+        } else {
+            assert!(y.is_err());
+        }
+        assert_eq!(count.get(), MAX_REFCOUNT + 1);
+        count.set(1);
+        drop(x);
+    }
+}
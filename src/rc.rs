@@ -0,0 +1,716 @@
+use core::cell::Cell;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::mem;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::ptr;
+use core::str;
+
+use erasable::{Erasable, ErasedPtr};
+
+use crate::shared_ptr::{impl_borrow, impl_ref, impl_shared_ptr, impl_unique};
+
+use crate::{
+    refcount_overflow, AllocError, Allocator, ArcInner, Count, FromUtf8Error, Global, MAX_REFCOUNT,
+    SATURATE,
+};
+#[cfg(not(loom))]
+use crate::{Arc, ArcBox, ArcCount};
+
+/// The object allocated by an [`Rc<T>`][`Rc`]
+///
+/// This is an [`ArcInner`] with a non-atomic reference count, and so has exactly the same layout as
+/// the [`ArcInner`] of an [`Arc<T>`][`crate::Arc`].
+pub type RcInner<T, A = Global> = ArcInner<T, A, Cell<usize>>;
+
+impl Count for Cell<usize> {
+    #[inline]
+    fn one() -> Self {
+        Cell::new(1)
+    }
+}
+
+/// A single-threaded reference counted shared pointer
+///
+/// See the documentation for [`Rc`][rr] in the standard library. Unlike the
+/// standard library [`Rc`][rr], this [`Rc`] does not support weak reference counting.
+///
+/// This is the non-atomic counterpart of [`Arc`][`crate::Arc`], with the same representation: it
+/// points directly to its data, and is always a single pointer wide (or two, for unsized `T`),
+/// whatever the allocator.
+///
+/// Since its reference count is not atomic, an [`Rc`] is neither [`Send`] nor [`Sync`]:
+///
+/// ```compile_fail,E0277
+/// fn assert_send<T: Send>() {}
+/// assert_send::<elysees::Rc<u32>>();
+/// ```
+///
+/// ```compile_fail,E0277
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<elysees::Rc<u32>>();
+/// ```
+///
+/// [rr]: https://doc.rust-lang.org/stable/std/rc/struct.Rc.html
+#[repr(transparent)]
+pub struct Rc<T: ?Sized, A: Allocator = Global> {
+    pub(crate) p: ptr::NonNull<T>,
+    phantom: PhantomData<RcInner<T, A>>,
+}
+
+impl<T> Rc<T> {
+    /// Construct an [`Rc`]
+    #[inline]
+    pub fn new(data: T) -> Self {
+        Rc::new_in(data, Global)
+    }
+
+    /// Construct an [`Rc`], returning an error if the allocation fails
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Rc;
+    ///
+    /// let x = Rc::try_new(3).unwrap();
+    /// assert_eq!(*x, 3);
+    /// ```
+    #[inline]
+    pub fn try_new(data: T) -> Result<Self, AllocError> {
+        Rc::try_new_in(data, Global)
+    }
+}
+
+impl<T, A: Allocator> Rc<T, A> {
+    /// Construct an [`Rc`] in the given allocator
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Rc, Global};
+    ///
+    /// let x = Rc::new_in(3, Global);
+    /// assert_eq!(*x, 3);
+    /// ```
+    #[inline]
+    pub fn new_in(data: T, alloc: A) -> Self {
+        Rc {
+            p: RcInner::<T, A>::new_in(data, alloc),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Construct an [`Rc`] in the given allocator, returning an error if the allocation fails
+    #[inline]
+    pub fn try_new_in(data: T, alloc: A) -> Result<Self, AllocError> {
+        Ok(Rc {
+            p: RcInner::<T, A>::try_new_in(data, alloc)?,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Transform an [`Rc`] into an allocated [`RcInner`].
+    #[inline]
+    pub(crate) fn into_raw_inner(self) -> ptr::NonNull<RcInner<T, A>> {
+        let p = self.p.as_ptr();
+        core::mem::forget(self);
+        unsafe { ptr::NonNull::new_unchecked(RcInner::from_data(p)) }
+    }
+
+    /// Construct an [`Rc`] from an allocated [`RcInner`].
+    /// # Safety
+    /// The `ptr` must point to a valid instance, allocated by an [`Rc`]. The reference count will
+    /// not be modified.
+    #[inline]
+    pub(crate) unsafe fn from_raw_inner(p: ptr::NonNull<RcInner<T, A>>) -> Self {
+        Rc {
+            p: ptr::NonNull::new_unchecked(RcInner::data_ptr(p.as_ptr())),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the inner value, if the [`Rc`] has exactly one strong reference.
+    ///
+    /// Otherwise, an [`Err`] is returned with the same [`Rc`] that was
+    /// passed in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Rc;
+    ///
+    /// let x = Rc::new(3);
+    /// assert_eq!(Rc::try_unwrap(x), Ok(3));
+    ///
+    /// let x = Rc::new(4);
+    /// let _y = Rc::clone(&x);
+    /// assert_eq!(*Rc::try_unwrap(x).unwrap_err(), 4);
+    /// ```
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        Self::try_unique(this).map(RcBox::into_inner)
+    }
+}
+
+impl<T: ?Sized> Rc<T> {
+    /// Reconstruct the [`Rc<T>`][`Rc`] from a raw pointer obtained from [`into_raw`][`Rc::into_raw`]
+    ///
+    /// # Safety
+    /// The pointer `ptr` must be the result of a call to [`into_raw`][`Rc::into_raw`]
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const T) -> Self {
+        Rc::from_raw_in(ptr)
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Rc<T, A> {
+    /// Reconstruct the [`Rc<T, A>`][`Rc`] from a raw pointer obtained from
+    /// [`into_raw`][`Rc::into_raw`]
+    ///
+    /// Since the allocator is stored in the allocation itself, it does not need to be passed in.
+    ///
+    /// # Safety
+    /// The pointer `ptr` must be the result of a call to [`into_raw`][`Rc::into_raw`] on an
+    /// [`Rc<T, A>`][`Rc`] with the same allocator type `A`
+    #[inline]
+    pub unsafe fn from_raw_in(ptr: *const T) -> Self {
+        #[cfg(feature = "header-canary")]
+        RcInner::<T, A>::check_canary(ptr, "Rc::from_raw");
+        Rc {
+            p: ptr::NonNull::new_unchecked(ptr as *mut T),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Convert the [`Rc`] to a raw pointer, suitable for use across FFI
+    #[inline]
+    pub fn into_raw(this: Self) -> *const T {
+        let ptr = Rc::as_ptr(&this);
+        mem::forget(this);
+        ptr
+    }
+
+    /// Returns the raw pointer.
+    ///
+    /// Same as into_raw except `self` isn't consumed.
+    #[inline]
+    pub fn as_ptr(this: &Self) -> *const T {
+        this.p.as_ptr()
+    }
+
+    /// Get a reference to the allocator this [`Rc`] was allocated with
+    #[inline]
+    pub fn allocator(this: &Self) -> &A {
+        unsafe { &*RcInner::<T, A>::alloc_ptr(this.p.as_ptr()) }
+    }
+
+    /// Produce a pointer to the data that can be converted back
+    /// to an Rc. This is basically an [`&Rc<T>`][`Rc`], without the extra indirection.
+    /// It has the benefits of an `&T` but also knows about the underlying refcount
+    /// and can be converted into more [`Rc<T>`][`Rc`]s if necessary.
+    #[inline]
+    pub fn borrow_rc(this: &Self) -> RcBorrow<'_, T, A> {
+        RcBorrow {
+            p: this.p,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the address on the heap of the [`Rc`] itself -- not the `T` within it -- for memory
+    /// reporting.
+    pub fn heap_ptr(&self) -> *const c_void {
+        unsafe { RcInner::<T, A>::alloc_ptr(self.p.as_ptr()) as *const c_void }
+    }
+
+    /// Test pointer equality between the two [`Rc`]s, i.e. they must be the _same_
+    /// allocation
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        core::ptr::eq(this.p.as_ptr(), other.p.as_ptr())
+    }
+
+    /// Leak this [`Rc<T>`][`Rc`], getting an [`RcBorrow<'static, T>`][`RcBorrow`]
+    ///
+    /// You can call the [`get`][`RcBorrow::get`] method on the returned [`RcBorrow`] to get an `&'static T`.
+    /// Note that using this can (obviously) cause memory leaks!
+    #[inline]
+    pub fn leak(this: Self) -> RcBorrow<'static, T, A> {
+        let result = RcBorrow {
+            p: this.p,
+            phantom: PhantomData,
+        };
+        mem::forget(this);
+        result
+    }
+}
+
+impl<T> Rc<MaybeUninit<T>> {
+    /// Create an [`Rc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`].
+    pub fn new_uninit() -> Self {
+        Rc::new_uninit_in(Global)
+    }
+
+    /// Create an [`Rc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`], returning an
+    /// error if the allocation fails
+    pub fn try_new_uninit() -> Result<Self, AllocError> {
+        Rc::try_new_uninit_in(Global)
+    }
+}
+
+impl<T, A: Allocator> Rc<MaybeUninit<T>, A> {
+    /// Create an [`Rc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`] in the given
+    /// allocator
+    pub fn new_uninit_in(alloc: A) -> Self {
+        Rc::new_in(MaybeUninit::<T>::uninit(), alloc)
+    }
+
+    /// Create an [`Rc`] containing a [`MaybeUninit<T>`][`core::mem::MaybeUninit`] in the given
+    /// allocator, returning an error if the allocation fails
+    pub fn try_new_uninit_in(alloc: A) -> Result<Self, AllocError> {
+        Rc::try_new_in(MaybeUninit::<T>::uninit(), alloc)
+    }
+
+    /// Calls `MaybeUninit::write` on the value contained.
+    pub fn write(&mut self, val: T) -> &mut T {
+        unsafe {
+            self.p.as_ptr().write(MaybeUninit::new(val));
+            &mut *self.p.as_mut().as_mut_ptr()
+        }
+    }
+
+    /// Obtain a mutable pointer to the stored `MaybeUninit<T>`.
+    pub fn as_mut_ptr(&mut self) -> *mut MaybeUninit<T> {
+        self.p.as_ptr()
+    }
+
+    /// # Safety
+    ///
+    /// Must initialize all fields before calling this function.
+    #[inline]
+    pub unsafe fn assume_init(self) -> Rc<T, A> {
+        #[cfg(feature = "track-allocations")]
+        crate::debug::retype::<T>(self.heap_ptr());
+        Rc::from_raw_in(Rc::into_raw(self) as *const T)
+    }
+}
+
+impl<T> Rc<[MaybeUninit<T>]> {
+    /// Create an [`Rc`] contains an array `[MaybeUninit<T>]` of `len`.
+    pub fn new_uninit_slice(len: usize) -> Self {
+        Rc::new_uninit_slice_in(len, Global)
+    }
+
+    /// Create an [`Rc`] contains an array `[MaybeUninit<T>]` of `len`, returning an error if the
+    /// allocation fails or the size of the allocation would overflow
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::mem::MaybeUninit;
+    /// use elysees::Rc;
+    ///
+    /// assert!(Rc::<[MaybeUninit<u64>]>::try_new_uninit_slice(5).is_ok());
+    /// assert!(Rc::<[MaybeUninit<u64>]>::try_new_uninit_slice(usize::MAX).is_err());
+    /// ```
+    pub fn try_new_uninit_slice(len: usize) -> Result<Self, AllocError> {
+        Rc::try_new_uninit_slice_in(len, Global)
+    }
+}
+
+impl<T, A: Allocator> Rc<[MaybeUninit<T>], A> {
+    /// Create an [`Rc`] contains an array `[MaybeUninit<T>]` of `len` in the given allocator.
+    pub fn new_uninit_slice_in(len: usize, alloc: A) -> Self {
+        Rc {
+            p: RcInner::<[MaybeUninit<T>], A>::new_uninit_slice_in(len, alloc),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Create an [`Rc`] contains an array `[MaybeUninit<T>]` of `len` in the given allocator,
+    /// returning an error if the allocation fails or the size of the allocation would overflow
+    pub fn try_new_uninit_slice_in(len: usize, alloc: A) -> Result<Self, AllocError> {
+        Ok(Rc {
+            p: RcInner::<[MaybeUninit<T>], A>::try_new_uninit_slice_in(len, alloc)?,
+            phantom: PhantomData,
+        })
+    }
+
+    /// # Safety
+    ///
+    /// Must initialize all fields before calling this function.
+    #[inline]
+    pub unsafe fn assume_init(self) -> Rc<[T], A> {
+        Rc::from_raw_in(Rc::into_raw(self) as *const [T])
+    }
+}
+
+impl Rc<str> {
+    /// Convert an [`Rc<[u8]>`][`Rc`] into an [`Rc<str>`][`Rc`] without copying, if it contains
+    /// valid UTF-8
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Rc;
+    ///
+    /// let bytes: Rc<[u8]> = Rc::from(&b"hello"[..]);
+    /// let s = Rc::from_utf8(bytes.clone()).unwrap();
+    /// assert_eq!(&*s, "hello");
+    /// assert_eq!(Rc::count(&bytes), 2);
+    ///
+    /// let invalid: Rc<[u8]> = Rc::from(&[0xff, 0xfe][..]);
+    /// let err = Rc::from_utf8(invalid).unwrap_err();
+    /// assert_eq!(err.as_bytes(), [0xff, 0xfe]);
+    /// ```
+    #[inline]
+    pub fn from_utf8(bytes: Rc<[u8]>) -> Result<Rc<str>, FromUtf8Error<Rc<[u8]>>> {
+        match str::from_utf8(&bytes) {
+            // Safety: we have just checked that the bytes are valid UTF-8
+            Ok(_) => Ok(unsafe { Rc::from_utf8_unchecked(bytes) }),
+            Err(error) => Err(FromUtf8Error { bytes, error }),
+        }
+    }
+
+    /// Convert an [`Rc<[u8]>`][`Rc`] into an [`Rc<str>`][`Rc`] without copying or checking
+    /// that it contains valid UTF-8
+    ///
+    /// # Safety
+    /// The bytes must be valid UTF-8, as for [`str::from_utf8_unchecked`]
+    #[inline]
+    pub unsafe fn from_utf8_unchecked(bytes: Rc<[u8]>) -> Rc<str> {
+        #[cfg(feature = "track-allocations")]
+        crate::debug::retype::<str>(bytes.heap_ptr());
+        Rc::from_raw(Rc::into_raw(bytes) as *const str)
+    }
+
+    /// Convert an [`Rc<str>`][`Rc`] into an [`Rc<[u8]>`][`Rc`] without copying
+    #[inline]
+    pub fn into_bytes(this: Self) -> Rc<[u8]> {
+        #[cfg(feature = "track-allocations")]
+        crate::debug::retype::<[u8]>(this.heap_ptr());
+        unsafe { Rc::from_raw(Rc::into_raw(this) as *const [u8]) }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Clone for Rc<T, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        #[cfg(feature = "header-canary")]
+        unsafe {
+            RcInner::<T, A>::check_canary(self.p.as_ptr(), "Rc::clone")
+        };
+        let count = unsafe { &*RcInner::<T, A>::count_ptr(self.p.as_ptr()) };
+        let old_size = count.get();
+
+        // We need to guard against massive refcounts in case someone is
+        // `mem::forget`ing Rcs. If we don't do this the count can overflow
+        // and users will use-after free. This branch will never be taken in
+        // any realistic program.
+        //
+        // We apply the overflow policy, by default aborting, because such a
+        // program is incredibly degenerate, and we don't care to support it.
+        // A saturated count is left above `MAX_REFCOUNT`, and never written to
+        // again.
+        if old_size > MAX_REFCOUNT {
+            refcount_overflow();
+        } else {
+            count.set(old_size + 1);
+        }
+
+        Rc {
+            p: self.p,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Rc<T, A> {
+    /// Makes a mutable reference to the [`Rc`], cloning if necessary
+    ///
+    /// This is functionally equivalent to [`Rc::make_mut`][mm] from the standard library.
+    ///
+    /// If this [`Rc`] is uniquely owned, `make_mut()` will provide a mutable
+    /// reference to the contents. If not, `make_mut()` will create a _new_ [`Rc`]
+    /// with a copy of the contents, update `this` to point to it, and provide
+    /// a mutable reference to its contents.
+    ///
+    /// This is useful for implementing copy-on-write schemes where you wish to
+    /// avoid copying things if your [`Rc`] is not shared.
+    ///
+    /// [mm]: https://doc.rust-lang.org/stable/std/rc/struct.Rc.html#method.make_mut
+    #[inline]
+    pub fn make_mut(this: &mut Self) -> &mut T {
+        if !Self::is_unique(this) {
+            // Another pointer exists; clone
+            *this = Rc::new_in((**this).clone(), Rc::allocator(this).clone());
+        }
+        debug_assert!(Self::is_unique(this));
+
+        unsafe {
+            // This unsafety is ok because we're guaranteed that the pointer
+            // returned is the *only* pointer that will ever be returned to T. Our
+            // reference count is guaranteed to be 1 at this point, and we required
+            // the Rc itself to be `mut`, so we're returning the only possible
+            // reference to the inner data.
+            this.p.as_mut()
+        }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Rc<T, A> {
+    /// Provides mutable access to the contents _if_ the [`Rc`] is uniquely owned.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if Self::is_unique(this) {
+            unsafe {
+                // See make_mut() for documentation of the safety here.
+                Some(this.p.as_mut())
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Whether or not the [`Rc`] is uniquely owned (is the refcount 1?).
+    #[inline]
+    pub fn is_unique(this: &Self) -> bool {
+        Self::count(this) == 1
+    }
+
+    /// Gets the number of [`Rc`] pointers to this allocation
+    #[inline]
+    pub fn count(this: &Self) -> usize {
+        unsafe { (*RcInner::<T, A>::count_ptr(this.p.as_ptr())).get() }
+    }
+
+    /// Returns an [`RcBox`] if the [`Rc`] has exactly one strong reference.
+    ///
+    /// Otherwise, an [`Err`] is returned with the same [`Rc`] that was
+    /// passed in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Rc, RcBox};
+    ///
+    /// let x = Rc::new(3);
+    /// assert_eq!(RcBox::into_inner(Rc::try_unique(x).unwrap()), 3);
+    ///
+    /// let x = Rc::new(4);
+    /// let _y = Rc::clone(&x);
+    /// assert_eq!(
+    ///     *Rc::try_unique(x).map(RcBox::into_inner).unwrap_err(),
+    ///     4,
+    /// );
+    /// ```
+    #[inline]
+    pub fn try_unique(this: Self) -> Result<RcBox<T, A>, Self> {
+        if Self::is_unique(&this) {
+            // Safety: The current rc is unique and making a `RcBox`
+            //         from it is sound
+            unsafe { Ok(RcBox::from_rc(this)) }
+        } else {
+            Err(this)
+        }
+    }
+
+    /// Convert this [`Rc`] to an [`RcBox`], cloning the internal data if necessary for uniqueness
+    #[inline]
+    pub fn unique(this: Self) -> RcBox<T, A>
+    where
+        T: Clone,
+        A: Clone,
+    {
+        if Self::is_unique(&this) {
+            RcBox(this)
+        } else {
+            RcBox::new_in(this.deref().clone(), Rc::allocator(&this).clone())
+        }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Drop for Rc<T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "header-canary")]
+        unsafe {
+            RcInner::<T, A>::check_canary(self.p.as_ptr(), "Rc::drop")
+        };
+        let count = unsafe { &*RcInner::<T, A>::count_ptr(self.p.as_ptr()) };
+        let old_size = count.get();
+        if SATURATE && old_size > MAX_REFCOUNT {
+            return;
+        }
+        count.set(old_size - 1);
+        if old_size == 1 {
+            unsafe {
+                RcInner::<T, A>::drop_slow(self.p.as_ptr());
+            }
+        }
+    }
+}
+
+impl_shared_ptr!(Rc, RcInner, RcBox);
+
+/// A "borrowed [`Rc`]". This is essentially a reference to an `RcInner<T>`
+///
+/// This is equivalent in guarantees to [`&Rc<T>`][`Rc`], however it has the same representation as an [`Rc<T>`], minimizing pointer-chasing.
+///
+/// [`RcBorrow`] lets us deal with borrows of known-refcounted objects
+/// without needing to worry about where the [`Rc<T>`][`Rc`] is.
+#[repr(transparent)]
+pub struct RcBorrow<'a, T: ?Sized + 'a, A: Allocator + 'a = Global> {
+    pub(crate) p: ptr::NonNull<T>,
+    pub(crate) phantom: PhantomData<&'a RcInner<T, A>>,
+}
+
+impl_borrow!(RcBorrow, Rc, RcInner, RcRef; clone_rc, as_rc, as_rc_ref);
+
+impl<'a, T: ?Sized, A: Allocator> RcBorrow<'a, T, A> {
+    /// Gets the number of [`Rc`] pointers to this allocation
+    #[inline]
+    pub fn count(this: Self) -> usize {
+        unsafe { (*RcInner::<T, A>::count_ptr(this.p.as_ptr())).get() }
+    }
+}
+
+/// A single-threaded reference counted shared pointer, which may hold either exactly 0 references (in which case it is analogous to an [`RcBorrow`])
+/// or 1 (in which case it is analogous to an [`Rc`])
+#[repr(transparent)]
+pub struct RcRef<'a, T: ?Sized + Erasable, A: Allocator = Global> {
+    pub(crate) p: ErasedPtr,
+    pub(crate) phantom: PhantomData<&'a RcInner<T, A>>,
+}
+
+impl_ref!(RcRef, Rc, RcInner, RcBorrow, RcBox; from_rc, try_into_rc, borrow_rc, into_rc, clone_rc);
+
+impl<'a, T: ?Sized + Erasable, A: Allocator> RcRef<'a, T, A> {
+    /// Gets the number of [`Rc`] pointers to this allocation
+    #[inline]
+    pub fn count(this: &Self) -> usize {
+        unsafe { (*RcInner::<T, A>::count_ptr(this.ptr())).get() }
+    }
+}
+
+/// An [`Rc`] that is known to be uniquely owned
+///
+/// When [`Rc`]s are constructed, they are known to be
+/// uniquely owned. In such a case it is safe to mutate
+/// the contents of the [`Rc`]. Normally, one would just handle
+/// this by mutating the data on the stack before allocating the
+/// [`Rc`], however it's possible the data is large or unsized
+/// and you need to heap-allocate it earlier in such a way
+/// that it can be freely converted into a regular [`Rc`] once you're
+/// done.
+///
+/// [`RcBox`] exists for this purpose, when constructed it performs
+/// the same allocations necessary for an [`Rc`], however it allows mutable access.
+/// Once the mutation is finished, you can call [`.shareable()`](`RcBox::shareable`) and get a regular [`Rc`]
+/// out of it.
+///
+/// ```rust
+/// # use elysees::RcBox;
+/// let data = [1, 2, 3, 4, 5];
+/// let mut x = RcBox::new(data);
+/// x[4] = 7; // mutate!
+/// let y = x.shareable(); // y is an Rc<T>
+/// ```
+#[repr(transparent)]
+pub struct RcBox<T: ?Sized, A: Allocator = Global>(pub(crate) Rc<T, A>);
+
+impl_unique!(RcBox, Rc, RcInner, RcRef, Cell<usize>; from_rc);
+
+// An `RcInner` and an `ArcInner` differ only in the type of their reference count, so this
+// guarantees that they have the same layout and that an allocation can be shared between them.
+// Under `loom`, the reference count of an `ArcInner` is loom's atomic, so they cannot be.
+#[cfg(not(loom))]
+const _: () = assert!(
+    mem::size_of::<Cell<usize>>() == mem::size_of::<ArcCount>()
+        && mem::align_of::<Cell<usize>>() == mem::align_of::<ArcCount>()
+);
+
+#[cfg(not(loom))]
+impl<T: ?Sized, A: Allocator> From<ArcBox<T, A>> for RcBox<T, A> {
+    /// Convert an [`ArcBox`] into an [`RcBox`] without reallocating
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{ArcBox, RcBox};
+    ///
+    /// let mut x = RcBox::from(ArcBox::new(5));
+    /// *x += 1;
+    /// assert_eq!(*x, 6);
+    /// ```
+    #[inline]
+    fn from(b: ArcBox<T, A>) -> Self {
+        // Safety: since `b` is uniquely owned, no other thread can access its reference count, so
+        //         it is sound to treat it as non-atomic. The layouts are checked to match above.
+        unsafe { RcBox(Rc::from_raw_in(Arc::into_raw(b.0))) }
+    }
+}
+
+#[cfg(not(loom))]
+impl<T: ?Sized, A: Allocator> From<RcBox<T, A>> for ArcBox<T, A> {
+    /// Convert an [`RcBox`] into an [`ArcBox`] without reallocating
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::{Arc, ArcBox, RcBox};
+    ///
+    /// let x = ArcBox::from(RcBox::new(5)).shareable();
+    /// let y = x.clone();
+    /// assert_eq!(Arc::count(&y), 2);
+    /// ```
+    #[inline]
+    fn from(b: RcBox<T, A>) -> Self {
+        // Safety: since `b` is uniquely owned, its reference count cannot be accessed from any
+        //         other thread. The layouts are checked to match above.
+        unsafe { ArcBox(Arc::from_raw_in(Rc::into_raw(b.0))) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rc::{Rc, RcBorrow, RcBox, RcInner, RcRef};
+
+    /// The behaviour shared with `Arc` is tested there, since both are generated by the same
+    /// macros; this only tests what differs, i.e. the non-atomic count
+    #[test]
+    fn cell_count() {
+        let x = Rc::new(5u32);
+        let count = unsafe { &*RcInner::<u32>::count_ptr(Rc::as_ptr(&x) as *mut u32) };
+        assert_eq!(count.get(), 1);
+        let y = RcBorrow::clone_rc(Rc::borrow_rc(&x));
+        let z = RcRef::from_rc(x.clone());
+        let w = RcRef::from_borrow(Rc::borrow_rc(&y));
+        assert_eq!(count.get(), 3);
+        assert_eq!(RcRef::count(&z), 3);
+        assert_eq!(RcRef::count(&w), 3);
+        drop(w);
+        drop((y, z));
+        assert_eq!(count.get(), 1);
+        assert!(Rc::is_unique(&x));
+        assert_eq!(RcBox::into_inner(Rc::try_unique(x).unwrap()), 5);
+    }
+
+    #[cfg(not(loom))]
+    #[test]
+    fn arc_box_conversions() {
+        use crate::{Arc, ArcBox};
+
+        let x = ArcBox::new(alloc::vec![1, 2, 3]);
+        let p: *const alloc::vec::Vec<i32> = &*x;
+        let mut y = RcBox::from(x);
+        y.push(4);
+        let z: Arc<_> = ArcBox::from(y).shareable();
+        assert_eq!(Arc::as_ptr(&z), p);
+        assert_eq!(*z, [1, 2, 3, 4]);
+
+        let s: RcBox<str> = RcBox::from(ArcBox::from("hello"));
+        let s: Rc<str> = s.shareable();
+        assert_eq!(Rc::count(&s.clone()), 2);
+        assert_eq!(&*s, "hello");
+    }
+}
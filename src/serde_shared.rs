@@ -0,0 +1,355 @@
+//! Sharing-preserving serialization of [`Arc`]s
+//!
+//! By default, an [`Arc`] is serialized as its contents, so an object graph in which the same
+//! [`Arc`] is reachable many times is duplicated on serialization, and comes back as distinct
+//! allocations on deserialization. This module instead records the identity of each allocation
+//! (by [`Arc::as_ptr`]): the first occurrence of an allocation is serialized as a pair of a fresh
+//! ID and its contents, and later occurrences as the ID alone, so that deserializing restores
+//! the original sharing.
+//!
+//! Use [`serialize`] and [`deserialize`] with `#[serde(with = "elysees::serde_shared")]` on an
+//! [`Arc`] field, or wrap [`Arc`]s nested in other containers in a [`Shared`]. All shared
+//! (de)serialization must happen inside a [`Session`], a wrapper around the value being
+//! (de)serialized which owns the IDs: sharing is preserved within a session, and each session's
+//! output is self-contained. Since the IDs are reset every time the format (de)serializes the
+//! [`Session`], formats which serialize a value several times, such as `bincode::serialize`,
+//! which first serializes it to compute its size, produce complete output on every pass.
+//!
+//! Each [`Arc`] is serialized as a tuple of an ID and an [`Option`], and so works with both
+//! self-describing formats such as JSON and binary formats such as `bincode`.
+//!
+//! # Examples
+//!
+//! ```
+//! use elysees::serde_shared::{self, Session, Shared};
+//! use elysees::Arc;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Node {
+//!     name: String,
+//!     children: Vec<Shared<Node>>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Graph {
+//!     #[serde(with = "serde_shared")]
+//!     root: Arc<Node>,
+//! }
+//!
+//! let leaf = Shared::from(Arc::new(Node { name: "leaf".into(), children: vec![] }));
+//! let root = Arc::new(Node { name: "root".into(), children: vec![leaf.clone(), leaf] });
+//! let json = serde_json::to_string(&Session(Graph { root })).unwrap();
+//! let Session(graph): Session<Graph> = serde_json::from_str(&json).unwrap();
+//! assert!(Arc::ptr_eq(&graph.root.children[0], &graph.root.children[1]));
+//! ```
+
+use std::any::{Any, TypeId};
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeTuple, Serializer};
+
+use crate::{Allocator, Arc, Global};
+
+/// The state of a session
+#[derive(Default)]
+struct State {
+    /// The allocations serialized so far, by address and type, along with their IDs. Each entry
+    /// holds a clone of the [`Arc`], so that its address cannot be reused during the session.
+    serialized: HashMap<(usize, TypeId), (u64, Box<dyn Any>)>,
+    /// The allocations deserialized so far, by ID
+    deserialized: HashMap<u64, Box<dyn Any>>,
+}
+
+thread_local! {
+    /// The states of the sessions being (de)serialized, innermost last
+    static SESSIONS: RefCell<Vec<State>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A value whose (de)serialization is a session, within which [`Arc`]s (de)serialized with this
+/// module preserve their sharing
+///
+/// A [`Session`] is (de)serialized as its contents, starting from fresh IDs every time. Sessions
+/// may be nested, in which case the inner session is independent of the outer one: an [`Arc`]
+/// shared between the two is written out in full in each, and comes back as two allocations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Session<T>(pub T);
+
+impl<T> Session<T> {
+    /// Get the wrapped value
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Serialize> Serialize for Session<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        in_session(|| self.0.serialize(serializer))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Session<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        in_session(|| T::deserialize(deserializer)).map(Session)
+    }
+}
+
+/// Run `f` inside a fresh session
+fn in_session<R>(f: impl FnOnce() -> R) -> R {
+    /// Ends the session on drop, even if `f` panics
+    struct End;
+
+    impl Drop for End {
+        fn drop(&mut self) {
+            // Drop the state outside of the borrow, since it may drop arbitrary values
+            let state = SESSIONS.with(|sessions| sessions.borrow_mut().pop());
+            drop(state)
+        }
+    }
+
+    SESSIONS.with(|sessions| sessions.borrow_mut().push(State::default()));
+    let _end = End;
+    f()
+}
+
+/// Access the state of the innermost session, if any
+fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> Option<R> {
+    SESSIONS.with(|sessions| sessions.borrow_mut().last_mut().map(f))
+}
+
+const NO_SESSION: &str =
+    "shared `Arc`s may only be (de)serialized inside a `serde_shared::Session`";
+
+/// Serialize an [`Arc`], preserving sharing within the current [`Session`]
+pub fn serialize<T, A, S>(arc: &Arc<T, A>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize + 'static,
+    A: Allocator + 'static,
+    S: Serializer,
+{
+    let key = (
+        Arc::as_ptr(arc) as *const u8 as usize,
+        TypeId::of::<Arc<T, A>>(),
+    );
+    let (id, first) = with_state(|state| {
+        if let Some((id, _)) = state.serialized.get(&key) {
+            return (*id, false);
+        }
+        let id = state.serialized.len() as u64;
+        state.serialized.insert(key, (id, Box::new(arc.clone())));
+        (id, true)
+    })
+    .ok_or_else(|| ser::Error::custom(NO_SESSION))?;
+    let mut tuple = serializer.serialize_tuple(2)?;
+    tuple.serialize_element(&id)?;
+    tuple.serialize_element(&if first { Some(&**arc) } else { None })?;
+    tuple.end()
+}
+
+/// Deserialize an [`Arc`], preserving sharing within the current [`Session`]
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Arc<T>, D::Error>
+where
+    T: ?Sized + 'static,
+    Arc<T>: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_tuple(2, SharedVisitor(PhantomData))
+}
+
+struct SharedVisitor<T: ?Sized>(PhantomData<Arc<T>>);
+
+impl<'de, T> Visitor<'de> for SharedVisitor<T>
+where
+    T: ?Sized + 'static,
+    Arc<T>: Deserialize<'de>,
+{
+    type Value = Arc<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a shared `Arc`, as a tuple of an ID and an optional value")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Arc<T>, S::Error> {
+        let id: u64 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let value: Option<Arc<T>> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        with_state(|state| match value {
+            Some(arc) => {
+                if state.deserialized.contains_key(&id) {
+                    return Err(de::Error::custom(format_args!(
+                        "shared `Arc` ID {} defined twice",
+                        id
+                    )));
+                }
+                state.deserialized.insert(id, Box::new(arc.clone()));
+                Ok(arc)
+            }
+            None => match state.deserialized.get(&id) {
+                Some(arc) => arc.downcast_ref::<Arc<T>>().cloned().ok_or_else(|| {
+                    de::Error::custom(format_args!(
+                        "shared `Arc` ID {} refers to a value of a different type",
+                        id
+                    ))
+                }),
+                None => Err(de::Error::custom(format_args!(
+                    "shared `Arc` ID {} used before it was defined",
+                    id
+                ))),
+            },
+        })
+        .unwrap_or_else(|| Err(de::Error::custom(NO_SESSION)))
+    }
+}
+
+/// An [`Arc`] which is (de)serialized preserving sharing, for use inside other containers such as
+/// [`Vec`]
+///
+/// See the [module documentation][`self`].
+pub struct Shared<T: ?Sized, A: Allocator = Global>(pub Arc<T, A>);
+
+impl<T: ?Sized, A: Allocator> Shared<T, A> {
+    /// Get the underlying [`Arc`]
+    #[inline]
+    pub fn into_arc(this: Self) -> Arc<T, A> {
+        this.0
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Clone for Shared<T, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Deref for Shared<T, A> {
+    type Target = Arc<T, A>;
+
+    #[inline]
+    fn deref(&self) -> &Arc<T, A> {
+        &self.0
+    }
+}
+
+impl<T: ?Sized, A: Allocator> From<Arc<T, A>> for Shared<T, A> {
+    #[inline]
+    fn from(arc: Arc<T, A>) -> Self {
+        Shared(arc)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, A: Allocator> fmt::Debug for Shared<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<T: ?Sized + Serialize + 'static, A: Allocator + 'static> Serialize for Shared<T, A> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T: ?Sized + 'static> Deserialize<'de> for Shared<T>
+where
+    Arc<T>: Deserialize<'de>,
+{
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Session, Shared};
+    use crate::Arc;
+    use serde::{Deserialize, Serialize};
+    use std::vec;
+    use std::vec::Vec;
+
+    #[derive(Serialize, Deserialize)]
+    struct Node {
+        value: u32,
+        children: Vec<Shared<Node>>,
+    }
+
+    /// A root with two children, which share a single grandchild
+    fn diamond() -> Session<Shared<Node>> {
+        let node = |value, children| Shared(Arc::new(Node { value, children }));
+        let leaf = node(3, vec![]);
+        Session(node(
+            0,
+            vec![node(1, vec![leaf.clone()]), node(2, vec![leaf])],
+        ))
+    }
+
+    fn check_diamond(Session(root): Session<Shared<Node>>) {
+        let [left, right] = &root.children[..] else {
+            panic!("expected two children")
+        };
+        assert_eq!((root.value, left.value, right.value), (0, 1, 2));
+        assert!(Arc::ptr_eq(&left.children[0], &right.children[0]));
+        assert_eq!(left.children[0].value, 3);
+        assert_eq!(Arc::count(&left.children[0]), 2);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = serde_json::to_string(&diamond()).unwrap();
+        // The grandchild is written out once, and referred to by its ID the second time
+        assert_eq!(json.matches(r#"{"value":3,"#).count(), 1);
+        assert_eq!(json.matches("[2,null]").count(), 1);
+        check_diamond(serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        // `bincode::serialize` serializes the value twice: once to compute its size, and then
+        // into the output
+        let bytes = bincode::serialize(&diamond()).unwrap();
+        assert_eq!(
+            bincode::serialized_size(&diamond()).unwrap(),
+            bytes.len() as u64
+        );
+        check_diamond(bincode::deserialize(&bytes).unwrap());
+    }
+
+    #[test]
+    fn nested_sessions() {
+        let x = Shared(Arc::new(5u32));
+        let value = Session((x.clone(), Session(vec![x.clone(), x.clone()]), x));
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "[[0,5],[[0,5],[0,null]],[0,null]]");
+        type Nested = Session<(Shared<u32>, Session<Vec<Shared<u32>>>, Shared<u32>)>;
+        let Session((a, Session(inner), b)): Nested = serde_json::from_str(&json).unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(Arc::ptr_eq(&inner[0], &inner[1]));
+        assert!(!Arc::ptr_eq(&a, &inner[0]));
+    }
+
+    #[test]
+    fn errors() {
+        let undefined = serde_json::from_str::<Session<Vec<Shared<u32>>>>("[[0,null],[0,5]]");
+        assert!(undefined
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("ID 0 used before it was defined"));
+        let twice = serde_json::from_str::<Session<Vec<Shared<u32>>>>("[[0,5],[0,5]]");
+        assert!(twice.err().unwrap().to_string().contains("defined twice"));
+        assert!(serde_json::to_string(&Shared(Arc::new(5))).is_err());
+        assert!(serde_json::from_str::<Shared<u32>>("[0,5]").is_err());
+    }
+}
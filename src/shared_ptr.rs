@@ -0,0 +1,1984 @@
+//! Trait implementations shared between [`Arc`][`crate::Arc`], [`Rc`][`crate::Rc`] and
+//! [`Arc32`][`crate::Arc32`]
+//!
+//! These pointers have the same representation, an [`ArcInner`][`crate::ArcInner`] differing only
+//! in the type of its reference count, so everything which does not touch the count is
+//! implemented once here, and only cloning, dropping and querying the count is implemented
+//! separately for each. The same goes for the borrowed, maybe-owned and uniquely owned pointers
+//! built on top of them, which are generated by [`impl_borrow`], [`impl_ref`] and [`impl_unique`].
+
+#[cfg(feature = "serde")]
+use core::{fmt, marker::PhantomData, str};
+
+/// Implement the traits which do not depend on the reference count for a shared pointer type
+///
+/// `$ptr` is the pointer type, whose `p` field points to the data of an `$inner`. If `$unique`, the
+/// corresponding uniquely owned pointer type, is given, this also implements the conversions which
+/// build a `$unique` and convert it into a `$ptr` with `shareable`.
+macro_rules! impl_shared_ptr {
+    ($ptr:ident, $inner:ident) => {
+        // The implementations are wrapped in an anonymous constant so that their imports do not
+        // clash with those of the module invoking the macro
+        const _: () = {
+            use ::core::borrow;
+            use ::core::cmp::Ordering;
+            use ::core::fmt;
+            use ::core::hash::{Hash, Hasher};
+            use ::core::ops::Deref;
+            use ::core::ptr;
+            use ::erasable::{Erasable, ErasablePtr};
+            use $crate::Allocator;
+
+            #[cfg(feature = "serde")]
+            use ::serde::{Deserialize, Serialize};
+            #[cfg(feature = "stable_deref_trait")]
+            use ::stable_deref_trait::{CloneStableDeref, StableDeref};
+            #[cfg(feature = "unsize")]
+            use core::mem::ManuallyDrop;
+
+            impl<T: ?Sized, A: Allocator> Deref for $ptr<T, A> {
+                type Target = T;
+
+                #[inline]
+                fn deref(&self) -> &T {
+                    unsafe { self.p.as_ref() }
+                }
+            }
+
+            impl<T: ?Sized, U: ?Sized + PartialEq<T>, A: Allocator, B: Allocator>
+                PartialEq<$ptr<T, B>> for $ptr<U, A>
+            {
+                #[inline]
+                fn eq(&self, other: &$ptr<T, B>) -> bool {
+                    *(*self) == *(*other)
+                }
+
+                #[allow(clippy::partialeq_ne_impl)]
+                #[inline]
+                fn ne(&self, other: &$ptr<T, B>) -> bool {
+                    *(*self) != *(*other)
+                }
+            }
+
+            impl<T: ?Sized, U: ?Sized + PartialOrd<T>, A: Allocator, B: Allocator>
+                PartialOrd<$ptr<T, B>> for $ptr<U, A>
+            {
+                #[inline]
+                fn partial_cmp(&self, other: &$ptr<T, B>) -> Option<Ordering> {
+                    (**self).partial_cmp(&**other)
+                }
+
+                #[inline]
+                fn lt(&self, other: &$ptr<T, B>) -> bool {
+                    *(*self) < *(*other)
+                }
+
+                #[inline]
+                fn le(&self, other: &$ptr<T, B>) -> bool {
+                    *(*self) <= *(*other)
+                }
+
+                #[inline]
+                fn gt(&self, other: &$ptr<T, B>) -> bool {
+                    *(*self) > *(*other)
+                }
+
+                #[inline]
+                fn ge(&self, other: &$ptr<T, B>) -> bool {
+                    *(*self) >= *(*other)
+                }
+            }
+
+            impl<T: ?Sized + Ord, A: Allocator> Ord for $ptr<T, A> {
+                fn cmp(&self, other: &$ptr<T, A>) -> Ordering {
+                    (**self).cmp(&**other)
+                }
+            }
+
+            impl<T: ?Sized + Eq, A: Allocator> Eq for $ptr<T, A> {}
+
+            impl<T: ?Sized + fmt::Display, A: Allocator> fmt::Display for $ptr<T, A> {
+                #[inline]
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Display::fmt(&**self, f)
+                }
+            }
+
+            impl<T: ?Sized + fmt::Debug, A: Allocator> fmt::Debug for $ptr<T, A> {
+                #[inline]
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Debug::fmt(&**self, f)
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> fmt::Pointer for $ptr<T, A> {
+                #[inline]
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Pointer::fmt(&self.p, f)
+                }
+            }
+
+            impl<T: Default> Default for $ptr<T> {
+                #[inline]
+                fn default() -> $ptr<T> {
+                    $ptr::new(Default::default())
+                }
+            }
+
+            impl<T: ?Sized + Hash, A: Allocator> Hash for $ptr<T, A> {
+                #[inline]
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    (**self).hash(state)
+                }
+            }
+
+            impl<T> From<T> for $ptr<T> {
+                #[inline]
+                fn from(t: T) -> Self {
+                    $ptr::new(t)
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> borrow::Borrow<T> for $ptr<T, A> {
+                #[inline]
+                fn borrow(&self) -> &T {
+                    self
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> AsRef<T> for $ptr<T, A> {
+                #[inline]
+                fn as_ref(&self) -> &T {
+                    self
+                }
+            }
+
+            unsafe impl<T: ?Sized + Erasable, A: Allocator> ErasablePtr for $ptr<T, A> {
+                #[inline]
+                fn erase(this: Self) -> erasable::ErasedPtr {
+                    T::erase(unsafe { ptr::NonNull::new_unchecked($ptr::into_raw(this) as *mut _) })
+                }
+
+                #[inline]
+                unsafe fn unerase(this: erasable::ErasedPtr) -> Self {
+                    $ptr::from_raw_in(T::unerase(this).as_ptr())
+                }
+            }
+
+            #[cfg(feature = "stable_deref_trait")]
+            unsafe impl<T: ?Sized, A: Allocator> StableDeref for $ptr<T, A> {}
+            #[cfg(feature = "stable_deref_trait")]
+            unsafe impl<T: ?Sized, A: Allocator> CloneStableDeref for $ptr<T, A> {}
+
+            #[cfg(feature = "serde")]
+            impl<'de, T: Deserialize<'de>> Deserialize<'de> for $ptr<T> {
+                #[inline]
+                fn deserialize<D>(deserializer: D) -> Result<$ptr<T>, D::Error>
+                where
+                    D: ::serde::de::Deserializer<'de>,
+                {
+                    T::deserialize(deserializer).map($ptr::new)
+                }
+            }
+
+            #[cfg(feature = "serde")]
+            impl<T: ?Sized + Serialize, A: Allocator> Serialize for $ptr<T, A> {
+                #[inline]
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: ::serde::ser::Serializer,
+                {
+                    (**self).serialize(serializer)
+                }
+            }
+
+            #[cfg(feature = "unsize")]
+            /// # Safety
+            ///
+            /// This implementation must guarantee that it is sound to call replace_ptr with an
+            /// unsized variant of the pointer retuned in `as_sized_ptr`. The basic property of
+            /// Unsize coercion is that safety variants and layout is unaffected. The pointer does
+            /// not rely on any other property of T. This makes any unsized inner valid for being
+            /// shared with the sized variant.
+            /// This does _not_ mean that any T can be unsized into an U, but rather than if such
+            /// unsizing is possible then it can be propagated into the pointer.
+            unsafe impl<T, U: ?Sized, A: Allocator> unsize::CoerciblePtr<U> for $ptr<T, A> {
+                type Pointee = T;
+                type Output = $ptr<U, A>;
+
+                fn as_sized_ptr(&mut self) -> *mut T {
+                    // Returns a pointer to the complete inner. The unsizing itself won't care about
+                    // the pointer value and promises not to offset it.
+                    self.p.as_ptr()
+                }
+
+                unsafe fn replace_ptr(self, new: *mut U) -> $ptr<U, A> {
+                    // Fix the provenance by ensuring that of `self` is used.
+                    let old_layout = $inner::<T, A>::layout(&*self);
+                    let inner = ManuallyDrop::new(self);
+                    let p = inner.p.as_ptr();
+                    // Safety: The caller upholds that `new` is an unsized version of the data in
+                    //         the previous inner.
+                    let result = $ptr::from_raw_in(p.replace_ptr(new));
+                    debug_assert_eq!(old_layout, $inner::<U, A>::layout(&*result));
+                    result
+                }
+            }
+        };
+    };
+    ($ptr:ident, $inner:ident, $unique:ident) => {
+        impl_shared_ptr!($ptr, $inner);
+
+        const _: () = {
+            use ::alloc::borrow::Cow;
+            use ::alloc::boxed::Box;
+            use ::alloc::string::String;
+            use ::alloc::vec::Vec;
+            use $crate::FromUtf8Error;
+
+            #[cfg(feature = "serde")]
+            use ::serde::Deserialize;
+            #[cfg(feature = "slice-dst")]
+            use ::slice_dst::{AllocSliceDst, SliceDst, TryAllocSliceDst};
+            #[cfg(feature = "slice-dst")]
+            use core::{alloc::Layout, ptr};
+
+            impl<T: ?Sized> From<Box<T>> for $ptr<T> {
+                /// Move a boxed value into a new allocation, freeing the original [`Box`]
+                ///
+                /// This works for unsized values such as slices and trait objects.
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($ptr), " as Ptr;")]
+                /// use std::fmt::Display;
+                ///
+                /// let x: Box<dyn Display> = Box::new(5);
+                /// let y: Ptr<dyn Display> = Ptr::from(x);
+                /// assert_eq!(y.to_string(), "5");
+                /// ```
+                #[inline]
+                fn from(b: Box<T>) -> Self {
+                    $unique::from(b).shareable()
+                }
+            }
+
+            impl<T> From<Vec<T>> for $ptr<[T]> {
+                /// Move the elements of a [`Vec`] into a new allocation
+                #[inline]
+                fn from(v: Vec<T>) -> Self {
+                    $unique::from(v).shareable()
+                }
+            }
+
+            impl<T: Clone> From<&[T]> for $ptr<[T]> {
+                #[inline]
+                fn from(s: &[T]) -> Self {
+                    $unique::from(s).shareable()
+                }
+            }
+
+            impl<T> FromIterator<T> for $ptr<[T]> {
+                /// Collect an iterator into a new allocation
+                ///
+                /// If the iterator reports an exact size via [`Iterator::size_hint`] of at most
+                /// 1 MiB, and yields that many elements, they are written directly into a single
+                /// allocation; otherwise, they are first collected into a [`Vec`].
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($ptr), " as Ptr;")]
+                ///
+                /// let x: Ptr<[u32]> = (0..5).collect();
+                /// assert_eq!(*x, [0, 1, 2, 3, 4]);
+                /// let y: Ptr<[u32]> = (0..10).filter(|x| x % 2 == 0).map(|x| x / 2).collect();
+                /// assert_eq!(x, y);
+                /// ```
+                #[inline]
+                fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+                    $unique::from_iter(iter).shareable()
+                }
+            }
+
+            impl From<&str> for $ptr<str> {
+                /// Copy a string slice into a new allocation
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($ptr), " as Ptr;")]
+                ///
+                /// let x: Ptr<str> = Ptr::from("hello");
+                /// assert_eq!(&*x, "hello");
+                /// ```
+                #[inline]
+                fn from(s: &str) -> Self {
+                    $unique::from(s).shareable()
+                }
+            }
+
+            impl From<String> for $ptr<str> {
+                #[inline]
+                fn from(s: String) -> Self {
+                    $unique::from(s).shareable()
+                }
+            }
+
+            impl From<Cow<'_, str>> for $ptr<str> {
+                #[inline]
+                fn from(s: Cow<'_, str>) -> Self {
+                    $unique::from(s).shareable()
+                }
+            }
+
+            impl From<$ptr<str>> for $ptr<[u8]> {
+                #[inline]
+                fn from(s: $ptr<str>) -> Self {
+                    $ptr::into_bytes(s)
+                }
+            }
+
+            impl TryFrom<$ptr<[u8]>> for $ptr<str> {
+                type Error = FromUtf8Error<$ptr<[u8]>>;
+
+                #[inline]
+                fn try_from(bytes: $ptr<[u8]>) -> Result<Self, FromUtf8Error<$ptr<[u8]>>> {
+                    $ptr::from_utf8(bytes)
+                }
+            }
+
+            #[cfg(feature = "serde")]
+            impl<'de> Deserialize<'de> for $ptr<str> {
+                #[inline]
+                fn deserialize<D>(deserializer: D) -> Result<$ptr<str>, D::Error>
+                where
+                    D: ::serde::de::Deserializer<'de>,
+                {
+                    deserializer.deserialize_str(crate::shared_ptr::StrVisitor::new())
+                }
+            }
+
+            #[cfg(feature = "slice-dst")]
+            /// # Safety
+            ///
+            /// This is implemented as an additional header before `S`, consisting of the
+            /// (zero-sized) global allocator and the reference count
+            unsafe impl<S: SliceDst + ?Sized> SliceDst for $inner<S> {
+                fn layout_for(len: usize) -> Layout {
+                    $inner::<S>::layout_with(S::layout_for(len)).unwrap().0
+                }
+
+                #[allow(clippy::let_and_return)]
+                fn retype(ptr: ptr::NonNull<[()]>) -> ptr::NonNull<Self> {
+                    let retype_inner = S::retype(ptr);
+                    // Safety: the metadata for `S` is the same as for the inner, since the inner
+                    // has `S` as it's last member. This is based on the implementation for
+                    // `triomphe`.
+                    let retyped =
+                        unsafe { ptr::NonNull::new_unchecked(retype_inner.as_ptr() as *mut _) };
+                    //TODO: add correctness assertions
+                    retyped
+                }
+            }
+
+            #[cfg(feature = "slice-dst")]
+            /// # Safety
+            ///
+            /// This function merely delegates to the [`TryAllocSliceDst`] implementation
+            unsafe impl<S: ?Sized + SliceDst> AllocSliceDst<S> for $ptr<S> {
+                unsafe fn new_slice_dst<I>(len: usize, init: I) -> Self
+                where
+                    I: FnOnce(ptr::NonNull<S>),
+                {
+                    #[allow(clippy::unit_arg)]
+                    let init = |ptr| Ok::<(), core::convert::Infallible>(init(ptr));
+                    #[allow(unreachable_patterns)]
+                    match Self::try_new_slice_dst(len, init) {
+                        Ok(a) => a,
+                        Err(void) => match void {},
+                    }
+                }
+            }
+
+            #[cfg(feature = "slice-dst")]
+            /// # Safety
+            ///
+            #[doc = concat!(
+                "This function merely delegates to the [`", stringify!($unique), "`] implementation"
+            )]
+            unsafe impl<S: ?Sized + SliceDst> TryAllocSliceDst<S> for $ptr<S> {
+                unsafe fn try_new_slice_dst<I, E>(len: usize, init: I) -> Result<Self, E>
+                where
+                    I: FnOnce(ptr::NonNull<S>) -> Result<(), E>,
+                {
+                    Ok($unique::try_new_slice_dst(len, init)?.shareable())
+                }
+            }
+        };
+    };
+}
+
+pub(crate) use impl_shared_ptr;
+
+/// A visitor which copies a string directly into a new shared pointer `P`, such as an
+/// [`Arc<str>`][`crate::Arc`]
+#[cfg(feature = "serde")]
+pub(crate) struct StrVisitor<P>(PhantomData<P>);
+
+#[cfg(feature = "serde")]
+impl<P> StrVisitor<P> {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        StrVisitor(PhantomData)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, P: for<'a> From<&'a str>> ::serde::de::Visitor<'de> for StrVisitor<P> {
+    type Value = P;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    #[inline]
+    fn visit_str<E: ::serde::de::Error>(self, v: &str) -> Result<P, E> {
+        Ok(P::from(v))
+    }
+
+    #[inline]
+    fn visit_bytes<E: ::serde::de::Error>(self, v: &[u8]) -> Result<P, E> {
+        match str::from_utf8(v) {
+            Ok(s) => Ok(P::from(s)),
+            Err(_) => Err(E::invalid_value(::serde::de::Unexpected::Bytes(v), &self)),
+        }
+    }
+}
+
+/// Implement a borrowed pointer type for a shared pointer type
+///
+/// `$borrow` is the borrowed pointer type, whose `p` field points to the data of an `$inner` owned
+/// by a `$ptr`, and `$ref` is the corresponding maybe-owned pointer type. `$clone_shared`,
+/// `$as_shared` and `$as_shared_ref` name the methods which clone a `$borrow` as a `$ptr` and
+/// borrow it as a `$ptr` and as a `$ref`, e.g. `clone_arc`, `as_arc` and `as_arc_ref`.
+///
+/// Querying the reference count is implemented separately for each type.
+macro_rules! impl_borrow {
+    (
+        $borrow:ident, $ptr:ident, $inner:ident, $ref:ident;
+        $clone_shared:ident, $as_shared:ident, $as_shared_ref:ident
+    ) => {
+        const _: () = {
+            use ::core::borrow::Borrow;
+            use ::core::cmp::Ordering;
+            use ::core::ffi::c_void;
+            use ::core::fmt;
+            use ::core::hash::{Hash, Hasher};
+            use ::core::marker::PhantomData;
+            use ::core::mem::ManuallyDrop;
+            use ::core::ops::Deref;
+            use ::core::ptr::{self, NonNull};
+            use ::erasable::{Erasable, ErasablePtr};
+            use $crate::Allocator;
+
+            impl<'a, T: ?Sized, A: Allocator> Copy for $borrow<'a, T, A> {}
+            impl<'a, T: ?Sized, A: Allocator> Clone for $borrow<'a, T, A> {
+                #[inline]
+                fn clone(&self) -> Self {
+                    *self
+                }
+            }
+
+            impl<'a, T: ?Sized> $borrow<'a, T> {
+                /// Construct a borrowed pointer from an internal pointer
+                ///
+                /// # Safety
+                #[doc = concat!(
+                    "This pointer must be the result of `", stringify!($borrow), "::from_raw` or `",
+                    stringify!($ptr),
+                    "::from_raw`. In the latter case, the reference count is not incremented."
+                )]
+                #[inline]
+                pub unsafe fn from_raw(raw: *const T) -> Self {
+                    $borrow::from_raw_in(raw)
+                }
+            }
+
+            impl<'a, T: ?Sized, A: Allocator> $borrow<'a, T, A> {
+                #[doc = concat!(
+                    "Clone this as an [`", stringify!($ptr), "<T>`]. This bumps the refcount."
+                )]
+                #[inline]
+                pub fn $clone_shared(this: Self) -> $ptr<T, A> {
+                    // Wrap the pointer in a `ManuallyDrop` so that the refcount is left untouched
+                    // if cloning panics on overflow
+                    let arc = ManuallyDrop::new(unsafe { $ptr::from_raw_in(this.p.as_ptr()) });
+                    // addref it!
+                    (*arc).clone()
+                }
+
+                /// Compare two borrowed pointers via pointer equality. Will only return
+                /// true if they come from the same allocation
+                #[inline]
+                pub fn ptr_eq(this: Self, other: Self) -> bool {
+                    core::ptr::eq(this.p.as_ptr(), other.p.as_ptr())
+                }
+
+                /// Similar to deref, but uses the lifetime `'a` rather than the lifetime of
+                /// `self`, which is incompatible with the signature of the [`Deref`] trait.
+                #[inline]
+                pub fn get(&self) -> &'a T {
+                    unsafe { &*(self.p.as_ptr() as *const T) }
+                }
+
+                #[doc = concat!(
+                    "Borrow this as an [`", stringify!($ptr),
+                    "`]. This does *not* bump the refcount."
+                )]
+                #[inline]
+                pub fn $as_shared(this: &Self) -> &$ptr<T, A> {
+                    unsafe { &*(this as *const _ as *const $ptr<T, A>) }
+                }
+
+                #[doc = concat!(
+                    "Get a reference to the allocator the underlying [`", stringify!($ptr),
+                    "`] was allocated with"
+                )]
+                #[inline]
+                pub fn allocator(this: Self) -> &'a A {
+                    unsafe { &*$inner::<T, A>::alloc_ptr(this.p.as_ptr()) }
+                }
+
+                /// Get the internal pointer
+                #[inline]
+                pub fn into_raw(this: Self) -> *const T {
+                    let arc = Self::$as_shared(&this);
+                    $ptr::as_ptr(arc)
+                }
+
+                /// Construct a borrowed pointer from an internal pointer to an allocation made with
+                /// an allocator of type `A`
+                ///
+                /// # Safety
+                #[doc = concat!(
+                    "This pointer must be the result of `", stringify!($borrow), "::into_raw` or `",
+                    stringify!($ptr), "::into_raw` with the same"
+                )]
+                /// allocator type `A`. In the latter case, the reference count is not incremented.
+                #[inline]
+                pub unsafe fn from_raw_in(raw: *const T) -> Self {
+                    #[cfg(feature = "header-canary")]
+                    $inner::<T, A>::check_canary(raw, concat!(stringify!($borrow), "::from_raw"));
+                    $borrow {
+                        p: NonNull::new_unchecked(raw as *mut T),
+                        phantom: PhantomData,
+                    }
+                }
+
+                /// Returns the address on the heap of the allocation itself -- not the `T` within
+                /// it -- for memory reporting.
+                pub fn heap_ptr(self) -> *const c_void {
+                    unsafe { $inner::<T, A>::alloc_ptr(self.p.as_ptr()) as *const c_void }
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> $borrow<'a, T, A> {
+                #[doc = concat!(
+                    "Borrow this as an [`", stringify!($ref),
+                    "`]. This does *not* bump the refcount."
+                )]
+                #[inline]
+                pub fn $as_shared_ref(this: &'a $borrow<'a, T, A>) -> &'a $ref<'a, T, A> {
+                    unsafe { &*(this as *const _ as *const $ref<'a, T, A>) }
+                }
+            }
+
+            impl<'a, T: ?Sized, A: Allocator> Deref for $borrow<'a, T, A> {
+                type Target = T;
+
+                #[inline]
+                fn deref(&self) -> &T {
+                    self.get()
+                }
+            }
+
+            unsafe impl<T: ?Sized + Erasable, A: Allocator> ErasablePtr for $borrow<'_, T, A> {
+                #[inline]
+                fn erase(this: Self) -> erasable::ErasedPtr {
+                    T::erase(unsafe {
+                        ptr::NonNull::new_unchecked($borrow::into_raw(this) as *mut _)
+                    })
+                }
+
+                #[inline]
+                unsafe fn unerase(this: erasable::ErasedPtr) -> Self {
+                    $borrow::from_raw_in(T::unerase(this).as_ptr())
+                }
+            }
+
+            impl<'a, 'b, T: ?Sized, U: ?Sized + PartialEq<T>, A: Allocator, B: Allocator>
+                PartialEq<$borrow<'a, T, B>> for $borrow<'b, U, A>
+            {
+                #[inline]
+                fn eq(&self, other: &$borrow<'a, T, B>) -> bool {
+                    *(*self) == *(*other)
+                }
+
+                #[allow(clippy::partialeq_ne_impl)]
+                #[inline]
+                fn ne(&self, other: &$borrow<'a, T, B>) -> bool {
+                    *(*self) != *(*other)
+                }
+            }
+
+            impl<'a, 'b, T: ?Sized, U: ?Sized + PartialOrd<T>, A: Allocator, B: Allocator>
+                PartialOrd<$borrow<'a, T, B>> for $borrow<'b, U, A>
+            {
+                #[inline]
+                fn partial_cmp(&self, other: &$borrow<'a, T, B>) -> Option<Ordering> {
+                    (**self).partial_cmp(&**other)
+                }
+
+                #[inline]
+                fn lt(&self, other: &$borrow<'a, T, B>) -> bool {
+                    *(*self) < *(*other)
+                }
+
+                #[inline]
+                fn le(&self, other: &$borrow<'a, T, B>) -> bool {
+                    *(*self) <= *(*other)
+                }
+
+                #[inline]
+                fn gt(&self, other: &$borrow<'a, T, B>) -> bool {
+                    *(*self) > *(*other)
+                }
+
+                #[inline]
+                fn ge(&self, other: &$borrow<'a, T, B>) -> bool {
+                    *(*self) >= *(*other)
+                }
+            }
+
+            impl<'a, T: ?Sized + Ord, A: Allocator> Ord for $borrow<'a, T, A> {
+                #[inline]
+                fn cmp(&self, other: &$borrow<'a, T, A>) -> Ordering {
+                    (**self).cmp(&**other)
+                }
+            }
+
+            impl<'a, T: ?Sized + Eq, A: Allocator> Eq for $borrow<'a, T, A> {}
+
+            impl<'a, T: ?Sized + fmt::Display, A: Allocator> fmt::Display for $borrow<'a, T, A> {
+                #[inline]
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Display::fmt(&**self, f)
+                }
+            }
+
+            impl<'a, T: ?Sized + fmt::Debug, A: Allocator> fmt::Debug for $borrow<'a, T, A> {
+                #[inline]
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Debug::fmt(&**self, f)
+                }
+            }
+
+            impl<T: ?Sized + Hash, A: Allocator> Hash for $borrow<'_, T, A> {
+                #[inline]
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    (**self).hash(state)
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> Borrow<T> for $borrow<'_, T, A> {
+                #[inline]
+                fn borrow(&self) -> &T {
+                    self
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> AsRef<T> for $borrow<'_, T, A> {
+                #[inline]
+                fn as_ref(&self) -> &T {
+                    self
+                }
+            }
+        };
+    };
+}
+
+pub(crate) use impl_borrow;
+
+/// Implement a maybe-owned pointer type for a shared pointer type
+///
+/// `$ref` is the maybe-owned pointer type, whose `p` field points to the data of an `$inner`
+/// either owned by a `$ptr` or borrowed as a `$borrow`, and `$unique` is the corresponding uniquely
+/// owned pointer type. `$from_shared`, `$try_into_shared`, `$borrow_shared`, `$into_shared` and
+/// `$clone_shared` name the methods converting between a `$ref` and a `$ptr` or `$borrow`, e.g.
+/// `from_arc`, `try_into_arc`, `borrow_arc`, `into_arc` and `clone_arc`; the latter must also be
+/// the name of the method cloning a `$borrow` as a `$ptr`.
+///
+/// Querying the reference count is implemented separately for each type.
+macro_rules! impl_ref {
+    (
+        $ref:ident, $ptr:ident, $inner:ident, $borrow:ident, $unique:ident;
+        $from_shared:ident, $try_into_shared:ident, $borrow_shared:ident, $into_shared:ident,
+        $clone_shared:ident
+    ) => {
+        const _: () = {
+            use ::core::borrow;
+            use ::core::cmp::Ordering;
+            use ::core::fmt;
+            use ::core::hash::{Hash, Hasher};
+            use ::core::marker::PhantomData;
+            use ::core::mem;
+            use ::core::ops::Deref;
+            use ::core::ptr::NonNull;
+            use ::erasable::Erasable;
+            use $crate::{AllocError, Allocator};
+
+            #[cfg(feature = "serde")]
+            use ::serde::{Deserialize, Serialize};
+            #[cfg(feature = "stable_deref_trait")]
+            use ::stable_deref_trait::{CloneStableDeref, StableDeref};
+
+            impl<'a, T: Erasable> $ref<'a, T> {
+                /// Construct an owned pointer to a new allocation
+                #[inline]
+                pub fn new(data: T) -> Self {
+                    let new = $ref::$from_shared($ptr::new(data));
+                    new
+                }
+
+                /// Construct an owned pointer to a new allocation, returning an error if the
+                /// allocation fails
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                ///
+                /// let x = Ref::try_new(3).unwrap();
+                /// assert!(Ref::is_owned(&x));
+                /// assert_eq!(*x, 3);
+                /// ```
+                #[inline]
+                pub fn try_new(data: T) -> Result<Self, AllocError> {
+                    $ptr::try_new(data).map($ref::$from_shared)
+                }
+            }
+
+            impl<'a, T: Erasable, A: Allocator> $ref<'a, T, A> {
+                /// Construct an owned pointer to a new allocation in the given allocator
+                #[inline]
+                pub fn new_in(data: T, alloc: A) -> Self {
+                    $ref::$from_shared($ptr::new_in(data, alloc))
+                }
+
+                /// Construct an owned pointer to a new allocation in the given allocator, returning
+                /// an error if the allocation fails
+                #[inline]
+                pub fn try_new_in(data: T, alloc: A) -> Result<Self, AllocError> {
+                    $ptr::try_new_in(data, alloc).map($ref::$from_shared)
+                }
+
+                /// Returns the inner value, if the pointer is owned and has exactly one strong
+                /// reference.
+                ///
+                /// Otherwise, an [`Err`] is returned with the same pointer that was
+                /// passed in.
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                ///
+                /// let x = Ref::new(3);
+                /// assert_eq!(Ref::try_unwrap(x), Ok(3));
+                ///
+                /// let x = Ref::new(4);
+                /// let _y = Ref::clone(&x);
+                /// assert_eq!(*Ref::try_unwrap(x).unwrap_err(), 4);
+                /// ```
+                #[inline]
+                pub fn try_unwrap(this: Self) -> Result<T, Self> {
+                    match $ref::$try_into_shared(this) {
+                        Ok(arc) => $ptr::try_unwrap(arc).map_err($ref::$from_shared),
+                        Err(borrow) => Err($ref::from_borrow(borrow)),
+                    }
+                }
+
+                /// Makes a mutable reference into the given pointer, cloning if necessary.
+                ///
+                #[doc = concat!("This is similar to [`", stringify!($ptr), "::make_mut`].")]
+                ///
+                /// If this pointer is uniquely owned, `make_mut()` will provide a mutable
+                /// reference to the contents. If not, `make_mut()` will create a _new_ pointer
+                /// with a copy of the contents, update `this` to point to it, and provide
+                /// a mutable reference to its contents.
+                ///
+                /// This is useful for implementing copy-on-write schemes where you wish to
+                /// avoid copying things if your pointer is not shared.
+                #[inline]
+                pub fn make_mut(this: &mut Self) -> &mut T
+                where
+                    T: Clone,
+                    A: Clone,
+                {
+                    if !$ref::is_unique(this) {
+                        // Another pointer exists *or* this value is borrowed; clone
+                        let alloc = $borrow::allocator($ref::$borrow_shared(this)).clone();
+                        *this = $ref::new_in((**this).clone(), alloc);
+                    }
+
+                    unsafe {
+                        // This unsafety is ok because we're guaranteed that the pointer
+                        // returned is the *only* pointer that will ever be returned to T. Our
+                        // reference count is guaranteed to be 1 at this point, and we required
+                        // the pointer itself to be `mut`, so we're returning the only possible
+                        // reference to the inner data.
+                        &mut *this.ptr()
+                    }
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> $ref<'a, T, A> {
+                /// Provides mutable access to the contents _if_ the pointer is uniquely owned.
+                #[inline]
+                pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+                    if Self::is_unique(this) {
+                        unsafe {
+                            // See make_mut() for documentation of the safety here.
+                            Some(&mut *this.ptr())
+                        }
+                    } else {
+                        None
+                    }
+                }
+
+                /// Whether or not the pointer is uniquely owned (is the refcount 1, and is the
+                /// pointer itself owned?).
+                #[inline]
+                pub fn is_unique(this: &Self) -> bool {
+                    $ref::is_owned(this) && Self::count(this) == 1
+                }
+
+                #[doc = concat!(
+                    "Returns an [`", stringify!($unique),
+                    "`] if the pointer has exactly one strong, owned reference."
+                )]
+                ///
+                /// Otherwise, an [`Err`] is returned with the same pointer that was
+                /// passed in.
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!(
+                    "use elysees::{", stringify!($ref), " as Ref, ", stringify!($unique),
+                    " as Unique};"
+                )]
+                ///
+                /// let x = Ref::new(3);
+                /// assert_eq!(Unique::into_inner(Ref::try_unique(x).unwrap()), 3);
+                ///
+                /// let x = Ref::new(4);
+                /// let _y = Ref::clone(&x);
+                /// assert_eq!(
+                ///     *Ref::try_unique(x).map(Unique::into_inner).unwrap_err(),
+                ///     4,
+                /// );
+                /// ```
+                #[inline]
+                pub fn try_unique(this: Self) -> Result<$unique<T, A>, Self> {
+                    if $ref::is_unique(&this) {
+                        // Safety: The current pointer is unique and making a unique box from it
+                        //         is sound
+                        unsafe {
+                            Ok($unique::$from_shared($ptr::from_raw_in($ref::into_raw(this))))
+                        }
+                    } else {
+                        Err(this)
+                    }
+                }
+
+                #[doc = concat!(
+                    "Construct an [`", stringify!($ref), "<'a, T>`] from an [`", stringify!($ptr),
+                    "<T>`]"
+                )]
+                ///
+                /// # Examples
+                ///
+                /// ```rust
+                #[doc = concat!(
+                    "use elysees::{", stringify!($ptr), " as Ptr, ", stringify!($ref), " as Ref};"
+                )]
+                ///
+                /// let x = Ptr::new(3);
+                #[doc = concat!("let y = Ref::", stringify!($from_shared), "(x.clone());")]
+                /// assert_eq!(Ref::count(&y), 2);
+                /// ```
+                #[inline]
+                pub fn $from_shared(arc: $ptr<T, A>) -> Self {
+                    unsafe { Self::from_raw($ptr::into_raw(arc), true) }
+                }
+
+                #[doc = concat!(
+                    "Construct an `", stringify!($ref), "<'a, T>` from an `", stringify!($borrow),
+                    "<'a, T>`"
+                )]
+                #[inline]
+                pub fn from_borrow(arc: $borrow<'a, T, A>) -> Self {
+                    unsafe { Self::from_raw(arc.p.as_ptr(), false) }
+                }
+
+                #[doc = concat!(
+                    "Try to convert this `", stringify!($ref), "<'a, T>` into an `",
+                    stringify!($ptr), "<T>` if owned; otherwise, return it as an `",
+                    stringify!($borrow), "`"
+                )]
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                ///
+                /// let x = Ref::new(3);
+                #[doc = concat!(
+                    "assert_eq!(*Ref::", stringify!($try_into_shared), "(x.clone()).unwrap(), 3);"
+                )]
+                /// ```
+                #[inline]
+                pub fn $try_into_shared(this: Self) -> Result<$ptr<T, A>, $borrow<'a, T, A>> {
+                    match this.into_raw_inner() {
+                        (p, true) => Ok(unsafe { $ptr::from_raw_in(p.as_ptr()) }),
+                        (p, false) => Err($borrow {
+                            p,
+                            phantom: PhantomData,
+                        }),
+                    }
+                }
+
+                /// Transform this into its data pointer and whether it is owned.
+                #[inline]
+                pub(crate) fn into_raw_inner(self) -> (NonNull<T>, bool) {
+                    let p = self.nn_ptr();
+                    let o = $ref::is_owned(&self);
+                    core::mem::forget(self);
+                    (p, o)
+                }
+
+                /// Construct a pointer from a data pointer and whether it is owned.
+                /// # Safety
+                #[doc = concat!(
+                    "The `ptr` must point to a valid instance, allocated by an [`",
+                    stringify!($ptr), "`]. The reference count will"
+                )]
+                /// not be modified.
+                #[inline]
+                pub(crate) unsafe fn from_raw(p: *const T, o: bool) -> Self {
+                    //TODO: replace with ptr_union...
+                    let result = $ref {
+                        p: Erasable::erase(NonNull::new_unchecked(
+                            (Erasable::erase(NonNull::new_unchecked(p as *mut T))
+                                .as_ptr()
+                                .wrapping_byte_add(if o { 0b10 } else { 0b00 })) as *mut u8,
+                        )),
+                        phantom: PhantomData,
+                    };
+                    debug_assert_eq!($ref::is_owned(&result), o);
+                    result
+                }
+
+                /// Test pointer equality between the two pointers, i.e. they must be the _same_
+                /// allocation
+                #[inline]
+                pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+                    core::ptr::eq(this.ptr(), other.ptr())
+                }
+
+                #[inline]
+                pub(crate) fn nn_ptr(&self) -> NonNull<T> {
+                    let buf_ptr = self
+                        .p
+                        .as_ptr()
+                        .wrapping_byte_sub(self.p.as_ptr() as usize & 0b11);
+                    let erased = unsafe { Erasable::erase(NonNull::new_unchecked(buf_ptr)) };
+                    unsafe { Erasable::unerase(erased) }
+                }
+
+                #[inline]
+                pub(crate) fn ptr(&self) -> *mut T {
+                    self.nn_ptr().as_ptr()
+                }
+
+                #[doc = concat!(
+                    "Leak this pointer, getting an [`", stringify!($borrow), "<'static, T>`]"
+                )]
+                ///
+                #[doc = concat!(
+                    "You can call the [`get`][`", stringify!($borrow),
+                    "::get`] method on the returned [`", stringify!($borrow),
+                    "`] to get an `&'static T`."
+                )]
+                /// Note that using this can (obviously) cause memory leaks!
+                #[inline]
+                pub fn leak(this: Self) -> $borrow<'static, T, A> {
+                    let result = $borrow {
+                        p: this.nn_ptr(),
+                        phantom: PhantomData,
+                    };
+                    mem::forget($ref::into_owned(this));
+                    result
+                }
+
+                /// Get whether this pointer is owned
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                ///
+                /// let x = Ref::new(3);
+                /// assert!(Ref::is_owned(&x));
+                /// let y = x.clone();
+                /// assert!(Ref::is_owned(&y));
+                /// let z = Ref::into_borrow(&x);
+                /// assert!(!Ref::is_owned(&z));
+                /// ```
+                #[inline]
+                pub fn is_owned(this: &Self) -> bool {
+                    this.p.as_ptr() as usize & 0b10 != 0
+                }
+
+                #[doc = concat!(
+                    "Borrow this as an [`", stringify!($borrow),
+                    "`]. This does *not* bump the refcount."
+                )]
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!(
+                    "use elysees::{", stringify!($ref), " as Ref, ", stringify!($borrow),
+                    " as Borrow};"
+                )]
+                ///
+                /// let x: Ref<u64> = Ref::new(3);
+                /// assert_eq!(Ref::count(&x), 1);
+                #[doc = concat!("let y: Borrow<u64> = Ref::", stringify!($borrow_shared), "(&x);")]
+                /// assert_eq!(Ref::as_ptr(&x), Borrow::into_raw(y));
+                /// assert_eq!(Ref::count(&x), 1);
+                /// assert_eq!(Borrow::count(y), 1);
+                /// ```
+                #[inline]
+                pub fn $borrow_shared(this: &'a Self) -> $borrow<'a, T, A> {
+                    $borrow {
+                        p: this.nn_ptr(),
+                        phantom: PhantomData,
+                    }
+                }
+
+                #[doc = concat!(
+                    "Get this as an [`", stringify!($ptr), "`], bumping the refcount if necessary."
+                )]
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!(
+                    "use elysees::{", stringify!($ptr), " as Ptr, ", stringify!($ref), " as Ref};"
+                )]
+                ///
+                /// let x = Ref::new(3);
+                /// let y = Ref::into_borrow(&x);
+                /// assert_eq!(Ref::as_ptr(&x), Ref::as_ptr(&y));
+                /// assert_eq!(Ref::count(&x), 1);
+                /// assert_eq!(Ref::count(&y), 1);
+                #[doc = concat!("let z = Ref::", stringify!($into_shared), "(y);")]
+                /// assert_eq!(Ref::as_ptr(&x), Ptr::as_ptr(&z));
+                /// assert_eq!(Ref::count(&x), 2);
+                /// assert_eq!(Ptr::count(&z), 2);
+                #[doc = concat!("let w = Ref::", stringify!($into_shared), "(x);")]
+                /// assert_eq!(Ptr::count(&w), 2);
+                /// assert_eq!(Ptr::count(&z), 2);
+                /// ```
+                #[inline]
+                pub fn $into_shared(this: Self) -> $ptr<T, A> {
+                    match $ref::$try_into_shared(this) {
+                        Ok(arc) => arc,
+                        Err(borrow) => $borrow::$clone_shared(borrow),
+                    }
+                }
+
+                #[doc = concat!("Clone this as an [`", stringify!($ptr), "`].")]
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!(
+                    "use elysees::{", stringify!($ptr), " as Ptr, ", stringify!($ref), " as Ref};"
+                )]
+                ///
+                /// let x: Ref<u64> = Ref::new(3);
+                /// assert_eq!(Ref::count(&x), 1);
+                #[doc = concat!("let y: Ptr<u64> = Ref::", stringify!($clone_shared), "(&x);")]
+                /// assert_eq!(Ref::as_ptr(&x), Ptr::as_ptr(&y));
+                /// assert_eq!(Ref::count(&x), 2);
+                /// assert_eq!(Ptr::count(&y), 2);
+                /// ```
+                #[inline]
+                pub fn $clone_shared(this: &'a Self) -> $ptr<T, A> {
+                    $borrow::$clone_shared($ref::$borrow_shared(this))
+                }
+
+                /// Get this as an owned pointer, with the `'static` lifetime
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                ///
+                /// let x = Ref::new(7);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// let y = Ref::into_borrow(&x);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// assert_eq!(Ref::count(&y), 1);
+                /// let z = Ref::into_owned(y);
+                /// assert_eq!(Ref::as_ptr(&x), Ref::as_ptr(&z));
+                /// assert_eq!(Ref::count(&x), 2);
+                /// assert_eq!(Ref::count(&z), 2);
+                /// ```
+                #[inline]
+                pub fn into_owned(this: Self) -> $ref<'static, T, A> {
+                    match Self::$try_into_shared(this) {
+                        Ok(arc) => $ref::$from_shared(arc),
+                        Err(borrow) => $ref::$from_shared($borrow::$clone_shared(borrow)),
+                    }
+                }
+
+                #[doc = concat!(
+                    "Borrow this as an [`", stringify!($ref),
+                    "`]. This does *not* bump the refcount."
+                )]
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                ///
+                /// let x = Ref::new(8);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// let y = Ref::into_borrow(&x);
+                /// assert_eq!(Ref::as_ptr(&x), Ref::as_ptr(&y));
+                /// assert_eq!(Ref::count(&x), 1);
+                /// assert_eq!(Ref::count(&y), 1);
+                /// ```
+                #[inline]
+                pub fn into_borrow(this: &'a Self) -> Self {
+                    $ref::from_borrow($ref::$borrow_shared(this))
+                }
+
+                /// Clone this into an owned pointer, with the `'static` lifetime
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                ///
+                /// let x = Ref::new(7);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// let y = Ref::into_borrow(&x);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// assert_eq!(Ref::count(&y), 1);
+                /// let z = Ref::clone_into_owned(&y);
+                /// assert_eq!(Ref::as_ptr(&x), Ref::as_ptr(&z));
+                /// assert_eq!(Ref::count(&x), 2);
+                /// assert_eq!(Ref::count(&y), 2);
+                /// assert_eq!(Ref::count(&z), 2);
+                /// ```
+                #[inline]
+                pub fn clone_into_owned(this: &Self) -> $ref<'static, T, A> {
+                    $ref::$from_shared($ref::$clone_shared(this))
+                }
+
+                /// Get the internal pointer. This does *not* bump the refcount.
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!(
+                    "use elysees::{", stringify!($ptr), " as Ptr, ", stringify!($ref), " as Ref};"
+                )]
+                ///
+                /// let x = Ref::new(7);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// let x_ = x.clone();
+                /// assert_eq!(Ref::count(&x), 2);
+                /// let p = Ref::into_raw(x_);
+                /// assert_eq!(Ref::count(&x), 2);
+                /// assert_eq!(Ref::as_ptr(&x), p);
+                /// let y = unsafe { Ptr::from_raw(p) };
+                /// assert_eq!(Ref::as_ptr(&x), Ptr::as_ptr(&y));
+                /// assert_eq!(Ref::count(&x), 2);
+                /// std::mem::drop(y);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// ```
+                #[inline]
+                pub fn into_raw(this: Self) -> *const T {
+                    let result = $borrow::into_raw($ref::$borrow_shared(&this));
+                    mem::forget(this);
+                    result
+                }
+
+                /// Get the internal pointer. This does *not* bump the refcount.
+                ///
+                /// # Examples
+                /// ```rust
+                #[doc = concat!("use elysees::", stringify!($ref), " as Ref;")]
+                /// let x = Ref::new(7);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// let p = Ref::as_ptr(&x);
+                /// assert_eq!(Ref::count(&x), 1);
+                /// ```
+                #[inline]
+                pub fn as_ptr(this: &Self) -> *const T {
+                    $borrow::into_raw($ref::$borrow_shared(this))
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> Drop for $ref<'a, T, A> {
+                #[inline]
+                fn drop(&mut self) {
+                    if $ref::is_owned(self) {
+                        core::mem::drop(unsafe { $ptr::<T, A>::from_raw_in(self.ptr()) })
+                    }
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> Clone for $ref<'a, T, A> {
+                #[inline]
+                fn clone(&self) -> Self {
+                    if $ref::is_owned(self) {
+                        $ref::$from_shared($ref::$clone_shared(self))
+                    } else {
+                        $ref {
+                            p: self.p,
+                            phantom: PhantomData,
+                        }
+                    }
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> Deref for $ref<'a, T, A> {
+                type Target = T;
+
+                #[inline]
+                fn deref(&self) -> &T {
+                    unsafe { &*self.ptr() }
+                }
+            }
+
+            impl<'a, 'b, T, U, A, B> PartialEq<$ref<'a, T, B>> for $ref<'b, U, A>
+            where
+                T: ?Sized + Erasable,
+                U: ?Sized + Erasable + PartialEq<T>,
+                A: Allocator,
+                B: Allocator,
+            {
+                fn eq(&self, other: &$ref<'a, T, B>) -> bool {
+                    *(*self) == *(*other)
+                }
+
+                #[allow(clippy::partialeq_ne_impl)]
+                fn ne(&self, other: &$ref<'a, T, B>) -> bool {
+                    *(*self) != *(*other)
+                }
+            }
+
+            impl<'a, 'b, T, U, A, B> PartialOrd<$ref<'a, T, B>> for $ref<'b, U, A>
+            where
+                T: ?Sized + Erasable,
+                U: ?Sized + Erasable + PartialOrd<T>,
+                A: Allocator,
+                B: Allocator,
+            {
+                fn partial_cmp(&self, other: &$ref<'a, T, B>) -> Option<Ordering> {
+                    (**self).partial_cmp(&**other)
+                }
+
+                fn lt(&self, other: &$ref<'a, T, B>) -> bool {
+                    *(*self) < *(*other)
+                }
+
+                fn le(&self, other: &$ref<'a, T, B>) -> bool {
+                    *(*self) <= *(*other)
+                }
+
+                fn gt(&self, other: &$ref<'a, T, B>) -> bool {
+                    *(*self) > *(*other)
+                }
+
+                fn ge(&self, other: &$ref<'a, T, B>) -> bool {
+                    *(*self) >= *(*other)
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable + Ord, A: Allocator> Ord for $ref<'a, T, A> {
+                fn cmp(&self, other: &$ref<'a, T, A>) -> Ordering {
+                    (**self).cmp(&**other)
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable + Eq, A: Allocator> Eq for $ref<'a, T, A> {}
+
+            impl<'a, T: ?Sized + Erasable + fmt::Display, A: Allocator> fmt::Display
+                for $ref<'a, T, A>
+            {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Display::fmt(&**self, f)
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable + fmt::Debug, A: Allocator> fmt::Debug
+                for $ref<'a, T, A>
+            {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Debug::fmt(&**self, f)
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> fmt::Pointer for $ref<'a, T, A> {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Pointer::fmt(&self.nn_ptr(), f)
+                }
+            }
+
+            impl<'a, T: Erasable + Default> Default for $ref<'a, T> {
+                #[inline]
+                fn default() -> $ref<'a, T> {
+                    let d = $ref::new(Default::default());
+                    d
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable + Hash, A: Allocator> Hash for $ref<'a, T, A> {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    (**self).hash(state)
+                }
+            }
+
+            impl<'a, T> From<T> for $ref<'a, T> {
+                #[inline]
+                fn from(t: T) -> Self {
+                    $ref::new(t)
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> borrow::Borrow<T> for $ref<'a, T, A> {
+                #[inline]
+                fn borrow(&self) -> &T {
+                    self
+                }
+            }
+
+            impl<'a, T: ?Sized + Erasable, A: Allocator> AsRef<T> for $ref<'a, T, A> {
+                #[inline]
+                fn as_ref(&self) -> &T {
+                    self
+                }
+            }
+
+            #[cfg(feature = "stable_deref_trait")]
+            unsafe impl<'a, T: ?Sized + Erasable, A: Allocator> StableDeref for $ref<'a, T, A> {}
+            #[cfg(feature = "stable_deref_trait")]
+            unsafe impl<'a, T: ?Sized + Erasable, A: Allocator> CloneStableDeref
+                for $ref<'a, T, A>
+            {
+            }
+
+            #[cfg(feature = "serde")]
+            impl<'a, 'de, T: Deserialize<'de>> Deserialize<'de> for $ref<'a, T> {
+                fn deserialize<D>(deserializer: D) -> Result<$ref<'a, T>, D::Error>
+                where
+                    D: ::serde::de::Deserializer<'de>,
+                {
+                    T::deserialize(deserializer).map($ref::new)
+                }
+            }
+
+            #[cfg(feature = "serde")]
+            impl<'a, T: ?Sized + Erasable + Serialize, A: Allocator> Serialize for $ref<'a, T, A> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: ::serde::ser::Serializer,
+                {
+                    (**self).serialize(serializer)
+                }
+            }
+        };
+    };
+}
+
+pub(crate) use impl_ref;
+
+/// Implement a uniquely owned pointer type for a shared pointer type
+///
+/// `$unique` is the uniquely owned pointer type, a wrapper around a `$ptr` whose reference count
+/// is known to be one, `$ref` is the corresponding maybe-owned pointer type and `$count` is the
+/// type of the reference count of an `$inner`. `$from_shared` names the unchecked conversion from
+/// a `$ptr` into a `$unique`, e.g. `from_arc`, which must also be the name of the method converting
+/// a `$ptr` into a `$ref`.
+macro_rules! impl_unique {
+    (
+        $unique:ident, $ptr:ident, $inner:ident, $ref:ident, $count:ty;
+        $from_shared:ident
+    ) => {
+        const _: () = {
+            use ::alloc::alloc::Layout;
+            use ::alloc::borrow::Cow;
+            use ::alloc::boxed::Box;
+            use ::alloc::string::String;
+            use ::alloc::vec::Vec;
+            use ::core::borrow::{Borrow, BorrowMut};
+            use ::core::fmt::{self, Debug, Display, Formatter};
+            use ::core::mem::{ManuallyDrop, MaybeUninit};
+            use ::core::ops::{Deref, DerefMut};
+            use ::core::ptr::{self, NonNull};
+            use $crate::{
+                allocate, exact_len_or_collect, free_inner, try_allocate, write_from_iter,
+                AllocError, Allocator, Global,
+            };
+
+            #[cfg(feature = "slice-dst")]
+            use ::slice_dst::{AllocSliceDst, SliceDst, TryAllocSliceDst};
+            #[cfg(feature = "slice-dst")]
+            use $crate::deallocate;
+
+            impl<T> $unique<T> {
+                #[inline]
+                /// Construct a new uniquely owned pointer
+                pub fn new(data: T) -> Self {
+                    $unique($ptr::new(data))
+                }
+
+                /// Construct a new uniquely owned pointer, returning an error if the allocation
+                /// fails
+                #[inline]
+                pub fn try_new(data: T) -> Result<Self, AllocError> {
+                    $ptr::try_new(data).map($unique)
+                }
+
+                /// Construct a uniquely owned pointer to uninitialized data
+                #[inline]
+                pub fn new_uninit() -> $unique<MaybeUninit<T>> {
+                    $unique::new_uninit_in(Global)
+                }
+
+                /// Construct a uniquely owned pointer to uninitialized data, returning an error if
+                /// the allocation fails
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($unique), " as Unique;")]
+                ///
+                /// let mut x = Unique::<u32>::try_new_uninit().unwrap();
+                /// x.write(5);
+                /// let x = unsafe { Unique::assume_init(x) };
+                /// assert_eq!(*x, 5);
+                /// ```
+                #[inline]
+                pub fn try_new_uninit() -> Result<$unique<MaybeUninit<T>>, AllocError> {
+                    $unique::try_new_uninit_in(Global)
+                }
+
+                /// Move the inner value into a new [`Box`]
+                ///
+                /// For slices and strings, use the [`From`] implementations for [`Box<[T]>`][`Box`]
+                /// and [`Box<str>`][`Box`] instead.
+                #[inline]
+                pub fn into_box(this: Self) -> Box<T> {
+                    Box::new($unique::into_inner(this))
+                }
+            }
+
+            impl<T, A: Allocator> $unique<T, A> {
+                /// Construct a new uniquely owned pointer in the given allocator
+                #[inline]
+                pub fn new_in(data: T, alloc: A) -> Self {
+                    $unique($ptr::new_in(data, alloc))
+                }
+
+                /// Construct a new uniquely owned pointer in the given allocator, returning an
+                /// error if the allocation fails
+                #[inline]
+                pub fn try_new_in(data: T, alloc: A) -> Result<Self, AllocError> {
+                    $ptr::try_new_in(data, alloc).map($unique)
+                }
+
+                /// Construct a uniquely owned pointer to uninitialized data in the given allocator
+                #[inline]
+                pub fn new_uninit_in(alloc: A) -> $unique<MaybeUninit<T>, A> {
+                    let layout = Layout::new::<$inner<MaybeUninit<T>, A>>();
+                    unsafe { Self::init_uninit(allocate::<T, _, $count>(&alloc, layout), alloc) }
+                }
+
+                /// Construct a uniquely owned pointer to uninitialized data in the given allocator,
+                /// returning an error if the allocation fails
+                #[inline]
+                pub fn try_new_uninit_in(
+                    alloc: A,
+                ) -> Result<$unique<MaybeUninit<T>, A>, AllocError> {
+                    let layout = Layout::new::<$inner<MaybeUninit<T>, A>>();
+                    unsafe {
+                        Ok(Self::init_uninit(
+                            try_allocate::<T, _, $count>(&alloc, layout)?,
+                            alloc,
+                        ))
+                    }
+                }
+
+                #[doc = concat!(
+                            "Initialize the header of a freshly allocated `", stringify!($inner),
+                            "<MaybeUninit<T>, A>`"
+                        )]
+                ///
+                /// # Safety
+                /// `p` must point to an allocation from `alloc` with the layout of an
+                #[doc = concat!("`", stringify!($inner), "<MaybeUninit<T>, A>`")]
+                #[inline]
+                unsafe fn init_uninit(p: NonNull<u8>, alloc: A) -> $unique<MaybeUninit<T>, A> {
+                    $inner::<MaybeUninit<T>, A>::init_header(p, alloc, <$count>::new(1));
+                    $unique($ptr::from_raw_inner(p.cast()))
+                }
+
+                /// Gets the inner value
+                pub fn into_inner(this: Self) -> T {
+                    // Wrap the pointer in a `ManuallyDrop` so that its drop routine never runs
+                    let this = ManuallyDrop::new(this.0);
+                    debug_assert!(
+                        $ptr::is_unique(&this),
+                        concat!(
+                            "attempted to call `.into_inner()` on a `",
+                            stringify!($unique),
+                            "` with a non-zero ref count"
+                        ),
+                    );
+
+                    // Safety: We have exclusive access to the inner data, which we move out before
+                    //         freeing the allocation. The pointer will not perform its drop routine
+                    //         since we've wrapped it in a `ManuallyDrop`
+                    unsafe {
+                        let (layout, inner) = $inner::<T, A>::raw_layout(this.p.as_ptr());
+                        let data = ptr::read(this.p.as_ptr());
+                        free_inner::<_, $count>(inner, layout);
+                        data
+                    }
+                }
+
+                #[doc = concat!(
+                            "Convert to a shareable [`", stringify!($ref),
+                            "<'static, T>`] once we're done mutating it"
+                        )]
+                #[inline]
+                pub fn shareable_ref(self) -> $ref<'static, T, A> {
+                    $ref::$from_shared(self.0)
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> $unique<T, A> {
+                #[doc = concat!(
+                            "Convert to a shareable [`", stringify!($ptr),
+                            "<T>`] once we're done mutating it"
+                        )]
+                #[inline]
+                pub fn shareable(self) -> $ptr<T, A> {
+                    self.0
+                }
+
+                #[doc = concat!(
+                            "Creates a new [`", stringify!($unique), "`] from the given [`",
+                            stringify!($ptr), "`]."
+                        )]
+                ///
+                #[doc = concat!(
+                            "An unchecked alternative to [`", stringify!($ptr), "::try_unique`]"
+                        )]
+                ///
+                /// # Safety
+                ///
+                #[doc = concat!(
+                            "The given [`", stringify!($ptr),
+                            "`] must have a reference count of exactly one"
+                        )]
+                ///
+                pub(crate) unsafe fn $from_shared(arc: $ptr<T, A>) -> Self {
+                    debug_assert_eq!($ptr::count(&arc), 1);
+                    Self(arc)
+                }
+            }
+
+            impl<T, A: Allocator> $unique<MaybeUninit<T>, A> {
+                #[doc = concat!("Convert to an initialized [`", stringify!($ptr), "`].")]
+                ///
+                /// # Safety
+                ///
+                /// This function is equivalent to [`MaybeUninit::assume_init`] and has the
+                /// same safety requirements. You are responsible for ensuring that the `T`
+                /// has actually been initialized before calling this method.
+                #[inline]
+                pub unsafe fn assume_init(this: Self) -> $unique<T, A> {
+                    $unique($ptr::from_raw_inner(this.0.into_raw_inner().cast()))
+                }
+            }
+
+            impl<T> $unique<[T]> {
+                /// Construct a uniquely owned slice from an iterator which reports that it yields
+                /// `len` elements, writing them directly into a single allocation
+                ///
+                /// If the iterator panics, the elements written so far are dropped and the
+                /// allocation is freed. Since a safe iterator may misreport its length, if it
+                /// yields more or fewer than `len` elements, they are collected into a [`Vec`]
+                /// instead, and then moved into a new allocation.
+                pub(crate) fn from_iter_exact<I: Iterator<Item = T>>(iter: I, len: usize) -> Self {
+                    let mut uninit = $ptr::<[MaybeUninit<T>]>::new_uninit_slice(len);
+                    // Safety: we have just allocated `uninit`, so it is uniquely owned. If
+                    // `write_from_iter` panics, `uninit` is dropped, freeing the allocation.
+                    match write_from_iter(unsafe { uninit.p.as_mut() }, iter) {
+                        // Safety: all `len` elements have been initialized, and `uninit` is
+                        // uniquely owned
+                        Ok(()) => unsafe { $unique(uninit.assume_init()) },
+                        Err(v) => {
+                            drop(uninit);
+                            $unique::from(v)
+                        }
+                    }
+                }
+            }
+
+            impl<T: Copy> $unique<[T]> {
+                /// Copy a slice of [`Copy`] elements into a new allocation with a single `memcpy`
+                pub(crate) fn from_slice_copy(s: &[T]) -> Self {
+                    let len = s.len();
+                    let mut uninit = $ptr::<[MaybeUninit<T>]>::new_uninit_slice(len);
+                    unsafe {
+                        // Safety: `uninit` is a fresh allocation of `len` elements, which we
+                        // initialize by copying those of `s`
+                        let dst = uninit.p.as_mut().as_mut_ptr() as *mut T;
+                        ptr::copy_nonoverlapping(s.as_ptr(), dst, len);
+                        $unique(uninit.assume_init())
+                    }
+                }
+            }
+
+            impl<T: ?Sized> From<Box<T>> for $unique<T> {
+                /// Move a boxed value into a new allocation, freeing the original [`Box`]
+                ///
+                /// This works for unsized values such as slices and trait objects.
+                fn from(b: Box<T>) -> Self {
+                    let data = $inner::<T>::from_box(b, <$count>::new(1));
+                    unsafe { $unique($ptr::from_raw(data.as_ptr())) }
+                }
+            }
+
+            impl<T> From<$unique<[T]>> for Box<[T]> {
+                /// Move the elements of a uniquely owned slice into a new [`Box<[T]>`][`Box`]
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($unique), " as Unique;")]
+                ///
+                /// let x: Unique<[String]> = Unique::from(vec!["a".to_string(), "b".to_string()]);
+                /// let y: Box<[String]> = Box::from(x);
+                /// assert_eq!(*y, ["a", "b"]);
+                /// ```
+                fn from(b: $unique<[T]>) -> Self {
+                    let len = b.len();
+                    let mut v = Vec::with_capacity(len);
+                    let arc = ManuallyDrop::new(b.0);
+                    unsafe {
+                        let (layout, inner) = $inner::<[T]>::raw_layout(arc.p.as_ptr());
+                        // Safety: the elements are moved out of the box, which we own uniquely and
+                        // then free without dropping its contents
+                        ptr::copy_nonoverlapping(arc.as_ptr(), v.as_mut_ptr(), len);
+                        v.set_len(len);
+                        free_inner::<_, $count>(inner, layout);
+                    }
+                    v.into_boxed_slice()
+                }
+            }
+
+            impl From<$unique<str>> for Box<str> {
+                #[inline]
+                fn from(s: $unique<str>) -> Self {
+                    let bytes = $unique($ptr::into_bytes(s.0));
+                    // Safety: the bytes were moved out of a `str`, and so are valid UTF-8
+                    unsafe { alloc::str::from_boxed_utf8_unchecked(Box::from(bytes)) }
+                }
+            }
+
+            impl<T> From<Vec<T>> for $unique<[T]> {
+                /// Move the elements of a [`Vec`] into a new allocation
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($unique), " as Unique;")]
+                ///
+                /// let mut x = Unique::from(vec![1, 2, 3]);
+                /// x[1] = 5;
+                /// assert_eq!(*x, [1, 5, 3]);
+                /// ```
+                fn from(mut v: Vec<T>) -> Self {
+                    let len = v.len();
+                    let mut uninit = $ptr::<[MaybeUninit<T>]>::new_uninit_slice(len);
+                    unsafe {
+                        // Safety: the elements of `v` are moved into `uninit`, and then forgotten
+                        // by `v`
+                        let dst = uninit.p.as_mut().as_mut_ptr() as *mut T;
+                        ptr::copy_nonoverlapping(v.as_ptr(), dst, len);
+                        v.set_len(0);
+                        $unique(uninit.assume_init())
+                    }
+                }
+            }
+
+            impl From<&str> for $unique<str> {
+                /// Copy a string slice into a new allocation
+                ///
+                /// # Examples
+                ///
+                /// ```
+                #[doc = concat!("use elysees::", stringify!($unique), " as Unique;")]
+                ///
+                /// let mut x: Unique<str> = Unique::from("hello");
+                /// x.make_ascii_uppercase();
+                /// assert_eq!(&*x, "HELLO");
+                /// ```
+                #[inline]
+                fn from(s: &str) -> Self {
+                    let bytes = $unique::from_slice_copy(s.as_bytes());
+                    // Safety: the bytes were copied from a `str`, and so are valid UTF-8
+                    unsafe { $unique($ptr::from_utf8_unchecked(bytes.0)) }
+                }
+            }
+
+            impl From<String> for $unique<str> {
+                #[inline]
+                fn from(s: String) -> Self {
+                    let bytes = $unique::from(s.into_bytes());
+                    // Safety: the bytes were moved out of a `String`, and so are valid UTF-8
+                    unsafe { $unique($ptr::from_utf8_unchecked(bytes.0)) }
+                }
+            }
+
+            impl From<Cow<'_, str>> for $unique<str> {
+                #[inline]
+                fn from(s: Cow<'_, str>) -> Self {
+                    match s {
+                        Cow::Borrowed(s) => $unique::from(s),
+                        Cow::Owned(s) => $unique::from(s),
+                    }
+                }
+            }
+
+            impl<T: Clone> From<&[T]> for $unique<[T]> {
+                #[inline]
+                fn from(s: &[T]) -> Self {
+                    $unique::from_iter_exact(s.iter().cloned(), s.len())
+                }
+            }
+
+            impl<T> FromIterator<T> for $unique<[T]> {
+                /// Collect an iterator into a new allocation
+                ///
+                /// If the iterator reports an exact size via [`Iterator::size_hint`] of at most
+                /// 1 MiB, and yields that many elements, they are written directly into a single
+                /// allocation; otherwise, they are first collected into a [`Vec`].
+                fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+                    match exact_len_or_collect(iter.into_iter()) {
+                        Ok((iter, len)) => $unique::from_iter_exact(iter, len),
+                        Err(v) => $unique::from(v),
+                    }
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> TryFrom<$ptr<T, A>> for $unique<T, A> {
+                type Error = $ptr<T, A>;
+
+                fn try_from(arc: $ptr<T, A>) -> Result<Self, Self::Error> {
+                    $ptr::try_unique(arc)
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> Deref for $unique<T, A> {
+                type Target = T;
+
+                #[inline]
+                fn deref(&self) -> &T {
+                    #[allow(clippy::explicit_auto_deref)]
+                    &*self.0
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> DerefMut for $unique<T, A> {
+                #[inline]
+                fn deref_mut(&mut self) -> &mut T {
+                    // We know this to be uniquely owned
+                    unsafe { self.0.p.as_mut() }
+                }
+            }
+
+            impl<T: Clone, A: Allocator + Clone> Clone for $unique<T, A> {
+                #[inline]
+                fn clone(&self) -> $unique<T, A> {
+                    $unique::new_in(self.0.deref().clone(), $ptr::allocator(&self.0).clone())
+                }
+            }
+
+            impl<T: Default> Default for $unique<T> {
+                #[inline]
+                fn default() -> $unique<T> {
+                    $unique::new(Default::default())
+                }
+            }
+
+            impl<T: Debug, A: Allocator> Debug for $unique<T, A> {
+                #[inline]
+                fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                    Debug::fmt(&self.0, f)
+                }
+            }
+
+            impl<T: Display, A: Allocator> Display for $unique<T, A> {
+                fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                    Display::fmt(&self.0, f)
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> Borrow<T> for $unique<T, A> {
+                #[inline]
+                fn borrow(&self) -> &T {
+                    self
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> AsRef<T> for $unique<T, A> {
+                #[inline]
+                fn as_ref(&self) -> &T {
+                    self
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> BorrowMut<T> for $unique<T, A> {
+                #[inline]
+                fn borrow_mut(&mut self) -> &mut T {
+                    self
+                }
+            }
+
+            impl<T: ?Sized, A: Allocator> AsMut<T> for $unique<T, A> {
+                #[inline]
+                fn as_mut(&mut self) -> &mut T {
+                    self
+                }
+            }
+
+            /// # Safety
+            /// This leverages the correctness of the shared pointer's CoerciblePtr impl.
+            /// Additionally, we must ensure that this can not be used to violate the safety
+            /// invariants of the uniquely owned pointer, which require that we can not duplicate
+            /// the shared pointer, such that replace_ptr returns a valid instance. This holds since
+            /// it consumes a unique owner of the contained allocation.
+            #[cfg(feature = "unsize")]
+            unsafe impl<T, U: ?Sized, A: Allocator> unsize::CoerciblePtr<U> for $unique<T, A> {
+                type Pointee = T;
+                type Output = $unique<U, A>;
+
+                fn as_sized_ptr(&mut self) -> *mut T {
+                    // Dispatch to the contained field.
+                    unsize::CoerciblePtr::<U>::as_sized_ptr(&mut self.0)
+                }
+
+                unsafe fn replace_ptr(self, new: *mut U) -> $unique<U, A> {
+                    // Dispatch to the contained field, work around conflict of destructuring and
+                    // Drop.
+                    let inner = ManuallyDrop::new(self);
+                    $unique(ptr::read(&inner.0).replace_ptr(new))
+                }
+            }
+
+            /// This implementation is based on that in the
+            /// [documentation for `slice-dst`](https://docs.rs/slice-dst/latest/slice_dst/trait.AllocSliceDst.html).
+            ///
+            /// # Safety
+            ///
+            /// This function merely calls `try_new_slice` with an initializer statically guaranteed
+            /// never to fail, and therefore is safe if and only if `try_new_slice` is.
+            #[cfg(feature = "slice-dst")]
+            unsafe impl<S: ?Sized + SliceDst> AllocSliceDst<S> for $unique<S> {
+                unsafe fn new_slice_dst<I>(len: usize, init: I) -> Self
+                where
+                    I: FnOnce(ptr::NonNull<S>),
+                {
+                    #[allow(clippy::unit_arg)]
+                    let init = |ptr| Ok::<(), core::convert::Infallible>(init(ptr));
+                    #[allow(unreachable_patterns)]
+                    match Self::try_new_slice_dst(len, init) {
+                        Ok(a) => a,
+                        Err(void) => match void {},
+                    }
+                }
+            }
+
+            #[cfg(feature = "slice-dst")]
+            /// # Safety
+            ///
+            ///
+            unsafe impl<S: ?Sized + SliceDst> TryAllocSliceDst<S> for $unique<S> {
+                unsafe fn try_new_slice_dst<I, E>(len: usize, init: I) -> Result<Self, E>
+                where
+                    I: FnOnce(ptr::NonNull<S>) -> Result<(), E>,
+                {
+                    // Get the offset for the `S` field in the allocation:
+                    let s_layout = S::layout_for(len);
+                    let (layout, offset) = $inner::<S>::layout_with(s_layout).unwrap();
+
+                    // Get an allocation for the header followed by an `S`
+                    let ptr: NonNull<$inner<S>> = slice_dst::alloc_slice_dst(len);
+                    #[cfg(feature = "track-allocations")]
+                    crate::debug::register::<S>(ptr.as_ptr() as *mut u8, layout.size());
+                    #[cfg(feature = "header-canary")]
+                    crate::canary::Canary::init::<S, Global, $count>(
+                        ptr.as_ptr() as *mut u8,
+                        layout,
+                    );
+
+                    // Safety: Since this pointer is to the beginning of the allocation, we can
+                    // initialize the header through it...
+                    $inner::<S>::init_header(ptr.cast(), Global, <$count>::new(1));
+
+                    // Safety: the offset `offset` is in bounds of the allocation `ptr`
+                    let s_data_ptr = ptr.cast::<u8>().as_ptr().add(offset) as *mut ();
+
+                    // Safety: we can construct `NonNull<[()]>` with length `len` and ptr `ptr`
+                    let s_slice_ptr: NonNull<[()]> =
+                        NonNull::new_unchecked(ptr::slice_from_raw_parts_mut(s_data_ptr, len));
+                    let s_ptr = S::retype(s_slice_ptr);
+
+                    match init(s_ptr) {
+                        Ok(()) => {
+                            // Yay! Everything was initialized! Do a few checks for good measure.
+                            debug_assert_eq!(Layout::for_value(&*s_ptr.as_ptr()), s_layout);
+                            debug_assert_eq!(Layout::for_value(&*ptr.as_ptr()), layout);
+                        }
+                        Err(err) => {
+                            // Deallocate ptr and return an error
+                            deallocate::<_, $count>(Global, ptr.as_ptr() as *mut u8, layout);
+                            return Err(err);
+                        }
+                    }
+
+                    Ok($unique($ptr::from_raw(s_ptr.as_ptr())))
+                }
+            }
+        };
+    };
+}
+
+pub(crate) use impl_unique;
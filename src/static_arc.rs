@@ -0,0 +1,185 @@
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::mem;
+use core::ptr;
+use core::sync::atomic::AtomicUsize;
+
+#[cfg(feature = "header-canary")]
+use crate::canary::Canary;
+use crate::{Arc, ArcInner, IMMORTAL_REFCOUNT};
+
+/// Declare one or more immortal [`ArcInner`]s in `static`s
+///
+/// Each `static NAME: T = value;` declares a `static NAME: ArcInner<T>`, which can be turned into
+/// an [`Arc<T>`][`Arc`] with [`Arc::from_static`] or an [`ArcBorrow<'static, T>`][`crate::ArcBorrow`]
+/// with [`ArcBorrow::from_static`][`crate::ArcBorrow::from_static`], without allocating. The
+/// value is never dropped.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{static_arc, Arc, ArcBorrow};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Config {
+///     verbose: bool,
+/// }
+///
+/// static_arc! {
+///     /// The default configuration
+///     pub static DEFAULT_CONFIG: Config = Config { verbose: false };
+///     static NAME: &str = "elysees";
+/// }
+///
+/// let config: Arc<Config> = Arc::from_static(&DEFAULT_CONFIG);
+/// assert_eq!(*config, Config { verbose: false });
+/// let name: ArcBorrow<'static, &str> = ArcBorrow::from_static(&NAME);
+/// assert_eq!(*name.get(), "elysees");
+/// ```
+///
+/// With the `header-canary` feature, the data cannot be aligned to more than the reference count
+/// and canary preceding it, since the padding between them would be left uninitialized:
+///
+#[cfg_attr(feature = "header-canary", doc = "```compile_fail,E0080")]
+#[cfg_attr(not(feature = "header-canary"), doc = "```")]
+/// use elysees::{static_arc, Arc};
+///
+/// #[repr(align(64))]
+/// struct Aligned(u8);
+///
+/// static_arc! {
+///     static ALIGNED: Aligned = Aligned(5);
+/// }
+///
+/// let aligned: Arc<Aligned> = Arc::from_static(&ALIGNED);
+/// assert_eq!(aligned.clone().0, 5);
+/// assert_eq!(Arc::as_ptr(&aligned) as usize % 64, 0);
+/// ```
+#[macro_export]
+macro_rules! static_arc {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $value:expr; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::ArcInner<$ty> = $crate::ArcInner::new_static($value);
+        $crate::static_arc!($($rest)*);
+    };
+}
+
+/// The immortal header shared by every empty [`Arc<[T]>`][`Arc`]
+///
+/// Since the allocator is zero-sized, the header of an `ArcInner<[T]>` is at offset zero, and its
+/// data at the first multiple of `align_of::<T>()` after it, which lies within (or just past the
+/// end of) this header as long as `align_of::<T>() <= align_of::<EmptyInner>()`. With the
+/// `header-canary` feature, the header includes a canary which is accepted for any empty slice,
+/// followed by explicitly zeroed padding, which the canary check may read.
+#[repr(C, align(64))]
+struct EmptyInner {
+    count: AtomicUsize,
+    #[cfg(feature = "header-canary")]
+    canary: Canary,
+    #[cfg(feature = "header-canary")]
+    padding: [u8; 64 - mem::size_of::<usize>() - mem::size_of::<Canary>()],
+}
+
+static EMPTY: EmptyInner = EmptyInner {
+    count: AtomicUsize::new(IMMORTAL_REFCOUNT),
+    #[cfg(feature = "header-canary")]
+    canary: Canary::EMPTY,
+    #[cfg(feature = "header-canary")]
+    padding: [0; 64 - mem::size_of::<usize>() - mem::size_of::<Canary>()],
+};
+
+impl<T> Arc<[T]> {
+    /// Get an empty [`Arc<[T]>`][`Arc`]
+    ///
+    /// This shares a single immortal, statically allocated header between all empty slices, and
+    /// so does not allocate unless `T` is aligned to more than 64 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use elysees::Arc;
+    ///
+    /// let x: Arc<[u64]> = Arc::empty();
+    /// let y: Arc<[u64]> = Default::default();
+    /// assert!(x.is_empty());
+    /// assert!(Arc::ptr_eq(&x, &y));
+    /// ```
+    #[inline]
+    pub fn empty() -> Self {
+        // Under `loom`, the reference count of an `Arc` is loom's atomic, which `EMPTY` cannot hold
+        if cfg!(loom) || mem::align_of::<T>() > mem::align_of::<EmptyInner>() {
+            return Arc::from(Vec::new());
+        }
+        let (_, offset) = ArcInner::<[T]>::layout_with(Layout::new::<[T; 0]>()).unwrap();
+        debug_assert!(offset <= mem::size_of::<EmptyInner>());
+        let base = &EMPTY as *const EmptyInner as *mut u8;
+        // Safety: `EMPTY` has an immortal count at the offset expected for an `ArcInner<[T]>`, and
+        //         `offset` is in bounds of it, as explained above. The pointer is derived from a
+        //         reference to all of `EMPTY`.
+        unsafe { Arc::from_raw(ptr::slice_from_raw_parts(base.add(offset) as *const T, 0)) }
+    }
+}
+
+impl<T> Default for Arc<[T]> {
+    /// Get an empty [`Arc<[T]>`][`Arc`], which does not allocate. See [`Arc::empty`].
+    #[inline]
+    fn default() -> Self {
+        Arc::empty()
+    }
+}
+
+impl Default for Arc<str> {
+    /// Get an empty [`Arc<str>`][`Arc`], which does not allocate
+    #[inline]
+    fn default() -> Self {
+        // Safety: the empty string is valid UTF-8
+        unsafe { Arc::from_utf8_unchecked(Arc::empty()) }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use crate::{Arc, ArcBorrow};
+
+    static_arc! {
+        static STATIC_VEC: alloc::vec::Vec<u8> = alloc::vec::Vec::new();
+    }
+
+    #[test]
+    fn static_clone_drop() {
+        let x = Arc::from_static(&STATIC_VEC);
+        let borrow = ArcBorrow::from_static(&STATIC_VEC);
+        let clones: alloc::vec::Vec<_> = (0..100).map(|_| ArcBorrow::clone_arc(borrow)).collect();
+        assert!(clones.iter().all(|c| Arc::ptr_eq(c, &x)));
+        drop(clones);
+        let mut x = x;
+        assert!(Arc::get_mut(&mut x).is_none());
+        assert!(Arc::try_unique(x).is_err());
+        assert!(borrow.is_empty());
+    }
+
+    #[test]
+    fn empty_singleton() {
+        #[repr(align(32))]
+        #[derive(Debug, PartialEq)]
+        struct Aligned32(u8);
+        #[repr(align(128))]
+        #[derive(Debug, PartialEq)]
+        struct Aligned128(u8);
+
+        let x: Arc<[u8]> = Arc::empty();
+        let y: Arc<[u64]> = Default::default();
+        let z: Arc<[Aligned32]> = Arc::empty();
+        let w: Arc<[Aligned128]> = Arc::empty();
+        assert_eq!(Arc::as_ptr(&z) as *const u8 as usize % 32, 0);
+        assert_eq!(Arc::as_ptr(&w) as *const u8 as usize % 128, 0);
+        assert_eq!((x.len(), y.len(), z.len(), w.len()), (0, 0, 0, 0));
+        assert!(Arc::ptr_eq(&y, &y.clone()));
+        assert!(Arc::is_unique(&w));
+
+        let s: Arc<str> = Default::default();
+        assert_eq!(&*s, "");
+        assert_eq!(Arc::into_bytes(s), x);
+    }
+}
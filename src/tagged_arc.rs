@@ -0,0 +1,334 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use crate::{Allocator, Arc, ArcBorrow, ArcInner, Global};
+
+/// An [`Arc<T>`][`Arc`] carrying a `BITS`-bit tag in the spare low bits of its pointer
+///
+/// The data of an [`ArcInner<T>`] is aligned to at least `align_of::<ArcInner<T>>()`, whatever the
+/// allocator, so this many low bits of its address are always zero. Asking for more bits than
+/// this fails to compile. The tag is added to and removed from the pointer by offsetting it, so
+/// its provenance is preserved.
+///
+/// # Examples
+///
+/// ```
+/// use elysees::{Arc, TaggedArc};
+///
+/// let x = Arc::new(5u64);
+/// let mut t: TaggedArc<u64, 2> = TaggedArc::new(x.clone(), 3);
+/// assert_eq!((*t, TaggedArc::tag(&t)), (5, 3));
+/// TaggedArc::set_tag(&mut t, 1);
+/// let (y, tag) = TaggedArc::into_parts(t.clone());
+/// assert!(Arc::ptr_eq(&x, &y));
+/// assert_eq!(tag, 1);
+/// assert_eq!(Arc::count(&x), 3);
+/// ```
+///
+/// ```compile_fail
+/// use elysees::{Arc, TaggedArc};
+///
+/// // A `u8` is only guaranteed to be aligned to `align_of::<AtomicUsize>()`
+/// let t: TaggedArc<u8, 8> = TaggedArc::new(Arc::new(5), 0);
+/// ```
+pub struct TaggedArc<T, const BITS: u32, A: Allocator = Global> {
+    p: NonNull<u8>,
+    phantom: PhantomData<Arc<T, A>>,
+}
+
+unsafe impl<T: Sync + Send, const BITS: u32, A: Allocator + Sync + Send> Send
+    for TaggedArc<T, BITS, A>
+{
+}
+unsafe impl<T: Sync + Send, const BITS: u32, A: Allocator + Sync + Send> Sync
+    for TaggedArc<T, BITS, A>
+{
+}
+
+/// A borrowed [`TaggedArc`], carrying a tag in the same way
+pub struct TaggedArcBorrow<'a, T, const BITS: u32, A: Allocator = Global> {
+    p: NonNull<u8>,
+    phantom: PhantomData<ArcBorrow<'a, T, A>>,
+}
+
+impl<'a, T, const BITS: u32, A: Allocator> Copy for TaggedArcBorrow<'a, T, BITS, A> {}
+impl<'a, T, const BITS: u32, A: Allocator> Clone for TaggedArcBorrow<'a, T, BITS, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+unsafe impl<'a, T: Sync + Send, const BITS: u32, A: Allocator + Sync + Send> Send
+    for TaggedArcBorrow<'a, T, BITS, A>
+{
+}
+unsafe impl<'a, T: Sync + Send, const BITS: u32, A: Allocator + Sync + Send> Sync
+    for TaggedArcBorrow<'a, T, BITS, A>
+{
+}
+
+/// The number of low bits which are always zero in the data pointer of an [`Arc<T>`][`Arc`]
+///
+/// The count precedes the data, and both are placed at a multiple of their alignment, so the data
+/// is aligned to the larger of the two. This is the alignment of an `ArcInner<T>` with the
+/// (zero-sized) [`Global`] allocator, and does not depend on the allocator used.
+#[inline]
+pub(crate) const fn spare_bits<T>() -> u32 {
+    mem::align_of::<ArcInner<T>>().trailing_zeros()
+}
+
+/// Tag a data pointer with `tag`, which must fit in its spare bits
+#[inline]
+fn tag_ptr<T>(p: NonNull<T>, tag: usize) -> NonNull<u8> {
+    let p = p.cast::<u8>();
+    // Safety: the data pointer is aligned to more than `tag`, so adding it cannot reach zero
+    unsafe { NonNull::new_unchecked(p.as_ptr().wrapping_add(tag)) }
+}
+
+/// Split a tagged pointer into its data pointer and tag
+#[inline]
+fn untag_ptr<T, const BITS: u32>(p: NonNull<u8>) -> (NonNull<T>, usize) {
+    let tag = p.as_ptr() as usize & ((1 << BITS) - 1);
+    // Safety: subtracting the tag gives back the original, non-null data pointer
+    let p = unsafe { NonNull::new_unchecked(p.as_ptr().wrapping_sub(tag)) };
+    (p.cast(), tag)
+}
+
+impl<T, const BITS: u32, A: Allocator> TaggedArc<T, BITS, A> {
+    /// The number of spare low bits available in the pointer of an [`Arc<T>`][`Arc`]
+    pub const AVAILABLE_BITS: u32 = spare_bits::<T>();
+
+    /// The largest tag which can be stored
+    pub const MAX_TAG: usize = {
+        assert!(
+            BITS <= spare_bits::<T>(),
+            "`T` is not aligned enough to store a tag of this many bits"
+        );
+        (1 << BITS) - 1
+    };
+
+    /// Tag an [`Arc`]
+    ///
+    /// # Panics
+    /// Panics if `tag` does not fit in `BITS` bits
+    #[inline]
+    pub fn new(arc: Arc<T, A>, tag: usize) -> Self {
+        assert!(tag <= Self::MAX_TAG, "tag does not fit in {} bits", BITS);
+        let p = tag_ptr(arc.p, tag);
+        mem::forget(arc);
+        TaggedArc {
+            p,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the tag
+    #[inline]
+    pub fn tag(this: &Self) -> usize {
+        untag_ptr::<T, BITS>(this.p).1
+    }
+
+    /// Set the tag
+    ///
+    /// # Panics
+    /// Panics if `tag` does not fit in `BITS` bits
+    #[inline]
+    pub fn set_tag(this: &mut Self, tag: usize) {
+        assert!(tag <= Self::MAX_TAG, "tag does not fit in {} bits", BITS);
+        this.p = tag_ptr(untag_ptr::<T, BITS>(this.p).0, tag);
+    }
+
+    /// Split this into the underlying [`Arc`] and its tag
+    #[inline]
+    pub fn into_parts(this: Self) -> (Arc<T, A>, usize) {
+        let (p, tag) = untag_ptr::<T, BITS>(this.p);
+        mem::forget(this);
+        // Safety: we owned this reference, and `this` is forgotten
+        (unsafe { Arc::from_raw_in(p.as_ptr()) }, tag)
+    }
+
+    /// Convert this into the underlying [`Arc`], discarding the tag
+    #[inline]
+    pub fn into_arc(this: Self) -> Arc<T, A> {
+        TaggedArc::into_parts(this).0
+    }
+
+    /// Borrow this as a [`TaggedArcBorrow`] with the same tag, without touching the reference
+    /// count
+    #[inline]
+    pub fn borrow(this: &Self) -> TaggedArcBorrow<'_, T, BITS, A> {
+        TaggedArcBorrow {
+            p: this.p,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Borrow the underlying [`Arc`] as an [`ArcBorrow`], without touching the reference count
+    #[inline]
+    pub fn borrow_arc(this: &Self) -> ArcBorrow<'_, T, A> {
+        TaggedArcBorrow::borrow_arc(TaggedArc::borrow(this))
+    }
+
+    /// Get the (untagged) data pointer
+    #[inline]
+    pub fn as_ptr(this: &Self) -> *const T {
+        untag_ptr::<T, BITS>(this.p).0.as_ptr()
+    }
+
+    /// Test pointer equality between the two [`TaggedArc`]s, ignoring their tags
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        TaggedArc::as_ptr(this) == TaggedArc::as_ptr(other)
+    }
+}
+
+impl<'a, T, const BITS: u32, A: Allocator> TaggedArcBorrow<'a, T, BITS, A> {
+    /// Tag an [`ArcBorrow`]
+    ///
+    /// # Panics
+    /// Panics if `tag` does not fit in `BITS` bits
+    #[inline]
+    pub fn new(arc: ArcBorrow<'a, T, A>, tag: usize) -> Self {
+        assert!(
+            tag <= TaggedArc::<T, BITS, A>::MAX_TAG,
+            "tag does not fit in {} bits",
+            BITS
+        );
+        TaggedArcBorrow {
+            p: tag_ptr(arc.p, tag),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the tag
+    #[inline]
+    pub fn tag(this: Self) -> usize {
+        untag_ptr::<T, BITS>(this.p).1
+    }
+
+    /// Get the underlying [`ArcBorrow`], discarding the tag
+    #[inline]
+    pub fn borrow_arc(this: Self) -> ArcBorrow<'a, T, A> {
+        ArcBorrow {
+            p: untag_ptr::<T, BITS>(this.p).0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get a reference to the data with the lifetime `'a`
+    #[inline]
+    pub fn get(&self) -> &'a T {
+        TaggedArcBorrow::borrow_arc(*self).get()
+    }
+
+    /// Clone this as a [`TaggedArc`] with the same tag, bumping the reference count
+    #[inline]
+    pub fn clone_arc(this: Self) -> TaggedArc<T, BITS, A> {
+        let arc = ArcBorrow::clone_arc(TaggedArcBorrow::borrow_arc(this));
+        TaggedArc::new(arc, TaggedArcBorrow::tag(this))
+    }
+}
+
+impl<T, const BITS: u32, A: Allocator> Clone for TaggedArc<T, BITS, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        TaggedArcBorrow::clone_arc(TaggedArc::borrow(self))
+    }
+}
+
+impl<T, const BITS: u32, A: Allocator> Drop for TaggedArc<T, BITS, A> {
+    #[inline]
+    fn drop(&mut self) {
+        let (p, _) = untag_ptr::<T, BITS>(self.p);
+        // Safety: we own this reference, and it is never used again
+        drop(unsafe { Arc::<T, A>::from_raw_in(p.as_ptr()) })
+    }
+}
+
+impl<T, const BITS: u32, A: Allocator> Deref for TaggedArc<T, BITS, A> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        TaggedArc::borrow(self).get()
+    }
+}
+
+impl<'a, T, const BITS: u32, A: Allocator> Deref for TaggedArcBorrow<'a, T, BITS, A> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: fmt::Debug, const BITS: u32, A: Allocator> fmt::Debug for TaggedArc<T, BITS, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&TaggedArc::borrow(self), f)
+    }
+}
+
+impl<'a, T: fmt::Debug, const BITS: u32, A: Allocator> fmt::Debug
+    for TaggedArcBorrow<'a, T, BITS, A>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaggedArc")
+            .field("data", self.get())
+            .field("tag", &TaggedArcBorrow::tag(*self))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arc, ArcBorrow, TaggedArc, TaggedArcBorrow};
+    use core::mem;
+    use core::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn available_bits() {
+        #[repr(align(64))]
+        struct Aligned64;
+
+        let word = mem::align_of::<AtomicUsize>().trailing_zeros();
+        assert_eq!(TaggedArc::<u8, 0>::AVAILABLE_BITS, word);
+        assert_eq!(TaggedArc::<Aligned64, 6>::AVAILABLE_BITS, 6);
+        assert_eq!(TaggedArc::<Aligned64, 6>::MAX_TAG, 63);
+
+        let x = Arc::new(Aligned64);
+        let tagged: alloc::vec::Vec<TaggedArc<Aligned64, 6>> =
+            (0..64).map(|tag| TaggedArc::new(x.clone(), tag)).collect();
+        for (tag, t) in tagged.iter().enumerate() {
+            assert_eq!(TaggedArc::tag(t), tag);
+            assert_eq!(TaggedArc::as_ptr(t), Arc::as_ptr(&x));
+        }
+        assert_eq!(Arc::count(&x), 65);
+        drop(tagged);
+        assert!(Arc::is_unique(&x));
+    }
+
+    #[test]
+    fn borrow_round_trip() {
+        let x = Arc::new(7u32);
+        let b = TaggedArcBorrow::<u32, 1>::new(Arc::borrow_arc(&x), 1);
+        assert_eq!((*b, TaggedArcBorrow::tag(b)), (7, 1));
+        let t = TaggedArcBorrow::clone_arc(b);
+        assert_eq!(Arc::count(&x), 2);
+        assert!(ArcBorrow::ptr_eq(
+            TaggedArc::borrow_arc(&t),
+            TaggedArcBorrow::borrow_arc(b)
+        ));
+        let mut u = t.clone();
+        TaggedArc::set_tag(&mut u, 0);
+        assert!(TaggedArc::ptr_eq(&t, &u));
+        assert_eq!((TaggedArc::tag(&t), TaggedArc::tag(&u)), (1, 0));
+        assert!(Arc::ptr_eq(&TaggedArc::into_arc(u), &x));
+        drop(t);
+        assert!(Arc::is_unique(&x));
+    }
+}
@@ -0,0 +1,456 @@
+use alloc::alloc::Layout;
+#[cfg(doc)]
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::ffi::c_void;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+
+use erasable::{Erasable, ErasablePtr, ErasedPtr};
+#[cfg(feature = "stable_deref_trait")]
+use stable_deref_trait::{CloneStableDeref, StableDeref};
+
+use crate::{
+    allocate, exact_len_or_collect, write_from_iter, Allocator, Arc, ArcBorrow, ArcBox, ArcCount,
+    ArcInner, Global,
+};
+
+/// A header together with a slice, which stores the length of the slice inline
+///
+/// Since the length can be read from the value itself, a `HeaderSlice<H, [T]>` is [`Erasable`],
+/// and so can be pointed to by a thin [`ThinArc`] or an [`ArcRef`][`crate::ArcRef`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(C)]
+pub struct HeaderSlice<H, T: ?Sized> {
+    /// The header
+    pub header: H,
+    /// The length of `slice`
+    length: usize,
+    /// The slice
+    pub slice: T,
+}
+
+impl<H, T> HeaderSlice<H, [T]> {
+    /// Get the number of elements in the slice
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Whether the slice is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Get the layout of a `HeaderSlice<H, [T]>` with a slice of length `len`, along with the
+    /// offset of the slice, or `None` on overflow
+    #[inline]
+    fn layout_for(len: usize) -> Option<(Layout, usize)> {
+        let (header, _) = Layout::new::<H>().extend(Layout::new::<usize>()).ok()?;
+        let (layout, offset) = header.extend(Layout::array::<T>(len).ok()?).ok()?;
+        Some((layout.pad_to_align(), offset))
+    }
+
+    /// Given a thin pointer to a `HeaderSlice<H, [T]>`, get a wide pointer by reading its length
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid `HeaderSlice<H, [T]>`
+    #[inline]
+    unsafe fn from_thin(ptr: *mut HeaderSlice<H, [T; 0]>) -> *mut HeaderSlice<H, [T]> {
+        // We only read the length, without creating any references, as required by `Erasable`
+        let len = ptr::addr_of!((*ptr).length).read();
+        ptr::slice_from_raw_parts_mut(ptr as *mut T, len) as *mut HeaderSlice<H, [T]>
+    }
+}
+
+unsafe impl<H, T> Erasable for HeaderSlice<H, [T]> {
+    #[inline]
+    unsafe fn unerase(this: ErasedPtr) -> NonNull<Self> {
+        NonNull::new_unchecked(HeaderSlice::from_thin(this.cast().as_ptr()))
+    }
+
+    const ACK_1_1_0: bool = true;
+}
+
+impl<H, T> ArcBox<HeaderSlice<H, [T]>> {
+    /// Construct an [`ArcBox<HeaderSlice<H, [T]>>`][`ArcBox`] from a header and an iterator, in a
+    /// single allocation
+    ///
+    /// If the iterator reports an exact size via [`Iterator::size_hint`] of at most 1 MiB, and
+    /// yields that many elements, they are written directly into the allocation; otherwise, they
+    /// are first collected into a [`Vec`].
+    pub fn from_header_and_iter<I: IntoIterator<Item = T>>(header: H, iter: I) -> Self {
+        match exact_len_or_collect(iter.into_iter()) {
+            Ok((iter, len)) => Self::from_header_and_iter_exact(header, iter, len),
+            Err(v) => {
+                let len = v.len();
+                Self::from_header_and_iter_exact(header, v.into_iter(), len)
+            }
+        }
+    }
+
+    /// Construct an [`ArcBox<HeaderSlice<H, [T]>>`][`ArcBox`] from a header and a slice to clone
+    #[inline]
+    pub fn from_header_and_slice(header: H, slice: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_header_and_iter_exact(header, slice.iter().cloned(), slice.len())
+    }
+
+    /// Construct an [`ArcBox<HeaderSlice<H, [T]>>`][`ArcBox`] from a header and an iterator which
+    /// reports that it yields `len` elements
+    ///
+    /// If the iterator panics, the header and the elements written so far are dropped, and the
+    /// allocation is freed. If it yields more or fewer than `len` elements, they are collected into
+    /// a [`Vec`] instead, and then moved into a new allocation.
+    fn from_header_and_iter_exact<I: Iterator<Item = T>>(header: H, iter: I, len: usize) -> Self {
+        let (value_layout, _) = HeaderSlice::<H, [T]>::layout_for(len).expect("capacity overflow");
+        let (layout, offset) =
+            ArcInner::<HeaderSlice<H, [T]>>::layout_with(value_layout).expect("capacity overflow");
+        unsafe {
+            let inner = allocate::<HeaderSlice<H, [T]>, _, ArcCount>(&Global, layout);
+            ArcInner::<HeaderSlice<H, [T]>>::init_header(inner, Global, ArcCount::new(1));
+            let data = ptr::slice_from_raw_parts_mut(inner.as_ptr().add(offset) as *mut T, len)
+                as *mut HeaderSlice<MaybeUninit<H>, [MaybeUninit<T>]>;
+            ptr::addr_of_mut!((*data).length).write(len);
+            // Safety: the length is initialized, and the header and slice need not be. The header
+            // is only written once the slice is. If `write_from_iter` panics, `uninit` is dropped,
+            // freeing the allocation, and `header` is dropped as usual.
+            let mut uninit = Arc::from_raw(data);
+            match write_from_iter(&mut uninit.p.as_mut().slice, iter) {
+                Ok(()) => {
+                    uninit.p.as_mut().header.write(header);
+                    // Safety: `HeaderSlice` is `repr(C)`, and the header and slice are now
+                    // initialized
+                    ArcBox(Arc::from_raw(
+                        Arc::into_raw(uninit) as *const HeaderSlice<H, [T]>
+                    ))
+                }
+                Err(v) => {
+                    drop(uninit);
+                    let len = v.len();
+                    Self::from_header_and_iter_exact(header, v.into_iter(), len)
+                }
+            }
+        }
+    }
+}
+
+impl<H, T> Arc<HeaderSlice<H, [T]>> {
+    /// Construct an [`Arc<HeaderSlice<H, [T]>>`][`Arc`] from a header and an iterator, in a single
+    /// allocation
+    ///
+    /// See [`ArcBox::from_header_and_iter`].
+    #[inline]
+    pub fn from_header_and_iter<I: IntoIterator<Item = T>>(header: H, iter: I) -> Self {
+        ArcBox::from_header_and_iter(header, iter).shareable()
+    }
+
+    /// Construct an [`Arc<HeaderSlice<H, [T]>>`][`Arc`] from a header and a slice to clone
+    #[inline]
+    pub fn from_header_and_slice(header: H, slice: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        ArcBox::from_header_and_slice(header, slice).shareable()
+    }
+}
+
+/// A thin, single-word [`Arc`] pointing to a [`HeaderSlice<H, [T]>`][`HeaderSlice`]
+///
+/// This has the same representation as an [`Arc<HeaderSlice<H, [T]>>`][`Arc`], except that the
+/// length of the slice is read from the allocation rather than stored in the pointer, and can be
+/// converted to and from one for free.
+///
+/// ```rust
+/// # use elysees::{Arc, ThinArc};
+/// let x = ThinArc::from_header_and_iter("header", 0..5);
+/// assert_eq!(std::mem::size_of_val(&x), std::mem::size_of::<usize>());
+/// assert_eq!(x.header, "header");
+/// assert_eq!(x.slice, [0, 1, 2, 3, 4]);
+///
+/// let y = ThinArc::into_arc(x.clone());
+/// assert_eq!(Arc::count(&y), 2);
+/// assert!(ThinArc::ptr_eq(&x, &ThinArc::from_arc(y)));
+/// ```
+#[repr(transparent)]
+pub struct ThinArc<H, T, A: Allocator = Global> {
+    p: NonNull<HeaderSlice<H, [T; 0]>>,
+    phantom: PhantomData<ArcInner<HeaderSlice<H, [T]>, A>>,
+}
+
+unsafe impl<H: Sync + Send, T: Sync + Send, A: Allocator + Sync + Send> Send for ThinArc<H, T, A> {}
+unsafe impl<H: Sync + Send, T: Sync + Send, A: Allocator + Sync + Send> Sync for ThinArc<H, T, A> {}
+
+impl<H, T> ThinArc<H, T> {
+    /// Construct a [`ThinArc`] from a header and an iterator, in a single allocation
+    ///
+    /// See [`ArcBox::from_header_and_iter`].
+    #[inline]
+    pub fn from_header_and_iter<I: IntoIterator<Item = T>>(header: H, iter: I) -> Self {
+        ThinArc::from_arc(Arc::from_header_and_iter(header, iter))
+    }
+
+    /// Construct a [`ThinArc`] from a header and a slice to clone
+    #[inline]
+    pub fn from_header_and_slice(header: H, slice: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        ThinArc::from_arc(Arc::from_header_and_slice(header, slice))
+    }
+}
+
+impl<H, T, A: Allocator> ThinArc<H, T, A> {
+    /// Convert an [`Arc<HeaderSlice<H, [T]>>`][`Arc`] into a [`ThinArc`]. This does not touch the
+    /// refcount.
+    #[inline]
+    pub fn from_arc(arc: Arc<HeaderSlice<H, [T]>, A>) -> Self {
+        ThinArc {
+            p: unsafe { NonNull::new_unchecked(Arc::into_raw(arc) as *mut _) },
+            phantom: PhantomData,
+        }
+    }
+
+    /// Convert a [`ThinArc`] into an [`Arc<HeaderSlice<H, [T]>>`][`Arc`]. This does not touch the
+    /// refcount.
+    #[inline]
+    pub fn into_arc(this: Self) -> Arc<HeaderSlice<H, [T]>, A> {
+        let this = ManuallyDrop::new(this);
+        unsafe { Arc::from_raw_in(ThinArc::ptr(&this)) }
+    }
+
+    /// Temporarily view this [`ThinArc`] as an [`Arc<HeaderSlice<H, [T]>>`][`Arc`]
+    #[inline]
+    pub fn with_arc<F, U>(this: &Self, f: F) -> U
+    where
+        F: FnOnce(&Arc<HeaderSlice<H, [T]>, A>) -> U,
+    {
+        // Wrap the `Arc` in a `ManuallyDrop` so that the refcount is never modified
+        let arc = ManuallyDrop::new(unsafe { Arc::from_raw_in(ThinArc::ptr(this)) });
+        f(&arc)
+    }
+
+    /// Borrow this as an [`ArcBorrow`]. This does *not* bump the refcount.
+    #[inline]
+    pub fn borrow_arc(this: &Self) -> ArcBorrow<'_, HeaderSlice<H, [T]>, A> {
+        unsafe { ArcBorrow::from_raw_in(ThinArc::ptr(this)) }
+    }
+
+    /// Test pointer equality between the two [`ThinArc`]s, i.e. they must be the _same_
+    /// allocation
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.p == other.p
+    }
+
+    /// Gets the number of [`Arc`] pointers to this allocation
+    #[inline]
+    pub fn count(this: &Self) -> usize {
+        ArcBorrow::count(ThinArc::borrow_arc(this))
+    }
+
+    /// Whether or not the [`ThinArc`] is uniquely owned (is the refcount 1?).
+    #[inline]
+    pub fn is_unique(this: &Self) -> bool {
+        ThinArc::count(this) == 1
+    }
+
+    /// Provides mutable access to the contents _if_ the [`ThinArc`] is uniquely owned.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut HeaderSlice<H, [T]>> {
+        if ThinArc::is_unique(this) {
+            // See `Arc::make_mut` for documentation of the threadsafety here.
+            unsafe { Some(&mut *ThinArc::ptr(this)) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns the address on the heap of the [`ThinArc`] itself -- not the `T` within it -- for
+    /// memory reporting.
+    #[inline]
+    pub fn heap_ptr(&self) -> *const c_void {
+        ArcBorrow::heap_ptr(ThinArc::borrow_arc(self))
+    }
+
+    /// Get the wide pointer to the [`HeaderSlice`]
+    #[inline]
+    fn ptr(this: &Self) -> *mut HeaderSlice<H, [T]> {
+        unsafe { HeaderSlice::from_thin(this.p.as_ptr()) }
+    }
+}
+
+impl<H, T, A: Allocator> Clone for ThinArc<H, T, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        ThinArc::with_arc(self, |arc| ThinArc::from_arc(arc.clone()))
+    }
+}
+
+impl<H, T, A: Allocator> Drop for ThinArc<H, T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        core::mem::drop(unsafe { Arc::<_, A>::from_raw_in(ThinArc::ptr(self)) })
+    }
+}
+
+impl<H, T, A: Allocator> Deref for ThinArc<H, T, A> {
+    type Target = HeaderSlice<H, [T]>;
+
+    #[inline]
+    fn deref(&self) -> &HeaderSlice<H, [T]> {
+        unsafe { &*ThinArc::ptr(self) }
+    }
+}
+
+impl<H, T, A: Allocator> From<Arc<HeaderSlice<H, [T]>, A>> for ThinArc<H, T, A> {
+    #[inline]
+    fn from(arc: Arc<HeaderSlice<H, [T]>, A>) -> Self {
+        ThinArc::from_arc(arc)
+    }
+}
+
+impl<H, T, A: Allocator> From<ThinArc<H, T, A>> for Arc<HeaderSlice<H, [T]>, A> {
+    #[inline]
+    fn from(thin: ThinArc<H, T, A>) -> Self {
+        ThinArc::into_arc(thin)
+    }
+}
+
+unsafe impl<H, T, A: Allocator> ErasablePtr for ThinArc<H, T, A> {
+    #[inline]
+    fn erase(this: Self) -> ErasedPtr {
+        let this = ManuallyDrop::new(this);
+        this.p.cast()
+    }
+
+    #[inline]
+    unsafe fn unerase(this: ErasedPtr) -> Self {
+        ThinArc {
+            p: this.cast(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<H: PartialEq, T: PartialEq, A: Allocator> PartialEq for ThinArc<H, T, A> {
+    #[inline]
+    fn eq(&self, other: &ThinArc<H, T, A>) -> bool {
+        **self == **other
+    }
+}
+
+impl<H: Eq, T: Eq, A: Allocator> Eq for ThinArc<H, T, A> {}
+
+impl<H: PartialOrd, T: PartialOrd, A: Allocator> PartialOrd for ThinArc<H, T, A> {
+    #[inline]
+    fn partial_cmp(&self, other: &ThinArc<H, T, A>) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<H: Ord, T: Ord, A: Allocator> Ord for ThinArc<H, T, A> {
+    #[inline]
+    fn cmp(&self, other: &ThinArc<H, T, A>) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<H: Hash, T: Hash, A: Allocator> Hash for ThinArc<H, T, A> {
+    #[inline]
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        (**self).hash(state)
+    }
+}
+
+impl<H: fmt::Debug, T: fmt::Debug, A: Allocator> fmt::Debug for ThinArc<H, T, A> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<H, T, A: Allocator> fmt::Pointer for ThinArc<H, T, A> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.p, f)
+    }
+}
+
+impl<H, T, A: Allocator> Borrow<HeaderSlice<H, [T]>> for ThinArc<H, T, A> {
+    #[inline]
+    fn borrow(&self) -> &HeaderSlice<H, [T]> {
+        self
+    }
+}
+
+impl<H, T, A: Allocator> AsRef<HeaderSlice<H, [T]>> for ThinArc<H, T, A> {
+    #[inline]
+    fn as_ref(&self) -> &HeaderSlice<H, [T]> {
+        self
+    }
+}
+
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<H, T, A: Allocator> StableDeref for ThinArc<H, T, A> {}
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<H, T, A: Allocator> CloneStableDeref for ThinArc<H, T, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArcRef;
+
+    #[test]
+    fn thin_arc_roundtrip() {
+        let x = ThinArc::from_header_and_slice(5u8, &[1u64, 2, 3]);
+        assert_eq!(core::mem::size_of_val(&x), core::mem::size_of::<usize>());
+        assert_eq!(x.header, 5);
+        assert_eq!(x.len(), 3);
+        assert_eq!(x.slice, [1, 2, 3]);
+        let y = x.clone();
+        assert_eq!(ThinArc::count(&x), 2);
+        let arc = ThinArc::into_arc(y);
+        assert_eq!(Arc::count(&arc), 2);
+        assert_eq!(arc.slice, [1, 2, 3]);
+        let y = ThinArc::from_arc(arc);
+        assert!(ThinArc::ptr_eq(&x, &y));
+        assert_eq!(x, y);
+        drop(y);
+        assert!(ThinArc::is_unique(&x));
+    }
+
+    #[test]
+    fn thin_arc_from_unsized_iter() {
+        let x = ThinArc::from_header_and_iter((), (0..10).filter(|x| x % 3 == 0));
+        assert_eq!(x.slice, [0, 3, 6, 9]);
+        let empty = ThinArc::<(), u8>::from_header_and_iter((), core::iter::empty());
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn thin_arc_erasable() {
+        let x = ThinArc::from_header_and_slice(7u32, &[0u16; 9]);
+        let erased = ErasablePtr::erase(x.clone());
+        let y: ThinArc<u32, u16> = unsafe { ErasablePtr::unerase(erased) };
+        assert!(ThinArc::ptr_eq(&x, &y));
+        assert_eq!(ThinArc::count(&x), 2);
+
+        let r = ArcRef::from_arc(ThinArc::into_arc(y));
+        assert!(ArcRef::is_owned(&r));
+        assert_eq!(r.header, 7);
+        assert_eq!(r.len(), 9);
+        let b = ArcRef::into_borrow(&r);
+        assert!(!ArcRef::is_owned(&b));
+        assert_eq!(ArcRef::count(&b), 2);
+        assert_eq!(&*b, &*x);
+    }
+}
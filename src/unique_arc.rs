@@ -1,16 +1,17 @@
-use alloc::{alloc::Layout, boxed::Box};
-use core::borrow::{Borrow, BorrowMut};
-use core::convert::TryFrom;
-use core::fmt::{self, Debug, Display, Formatter};
-use core::mem::{ManuallyDrop, MaybeUninit};
-use core::ops::{Deref, DerefMut};
-use core::ptr::{self, NonNull};
-use core::sync::atomic::AtomicUsize;
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use core::fmt::{self, Formatter};
+#[cfg(feature = "serde")]
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::pin::Pin;
+use core::ptr;
 
-use super::{Arc, ArcInner, ArcRef};
+use super::{Allocator, Arc, ArcCount, ArcInner, ArcRef, Global};
+use crate::shared_ptr::impl_unique;
 
-#[cfg(feature = "slice-dst")]
-use slice_dst::{AllocSliceDst, SliceDst, TryAllocSliceDst};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// An [`Arc`] that is known to be uniquely owned
 ///
@@ -36,263 +37,286 @@ use slice_dst::{AllocSliceDst, SliceDst, TryAllocSliceDst};
 /// let y = x.shareable(); // y is an Arc<T>
 /// ```
 #[repr(transparent)]
-pub struct ArcBox<T: ?Sized>(pub(crate) Arc<T>);
+pub struct ArcBox<T: ?Sized, A: Allocator = Global>(pub(crate) Arc<T, A>);
 
-impl<T> ArcBox<T> {
-    #[inline]
-    /// Construct a new [`ArcBox`]
-    pub fn new(data: T) -> Self {
-        ArcBox(Arc::new(data))
-    }
-
-    /// Construct an uninitialized [`ArcBox`]
-    #[inline]
-    pub fn new_uninit() -> ArcBox<MaybeUninit<T>> {
-        unsafe {
-            let layout = Layout::new::<ArcInner<MaybeUninit<T>>>();
-            let ptr = alloc::alloc::alloc(layout);
-            let mut p = NonNull::new(ptr)
-                .unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
-                .cast::<ArcInner<MaybeUninit<T>>>();
-            ptr::write(&mut p.as_mut().count, AtomicUsize::new(1));
-
-            ArcBox(Arc::from_raw_inner(p))
-        }
-    }
-
-    /// Gets the inner value of this [`ArcBox`]
-    pub fn into_inner(this: Self) -> T {
-        // Wrap the Arc in a `ManuallyDrop` so that its drop routine never runs
-        let this = ManuallyDrop::new(this.0);
-        debug_assert!(
-            Arc::is_unique(&this),
-            "attempted to call `.into_inner()` on a `ArcBox` with a non-zero ref count",
-        );
-
-        // Safety: We have exclusive access to the inner data and the
-        //         arc will not perform its drop routine since we've
-        //         wrapped it in a `ManuallyDrop`
-        unsafe { Box::from_raw(ArcInner::from_data(this.p.as_ptr())).data }
-    }
+impl_unique!(ArcBox, Arc, ArcInner, ArcRef, ArcCount; from_arc);
 
-    /// Convert to a shareable [`ArcRef<'static, T>`] once we're done mutating it
-    #[inline]
-    pub fn shareable_ref(self) -> ArcRef<'static, T> {
-        ArcRef::from_arc(self.0)
-    }
-}
-
-impl<T: ?Sized> ArcBox<T> {
-    /// Convert to a shareable [`Arc<T>`] once we're done mutating it
-    #[inline]
-    pub fn shareable(self) -> Arc<T> {
-        self.0
-    }
-
-    /// Creates a new [`ArcBox`] from the given [`Arc`].
+impl<T> ArcBox<T> {
+    /// Construct a new [`Pin<ArcBox<T>>`][`Pin`]. If `T` does not implement [`Unpin`], then
+    /// `data` will be pinned in memory and unable to be moved.
     ///
-    /// An unchecked alternative to [`Arc::try_unique`]
+    /// The contents can still be mutated through [`Pin::as_mut`], and the result can be frozen
+    /// into a [`Pin<Arc<T>>`][`Pin`] with [`From`].
     ///
-    /// # Safety
+    /// # Examples
     ///
-    /// The given [`Arc`] must have a reference count of exactly one
+    /// ```
+    /// use elysees::{Arc, ArcBox};
+    /// use core::pin::Pin;
     ///
-    pub(crate) unsafe fn from_arc(arc: Arc<T>) -> Self {
-        debug_assert_eq!(Arc::count(&arc), 1);
-        Self(arc)
+    /// let mut x = ArcBox::pin(5);
+    /// *x.as_mut() += 1;
+    /// let y: Pin<Arc<i32>> = x.into();
+    /// assert_eq!(*y, 6);
+    /// ```
+    #[inline]
+    pub fn pin(data: T) -> Pin<Self> {
+        ArcBox::into_pin(ArcBox::new(data))
     }
 }
 
-impl<T> ArcBox<MaybeUninit<T>> {
-    /// Convert to an initialized [`Arc`].
-    ///
-    /// # Safety
-    ///
-    /// This function is equivalent to [`MaybeUninit::assume_init`] and has the
-    /// same safety requirements. You are responsible for ensuring that the `T`
-    /// has actually been initialized before calling this method.
+impl<T: ?Sized, A: Allocator> ArcBox<T, A> {
+    /// Convert an [`ArcBox<T>`][`ArcBox`] into a [`Pin<ArcBox<T>>`][`Pin`]. If `T` does not
+    /// implement [`Unpin`], then `*this` will be pinned in memory and unable to be moved.
     #[inline]
-    pub unsafe fn assume_init(this: Self) -> ArcBox<T> {
-        ArcBox(Arc::from_raw_inner(this.0.into_raw_inner().cast()))
+    pub fn into_pin(this: Self) -> Pin<Self> {
+        // Safety: the data of an `ArcBox` is never moved while it is behind a `Pin`, since it is
+        //         only accessible through `Deref` and `DerefMut`
+        unsafe { Pin::new_unchecked(this) }
     }
 }
 
-impl<T: ?Sized> TryFrom<Arc<T>> for ArcBox<T> {
-    type Error = Arc<T>;
+/// Drops the initialized prefix of a slice if initialization fails
+struct PartialSlice<'a, T> {
+    slice: &'a mut [MaybeUninit<T>],
+    init: usize,
+}
 
-    fn try_from(arc: Arc<T>) -> Result<Self, Self::Error> {
-        Arc::try_unique(arc)
+impl<T> PartialSlice<'_, T> {
+    /// Move the initialized prefix into a new [`Vec`], leaving the slice uninitialized
+    fn into_vec(self) -> Vec<T> {
+        let mut v = Vec::with_capacity(self.init + 1);
+        // Safety: the first `init` elements of `slice` have been initialized, and are forgotten
+        // along with `self` once they have been moved into `v`
+        unsafe {
+            ptr::copy_nonoverlapping(self.slice.as_ptr() as *const T, v.as_mut_ptr(), self.init);
+            v.set_len(self.init);
+        }
+        mem::forget(self);
+        v
     }
 }
 
-impl<T: ?Sized> Deref for ArcBox<T> {
-    type Target = T;
-
-    #[inline]
-    fn deref(&self) -> &T {
-        #[allow(clippy::explicit_auto_deref)]
-        &*self.0
+impl<T> Drop for PartialSlice<'_, T> {
+    fn drop(&mut self) {
+        // Safety: the first `init` elements of `slice` have been initialized
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.slice.as_mut_ptr() as *mut T,
+                self.init,
+            ))
+        }
     }
 }
 
-impl<T: ?Sized> DerefMut for ArcBox<T> {
-    #[inline]
-    fn deref_mut(&mut self) -> &mut T {
-        // We know this to be uniquely owned
-        unsafe { self.0.p.as_mut() }
+/// Initialize `slice` with the elements of an iterator which reports that it yields exactly
+/// `slice.len()` of them
+///
+/// If the iterator panics, the elements written so far are dropped. Since a safe iterator may
+/// misreport its length, if it yields more or fewer elements than `slice.len()`, all of its
+/// elements are instead moved into a [`Vec`], which is returned as an error, and `slice` is left
+/// uninitialized.
+pub(crate) fn write_from_iter<T, I: Iterator<Item = T>>(
+    slice: &mut [MaybeUninit<T>],
+    mut iter: I,
+) -> Result<(), Vec<T>> {
+    let len = slice.len();
+    let mut partial = PartialSlice { slice, init: 0 };
+    while partial.init < len {
+        match iter.next() {
+            Some(item) => {
+                partial.slice[partial.init].write(item);
+                partial.init += 1;
+            }
+            None => return Err(partial.into_vec()),
+        }
+    }
+    match iter.next() {
+        None => {
+            mem::forget(partial);
+            Ok(())
+        }
+        Some(extra) => {
+            let mut v = partial.into_vec();
+            v.push(extra);
+            // Not `extend`, which would reserve space for the misreported length up front
+            iter.for_each(|item| v.push(item));
+            Err(v)
+        }
     }
 }
 
-impl<T: Clone> Clone for ArcBox<T> {
-    #[inline]
-    fn clone(&self) -> ArcBox<T> {
-        ArcBox(Arc::new(self.0.deref().clone()))
-    }
+/// The most memory allocated up front for a slice based on a length reported by an untrusted
+/// source, such as the size hint of an iterator or the length of a serialized sequence, as in
+/// `serde`
+///
+/// A misreported length must not be allowed to trigger an arbitrarily large allocation before any
+/// element has been produced.
+const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+
+/// The most elements of type `T` to allocate up front based on an untrusted length
+#[inline]
+fn max_prealloc<T>() -> usize {
+    MAX_PREALLOC_BYTES / mem::size_of::<T>().max(1)
 }
 
-impl<T: Default> Default for ArcBox<T> {
-    #[inline]
-    fn default() -> ArcBox<T> {
-        ArcBox::new(Default::default())
+/// Get the exact length reported by an iterator's [`Iterator::size_hint`], if it has one small
+/// enough to allocate up front, or otherwise collect its elements into a [`Vec`]
+///
+/// The length is only a hint, which the caller must still check against the elements actually
+/// yielded, e.g. with [`write_from_iter`].
+pub(crate) fn exact_len_or_collect<I: Iterator>(iter: I) -> Result<(I, usize), Vec<I::Item>> {
+    let (lower, upper) = iter.size_hint();
+    if upper == Some(lower) && lower <= max_prealloc::<I::Item>() {
+        return Ok((iter, lower));
     }
+    // Not `collect`, which would reserve space for the lower bound up front
+    let mut v = Vec::with_capacity(lower.min(max_prealloc::<I::Item>()));
+    iter.for_each(|item| v.push(item));
+    Err(v)
 }
 
-impl<T: Debug> Debug for ArcBox<T> {
-    #[inline]
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        Debug::fmt(&self.0, f)
+/// Initialize each element of `slice` with the result of calling `f` on its index, in order
+///
+/// If `f` fails or panics, the elements written so far are dropped.
+#[cfg(feature = "serde")]
+pub(crate) fn try_write_with<T, E>(
+    slice: &mut [MaybeUninit<T>],
+    mut f: impl FnMut(usize) -> Result<T, E>,
+) -> Result<(), E> {
+    let len = slice.len();
+    let mut partial = PartialSlice { slice, init: 0 };
+    while partial.init < len {
+        let item = f(partial.init)?;
+        partial.slice[partial.init].write(item);
+        partial.init += 1;
     }
+    mem::forget(partial);
+    Ok(())
 }
 
-impl<T: Display> Display for ArcBox<T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        Display::fmt(&self.0, f)
+impl<T: ?Sized, A: Allocator> From<ArcBox<T, A>> for Pin<ArcBox<T, A>> {
+    /// Pin an [`ArcBox`]. See [`ArcBox::into_pin`].
+    #[inline]
+    fn from(b: ArcBox<T, A>) -> Self {
+        ArcBox::into_pin(b)
     }
 }
 
-impl<T: ?Sized> Borrow<T> for ArcBox<T> {
+impl<T: ?Sized, A: Allocator> From<Pin<ArcBox<T, A>>> for Pin<Arc<T, A>> {
+    /// Freeze a pinned [`ArcBox`] into a pinned, shareable [`Arc`]
     #[inline]
-    fn borrow(&self) -> &T {
-        self
+    fn from(b: Pin<ArcBox<T, A>>) -> Self {
+        // Safety: converting an `ArcBox` into an `Arc` does not move its data
+        unsafe { Pin::new_unchecked(Pin::into_inner_unchecked(b).shareable()) }
     }
 }
 
-impl<T: ?Sized> AsRef<T> for ArcBox<T> {
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ArcBox<T> {
     #[inline]
-    fn as_ref(&self) -> &T {
-        self
+    fn deserialize<D>(deserializer: D) -> Result<ArcBox<T>, D::Error>
+    where
+        D: ::serde::de::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(ArcBox::new)
     }
 }
 
-impl<T: ?Sized> BorrowMut<T> for ArcBox<T> {
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for ArcBox<str> {
     #[inline]
-    fn borrow_mut(&mut self) -> &mut T {
-        self
+    fn deserialize<D>(deserializer: D) -> Result<ArcBox<str>, D::Error>
+    where
+        D: ::serde::de::Deserializer<'de>,
+    {
+        let arc = Arc::<str>::deserialize(deserializer)?;
+        // Safety: the string has just been deserialized into a new allocation
+        Ok(unsafe { ArcBox::from_arc(arc) })
     }
 }
 
-impl<T: ?Sized> AsMut<T> for ArcBox<T> {
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ArcBox<[T]> {
+    /// Deserialize a sequence into an [`ArcBox<[T]>`][`ArcBox`]
+    ///
+    /// If the format reports the length of the sequence, the elements are written directly into a
+    /// single allocation; otherwise, or if the reported length would take more than 1 MiB to
+    /// allocate up front, they are first collected into a [`Vec`].
     #[inline]
-    fn as_mut(&mut self) -> &mut T {
-        self
+    fn deserialize<D>(deserializer: D) -> Result<ArcBox<[T]>, D::Error>
+    where
+        D: ::serde::de::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(ArcBoxSliceVisitor(PhantomData))
     }
 }
 
-/// # Safety
-/// This leverages the correctness of Arc's CoerciblePtr impl. Additionally, we must ensure that
-/// this can not be used to violate the safety invariants of ArcBox, which require that we can not
-/// duplicate the Arc, such that replace_ptr returns a valid instance. This holds since it consumes
-/// a unique owner of the contained ArcInner.
-#[cfg(feature = "unsize")]
-unsafe impl<T, U: ?Sized> unsize::CoerciblePtr<U> for ArcBox<T> {
-    type Pointee = T;
-    type Output = ArcBox<U>;
-
-    fn as_sized_ptr(&mut self) -> *mut T {
-        // Dispatch to the contained field.
-        unsize::CoerciblePtr::<U>::as_sized_ptr(&mut self.0)
-    }
+/// A visitor which deserializes a sequence directly into an [`ArcBox<[T]>`][`ArcBox`]
+#[cfg(feature = "serde")]
+struct ArcBoxSliceVisitor<T>(PhantomData<T>);
 
-    unsafe fn replace_ptr(self, new: *mut U) -> ArcBox<U> {
-        // Dispatch to the contained field, work around conflict of destructuring and Drop.
-        let inner = ManuallyDrop::new(self);
-        ArcBox(ptr::read(&inner.0).replace_ptr(new))
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> ::serde::de::Visitor<'de> for ArcBoxSliceVisitor<T> {
+    type Value = ArcBox<[T]>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("a sequence")
     }
-}
 
-/// This implementation is based on that in the [documentation for `slice-dst`](https://docs.rs/slice-dst/latest/slice_dst/trait.AllocSliceDst.html).
-///
-/// # Safety
-///
-/// This function merely calls `try_new_slice` with an initializer statically guaranteed never to fail, and therefore is safe if and only if
-/// `try_new_slice` is.
-#[cfg(feature = "slice-dst")]
-unsafe impl<S: ?Sized + SliceDst> AllocSliceDst<S> for ArcBox<S> {
-    unsafe fn new_slice_dst<I>(len: usize, init: I) -> Self
+    fn visit_seq<S>(self, mut seq: S) -> Result<ArcBox<[T]>, S::Error>
     where
-        I: FnOnce(ptr::NonNull<S>),
+        S: ::serde::de::SeqAccess<'de>,
     {
-        #[allow(clippy::unit_arg)]
-        let init = |ptr| Ok::<(), core::convert::Infallible>(init(ptr));
-        #[allow(unreachable_patterns)]
-        match Self::try_new_slice_dst(len, init) {
-            Ok(a) => a,
-            Err(void) => match void {},
+        use ::serde::de::{Error, IgnoredAny};
+
+        let max_prealloc = max_prealloc::<T>();
+        let len = match seq.size_hint() {
+            Some(len) if len <= max_prealloc => len,
+            hint => {
+                let mut v = Vec::with_capacity(hint.map_or(0, |len| len.min(max_prealloc)));
+                while let Some(elem) = seq.next_element()? {
+                    v.push(elem);
+                }
+                return Ok(ArcBox::from(v));
+            }
+        };
+        let mut uninit = Arc::<[MaybeUninit<T>]>::try_new_uninit_slice(len).map_err(|_| {
+            S::Error::custom(format_args!("failed to allocate a slice of length {}", len))
+        })?;
+        // Safety: we have just allocated `uninit`, so it is uniquely owned. If deserializing an
+        // element fails, the elements written so far are dropped, and then `uninit` is dropped,
+        // freeing the allocation.
+        try_write_with(unsafe { uninit.p.as_mut() }, |i| {
+            seq.next_element()?
+                .ok_or_else(|| S::Error::invalid_length(i, &self))
+        })?;
+        // Safety: all `len` elements have been initialized, and `uninit` is uniquely owned
+        let slice = unsafe { ArcBox(uninit.assume_init()) };
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(S::Error::custom(format_args!(
+                "sequence has more than its reported {} elements",
+                len
+            )));
         }
+        Ok(slice)
     }
 }
 
-#[cfg(feature = "slice-dst")]
-/// # Safety
-///
-///
-unsafe impl<S: ?Sized + SliceDst> TryAllocSliceDst<S> for ArcBox<S> {
-    unsafe fn try_new_slice_dst<I, E>(len: usize, init: I) -> Result<Self, E>
+#[cfg(feature = "serde")]
+impl<T: ?Sized + Serialize, A: Allocator> Serialize for ArcBox<T, A> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        I: FnOnce(ptr::NonNull<S>) -> Result<(), E>,
+        S: ::serde::ser::Serializer,
     {
-        // Get the offset for the `S` field in an `ArcInner<S>`:
-        let s_layout = S::layout_for(len);
-        let (unpadded_layout, offset) = Layout::new::<AtomicUsize>().extend(s_layout).unwrap();
-
-        // Get an allocation for an `ArcInner<S>`
-        let ptr: NonNull<ArcInner<S>> = slice_dst::alloc_slice_dst(len);
-
-        // Safety: Since this pointer is to the beginning of the allocation, we can initialize the counter through it...
-        ptr.cast::<AtomicUsize>()
-            .as_ptr()
-            .write(AtomicUsize::new(1));
-
-        // Safety: the offset `offset` is in bounds of the allocation `ptr`
-        let s_data_ptr = ptr.cast::<u8>().as_ptr().add(offset) as *mut ();
-
-        // Safety: we can construct `NonNull<[()]>` with length `len` and ptr `ptr`
-        let s_slice_ptr: NonNull<[()]> =
-            NonNull::new_unchecked(core::ptr::slice_from_raw_parts_mut(s_data_ptr, len));
-        let s_ptr = S::retype(s_slice_ptr);
-
-        match init(s_ptr) {
-            Ok(()) => {
-                // Yay! Everything was initialized! Do a few checks for good measure.
-                debug_assert_eq!(Layout::for_value(&*s_ptr.as_ptr()), s_layout);
-                let layout = unpadded_layout.pad_to_align();
-                debug_assert_eq!(Layout::for_value(&*ptr.as_ptr()), layout);
-            }
-            Err(err) => {
-                // Deallocate ptr and return an error
-                let layout = unpadded_layout.pad_to_align();
-                alloc::alloc::dealloc(ptr.as_ptr() as *mut u8, layout);
-                return Err(err);
-            }
-        }
-
-        Ok(ArcBox(Arc::from_raw(s_ptr.as_ptr())))
+        (**self).serialize(serializer)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Arc, ArcBox};
+    use crate::{Arc, ArcBorrow, ArcBox};
     use core::convert::TryFrom;
 
     #[test]
@@ -301,6 +325,39 @@ mod tests {
         assert_eq!(ArcBox::into_inner(unique), 10);
     }
 
+    #[test]
+    fn slice_conversions() {
+        let v = alloc::vec![1, 2, 3];
+        let x: ArcBox<[i32]> = ArcBox::from(&v[..]);
+        let y: ArcBox<[i32]> = ArcBox::from(v.clone().into_boxed_slice());
+        let z: ArcBox<[i32]> = v.iter().copied().collect();
+        let w: ArcBox<[i32]> = (1..10).filter(|x| *x <= 3).collect();
+        assert_eq!(*x, [1, 2, 3]);
+        assert_eq!(*x, *y);
+        assert_eq!(*x, *z);
+        assert_eq!(*x, *w);
+        let u: Arc<[i32]> = ArcBox::from(v).shareable();
+        assert!(Arc::is_unique(&u));
+        assert_eq!(*u, [1, 2, 3]);
+        let empty: Arc<[i32]> = core::iter::empty().collect();
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn into_box() {
+        use alloc::boxed::Box;
+        use alloc::string::String;
+
+        let x: Box<u64> = ArcBox::into_box(ArcBox::new(5));
+        assert_eq!(*x, 5);
+        let y: Box<[String]> = Box::from(ArcBox::from(alloc::vec![String::from("a")]));
+        assert_eq!(*y, [String::from("a")]);
+        let z: Box<str> = Box::from(ArcBox::from("hello"));
+        assert_eq!(&*z, "hello");
+        let w: ArcBox<str> = ArcBox::from(z);
+        assert_eq!(&*w, "hello");
+    }
+
     #[test]
     fn try_from_arc() {
         let x = Arc::new(10_000);
@@ -309,4 +366,42 @@ mod tests {
         assert!(ArcBox::try_from(x).is_err());
         assert_eq!(ArcBox::into_inner(ArcBox::try_from(y).unwrap()), 10_000,);
     }
+
+    #[test]
+    fn pinned() {
+        use core::marker::PhantomPinned;
+        use core::pin::Pin;
+
+        struct Pinned(u32, PhantomPinned);
+
+        let mut x = ArcBox::pin(Pinned(1, PhantomPinned));
+        unsafe { x.as_mut().get_unchecked_mut().0 += 1 };
+        let address = &*x as *const Pinned;
+        let mut y: Pin<Arc<Pinned>> = x.into();
+        assert_eq!(&*y as *const Pinned, address);
+        assert!(Arc::get_pin_mut(&mut y).is_some());
+        let z = ArcBorrow::clone_pin(Arc::borrow_pin(&y));
+        assert!(Arc::get_pin_mut(&mut y).is_none());
+        assert_eq!(ArcBorrow::get_pin(Arc::borrow_pin(&z)).0, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deserialize_slice() {
+        use serde::de::value::{Error, SeqDeserializer};
+        use serde::Deserialize;
+
+        let exact: SeqDeserializer<_, Error> = SeqDeserializer::new(0..4u32);
+        let x = ArcBox::<[u32]>::deserialize(exact).unwrap();
+        assert_eq!(*x, [0, 1, 2, 3]);
+
+        let unknown: SeqDeserializer<_, Error> =
+            SeqDeserializer::new((0..6u32).filter(|x| x % 2 == 0));
+        let y = Arc::<[u32]>::deserialize(unknown).unwrap();
+        assert_eq!(*y, [0, 2, 4]);
+
+        let s: ArcBox<str> =
+            ArcBox::deserialize(serde::de::value::StrDeserializer::<Error>::new("hello")).unwrap();
+        assert_eq!(&*s, "hello");
+    }
 }
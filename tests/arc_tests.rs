@@ -16,7 +16,7 @@ extern "Rust" {
 
 #[test]
 fn basic_arc_usage() {
-    #![allow(clippy::many_single_char_names)]
+    #![allow(clippy::many_single_char_names, clippy::needless_borrow)]
     let mut x = Arc::new(7);
     assert!(Arc::is_unique(&x));
     assert_eq!(Arc::get_mut(&mut x), Some(&mut 7));
@@ -128,7 +128,7 @@ fn basic_arc_usage() {
 
 #[test]
 fn basic_arc_ref_usage() {
-    #![allow(clippy::many_single_char_names)]
+    #![allow(clippy::many_single_char_names, clippy::needless_borrow)]
     let mut x = ArcRef::new(7);
     assert!(ArcRef::is_unique(&x));
     assert_eq!(ArcRef::get_mut(&mut x), Some(&mut 7));
@@ -377,3 +377,189 @@ fn arc_ref_hash() {
     assert!(map.insert(ArcRef::new(9)));
     assert!(!map.insert(ArcRef::new(7)));
 }
+
+/// Counts the number of times a value has been dropped
+#[derive(Clone)]
+struct DropCounter<'a>(&'a std::cell::Cell<usize>);
+
+impl Drop for DropCounter<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1)
+    }
+}
+
+#[test]
+fn arc_drops_contents() {
+    let drops = std::cell::Cell::new(0);
+    let x = Arc::new(DropCounter(&drops));
+    let y = x.clone();
+    std::mem::drop(x);
+    assert_eq!(drops.get(), 0);
+    std::mem::drop(y);
+    assert_eq!(drops.get(), 1);
+
+    let v = vec![
+        DropCounter(&drops),
+        DropCounter(&drops),
+        DropCounter(&drops),
+    ];
+    let s: Arc<[DropCounter]> = Arc::from(v);
+    assert_eq!(drops.get(), 1);
+    std::mem::drop(s);
+    assert_eq!(drops.get(), 4);
+
+    let b: Box<[DropCounter]> = vec![DropCounter(&drops), DropCounter(&drops)].into_boxed_slice();
+    let s: Arc<[DropCounter]> = Arc::from(b);
+    assert_eq!(drops.get(), 4);
+    let b: Box<[DropCounter]> = Box::from(Arc::try_unique(s).ok().unwrap());
+    assert_eq!(drops.get(), 4);
+    std::mem::drop(b);
+    assert_eq!(drops.get(), 6);
+}
+
+#[test]
+fn arc_slice_from_iter_panic() {
+    let drops = std::cell::Cell::new(0);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        (0..5)
+            .map(|i| {
+                if i == 3 {
+                    panic!("iterator panicked")
+                }
+                DropCounter(&drops)
+            })
+            .collect::<Arc<[DropCounter]>>()
+    }));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 3);
+}
+
+/// An iterator which claims to have exactly `claimed` elements, whatever `iter` actually yields
+struct Liar<I> {
+    iter: I,
+    claimed: usize,
+}
+
+impl<I: Iterator> Iterator for Liar<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.claimed, Some(self.claimed))
+    }
+}
+
+/// `len` boxed integers, which claim to be `claimed` of them
+fn lying_boxes(len: usize, claimed: usize) -> Liar<impl Iterator<Item = Box<usize>>> {
+    Liar {
+        iter: (0..len).map(Box::new),
+        claimed,
+    }
+}
+
+#[test]
+fn arc_slice_from_lying_iter() {
+    let expected = |len| (0..len).map(Box::new).collect::<Vec<_>>();
+
+    let short: Arc<[Box<usize>]> = lying_boxes(3, 5).collect();
+    assert_eq!(*short, *expected(3));
+    let long: Arc<[Box<usize>]> = lying_boxes(5, 3).collect();
+    assert_eq!(*long, *expected(5));
+
+    let short: Rc<[Box<usize>]> = lying_boxes(3, 5).collect();
+    assert_eq!(*short, *expected(3));
+    let long: Rc<[Box<usize>]> = lying_boxes(5, 3).collect();
+    assert_eq!(*long, *expected(5));
+
+    let short = ThinArc::from_header_and_iter("short", lying_boxes(3, 5));
+    assert_eq!((short.header, &short.slice), ("short", &*expected(3)));
+    let long = ThinArc::from_header_and_iter("long", lying_boxes(5, 3));
+    assert_eq!((long.header, &long.slice), ("long", &*expected(5)));
+
+    // A huge reported length is not allocated up front, so only the actual elements are collected
+    let huge: Arc<[Box<usize>]> = lying_boxes(3, usize::MAX / 2).collect();
+    assert_eq!(*huge, *expected(3));
+    let huge: Rc<[Box<usize>]> = lying_boxes(3, usize::MAX / 2).collect();
+    assert_eq!(*huge, *expected(3));
+    let huge = ThinArc::from_header_and_iter("huge", lying_boxes(3, usize::MAX / 2));
+    assert_eq!((huge.header, &huge.slice), ("huge", &*expected(3)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_lying_seq() {
+    use serde::de::value::{Error, SeqDeserializer};
+    use serde::Deserialize;
+
+    let lying_strings = |claimed| -> SeqDeserializer<_, Error> {
+        SeqDeserializer::new(Liar {
+            iter: ["a", "b"].into_iter(),
+            claimed,
+        })
+    };
+    assert!(ArcBox::<[Arc<str>]>::deserialize(lying_strings(3)).is_err());
+    assert!(ArcBox::<[Arc<str>]>::deserialize(lying_strings(1)).is_err());
+    // A huge reported length is not allocated up front, so only the actual elements are read
+    let huge = Arc::<[Arc<str>]>::deserialize(lying_strings(usize::MAX / 2)).unwrap();
+    assert_eq!(huge.iter().map(|s| &**s).collect::<Vec<_>>(), ["a", "b"]);
+}
+
+/// An allocator which keeps track of the number of live allocations
+#[derive(Clone, Copy)]
+struct CountingAlloc<'a>(&'a std::sync::atomic::AtomicUsize);
+
+unsafe impl Allocator for CountingAlloc<'_> {
+    fn allocate(&self, layout: std::alloc::Layout) -> Result<std::ptr::NonNull<[u8]>, AllocError> {
+        self.0.fetch_add(1, Relaxed);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        self.0.fetch_sub(1, Relaxed);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+#[test]
+fn arc_custom_allocator() {
+    let live = std::sync::atomic::AtomicUsize::new(0);
+    let alloc = CountingAlloc(&live);
+    assert_eq!(
+        std::mem::size_of::<Arc<u8, CountingAlloc>>(),
+        std::mem::size_of::<usize>()
+    );
+
+    let mut x = Arc::new_in(5u8, alloc);
+    assert_eq!(live.load(Relaxed), 1);
+    let y = ArcBorrow::clone_arc(Arc::borrow_arc(&x));
+    assert!(std::ptr::eq(Arc::allocator(&y).0, &live));
+    *Arc::make_mut(&mut x) += 1;
+    assert_eq!(live.load(Relaxed), 2);
+    assert_eq!((*x, *y), (6, 5));
+    assert_eq!(ArcBox::into_inner(Arc::unique(x)), 6);
+    assert_eq!(live.load(Relaxed), 1);
+
+    let r = ArcRef::from_arc(y);
+    let s = ArcRef::into_borrow(&r);
+    assert!(ArcRef::is_owned(&r) && !ArcRef::is_owned(&s));
+    assert_eq!(ArcRef::count(&s), 1);
+    drop(s);
+    drop(r);
+    assert_eq!(live.load(Relaxed), 0);
+
+    let mut v = Arc::<[std::mem::MaybeUninit<String>], _>::new_uninit_slice_in(3, alloc);
+    for (i, s) in Arc::get_mut(&mut v).unwrap().iter_mut().enumerate() {
+        s.write(i.to_string());
+    }
+    let v = unsafe { v.assume_init() };
+    assert_eq!(*v, ["0", "1", "2"]);
+    let mut b = ArcBox::new_uninit_in(alloc);
+    b.write(v);
+    let b = unsafe { ArcBox::assume_init(b) }.shareable();
+    assert_eq!(live.load(Relaxed), 2);
+    drop(b);
+    assert_eq!(live.load(Relaxed), 0);
+}